@@ -0,0 +1,204 @@
+//! Block-based ADPCM decoders. Each block starts with a header holding the initial decoder
+//! state per channel, followed by the interleaved 4-bit codes.
+
+use crate::error::{OndaError, Result};
+
+/// Size in bytes of the per-channel header of IMA ADPCM blocks.
+pub(crate) const IMA_HEADER_SIZE: usize = 4;
+
+/// Size in bytes of the per-channel header of Microsoft ADPCM blocks.
+pub(crate) const MS_HEADER_SIZE: usize = 7;
+
+/// Predictor coefficients used by almost every Microsoft ADPCM file, for those without a
+/// table in their fmt chunk.
+pub(crate) const MS_STANDARD_COEFFICIENTS: [(i16, i16); 7] = [
+    (256, 0),
+    (512, -256),
+    (0, 0),
+    (192, 64),
+    (240, 0),
+    (460, -208),
+    (392, -232),
+];
+
+const MS_ADAPTATION_TABLE: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230,
+];
+
+/// Largest step size of Microsoft ADPCM, so the adaptation can't overflow.
+const MS_MAX_DELTA: i32 = i32::MAX / 768;
+
+const IMA_INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const IMA_STEP_TABLE: [i16; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// Decoder state of a single IMA ADPCM channel.
+struct ImaChannel {
+    predictor: i16,
+    step_index: usize,
+}
+
+impl ImaChannel {
+    fn decode(&mut self, code: u8) -> i16 {
+        let step = IMA_STEP_TABLE[self.step_index] as i32;
+
+        let mut diff = step >> 3;
+        if code & 1 != 0 {
+            diff += step >> 2;
+        }
+        if code & 2 != 0 {
+            diff += step >> 1;
+        }
+        if code & 4 != 0 {
+            diff += step;
+        }
+        if code & 8 != 0 {
+            diff = -diff;
+        }
+
+        self.predictor =
+            (self.predictor as i32 + diff).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.step_index =
+            (self.step_index as i32 + IMA_INDEX_TABLE[code as usize] as i32).clamp(0, 88) as usize;
+
+        self.predictor
+    }
+}
+
+/// Decodes IMA ADPCM data into 16-bit channels. A trailing partial block is decoded as far
+/// as it goes.
+pub(crate) fn decode_ima(data: &[u8], num_channels: u16, block_align: u16) -> Vec<Vec<i16>> {
+    let num_channels = num_channels as usize;
+    let header_size = IMA_HEADER_SIZE * num_channels;
+
+    let mut channels = vec![vec![]; num_channels];
+
+    for block in data.chunks(block_align as usize) {
+        if block.len() < header_size {
+            break;
+        }
+
+        let (header, codes) = block.split_at(header_size);
+
+        let mut states: Vec<ImaChannel> = header
+            .chunks_exact(IMA_HEADER_SIZE)
+            .zip(&mut channels)
+            .map(|(state, channel)| {
+                let predictor = i16::from_le_bytes([state[0], state[1]]);
+                channel.push(predictor);
+
+                ImaChannel {
+                    predictor,
+                    step_index: (state[2] as usize).min(88),
+                }
+            })
+            .collect();
+
+        // each channel takes turns with 4 bytes holding 8 codes, low nibble first
+        for group in codes.chunks_exact(header_size) {
+            for ((bytes, state), channel) in
+                group.chunks_exact(4).zip(&mut states).zip(&mut channels)
+            {
+                for byte in bytes {
+                    channel.push(state.decode(byte & 0x0F));
+                    channel.push(state.decode(byte >> 4));
+                }
+            }
+        }
+    }
+
+    channels
+}
+
+/// Decoder state of a single Microsoft ADPCM channel.
+struct MsChannel {
+    coefficients: (i16, i16),
+    delta: i32,
+    sample1: i16,
+    sample2: i16,
+}
+
+impl MsChannel {
+    fn decode(&mut self, code: u8) -> i16 {
+        let (coefficient1, coefficient2) = self.coefficients;
+        let prediction = (self.sample1 as i32 * coefficient1 as i32
+            + self.sample2 as i32 * coefficient2 as i32)
+            >> 8;
+
+        // codes are signed 4-bit values
+        let signed_code = ((code << 4) as i8 >> 4) as i32;
+        let sample =
+            (prediction + signed_code * self.delta).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta =
+            ((MS_ADAPTATION_TABLE[code as usize] * self.delta) >> 8).clamp(16, MS_MAX_DELTA);
+
+        sample
+    }
+}
+
+/// Decodes Microsoft ADPCM data into 16-bit channels, choosing each block's predictors from
+/// `coefficients`. A trailing partial block is decoded as far as it goes.
+pub(crate) fn decode_ms(
+    data: &[u8],
+    num_channels: u16,
+    block_align: u16,
+    coefficients: &[(i16, i16)],
+) -> Result<Vec<Vec<i16>>> {
+    let num_channels = num_channels as usize;
+    let header_size = MS_HEADER_SIZE * num_channels;
+
+    let mut channels = vec![vec![]; num_channels];
+
+    for block in data.chunks(block_align as usize) {
+        if block.len() < header_size {
+            break;
+        }
+
+        // header fields are grouped by field, with one entry per channel each
+        let (header, codes) = block.split_at(header_size);
+        let field = |start: usize, c: usize| {
+            let i = start + 2 * c;
+            i16::from_le_bytes([header[i], header[i + 1]])
+        };
+
+        let mut states = Vec::with_capacity(num_channels);
+        for (c, &index) in header[..num_channels].iter().enumerate() {
+            let Some(&predictor) = coefficients.get(index as usize) else {
+                return Err(OndaError::Malformed(
+                    "ADPCM block uses an unknown predictor",
+                ));
+            };
+
+            states.push(MsChannel {
+                coefficients: predictor,
+                delta: field(num_channels, c) as i32,
+                sample1: field(3 * num_channels, c),
+                sample2: field(5 * num_channels, c),
+            });
+        }
+
+        // the two initial samples come out oldest first
+        for (state, channel) in states.iter().zip(&mut channels) {
+            channel.push(state.sample2);
+            channel.push(state.sample1);
+        }
+
+        // codes alternate between channels, high nibble first
+        let nibbles = codes.iter().flat_map(|byte| [byte >> 4, byte & 0x0F]);
+        for (i, code) in nibbles.enumerate() {
+            let c = i % num_channels;
+            channels[c].push(states[c].decode(code));
+        }
+    }
+
+    Ok(channels)
+}
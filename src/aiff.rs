@@ -0,0 +1,242 @@
+//! AIFF and AIFF-C support. Samples are big-endian, and are converted to the layout of WAV
+//! data chunks so they share the WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{
+    frame_size, parse_id, parse_slice, parse_u16, parse_u32, to_wav_layout, Endianness,
+    SampleFormat, Spec,
+};
+use crate::write::{validate_channels, write_file};
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+/// Creates a vector of 16-bit AIFF bytes from audio data.
+pub fn create_aiff_bytes<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    const BITS_PER_SAMPLE: u16 = 16;
+
+    let audiodata = audiodata.as_ref();
+    let num_frames = validate_channels(audiodata)?;
+    let num_channels = audiodata.len() as u16;
+
+    let audiodata_size = num_frames * num_channels as usize * BITS_PER_SAMPLE as usize / 8;
+    if audiodata_size > (u32::MAX - 64) as usize {
+        return Err(OndaError::TooLarge("audio data for AIFF"));
+    }
+
+    let mut buf = Vec::with_capacity(audiodata_size + 64);
+
+    write!(buf, "FORM")?;
+    // the chunk size is patched once all chunks are written
+    buf.extend_from_slice(&0u32.to_be_bytes());
+    write!(buf, "AIFF")?;
+
+    write!(buf, "COMM")?;
+    buf.extend_from_slice(&18u32.to_be_bytes());
+    buf.extend_from_slice(&num_channels.to_be_bytes());
+    buf.extend_from_slice(&(num_frames as u32).to_be_bytes());
+    buf.extend_from_slice(&BITS_PER_SAMPLE.to_be_bytes());
+    buf.extend_from_slice(&encode_extended(samplerate));
+
+    write!(buf, "SSND")?;
+    buf.extend_from_slice(&(8 + audiodata_size as u32).to_be_bytes());
+    // no data offset or block size
+    buf.extend_from_slice(&[0; 8]);
+
+    for f in 0..num_frames {
+        for channel in audiodata {
+            buf.extend_from_slice(&channel.as_ref()[f].to_be_bytes());
+        }
+    }
+
+    let chunksize = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&chunksize.to_be_bytes());
+
+    Ok(buf)
+}
+
+/// Writes audio data into a 16-bit AIFF file.
+pub fn write_aiff<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_aiff_bytes(audiodata, samplerate)?, path)
+}
+
+/// Parses an AIFF or AIFF-C file and returns its spec along with the sample bytes of the
+/// SSND chunk, in the layout of a WAV data chunk.
+pub(crate) fn parse_aiff(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    let mut offset = 0;
+
+    if parse_id(buf, &mut offset)? != b"FORM" {
+        return Err(OndaError::NotFormat("IFF"));
+    }
+
+    // ignore chunk size
+    offset += 4;
+
+    let aifc = match parse_id(buf, &mut offset)? {
+        b"AIFF" => false,
+        b"AIFC" => true,
+        _ => return Err(OndaError::NotFormat("AIFF")),
+    };
+
+    let mut comm = None;
+    let mut ssnd = None;
+
+    while offset + 8 <= buf.len() {
+        let id = parse_id(buf, &mut offset)?;
+        let size = parse_u32(buf, &mut offset, Endianness::Big)? as usize;
+        let payload = parse_slice(buf, &mut offset, size)?;
+
+        match id {
+            b"COMM" => comm = Some(parse_comm_chunk(payload, aifc)?),
+            b"SSND" => ssnd = Some(parse_ssnd_chunk(payload)?),
+            _ => {}
+        }
+
+        // chunks are padded to an even size
+        offset += size % 2;
+    }
+
+    let Some((spec, num_frames, byte_order)) = comm else {
+        return Err(OndaError::MissingChunk { id: *b"COMM" });
+    };
+    let Some(data) = ssnd else {
+        return Err(OndaError::MissingChunk { id: *b"SSND" });
+    };
+
+    let size = (num_frames as usize * spec.block_align as usize).min(data.len());
+    let data = to_wav_layout(&data[..size], &spec, byte_order, true)?;
+
+    Ok((spec, data))
+}
+
+/// Parses the COMM chunk into a spec, and returns it along with the number of frames and
+/// the byte order of the samples.
+fn parse_comm_chunk(payload: &[u8], aifc: bool) -> Result<(Spec, u32, Endianness)> {
+    if payload.len() < 18 || (aifc && payload.len() < 22) {
+        return Err(OndaError::BadChunkSize { id: *b"COMM" });
+    }
+
+    let mut offset = 0;
+    let num_channels = parse_u16(payload, &mut offset, Endianness::Big)?;
+    let num_frames = parse_u32(payload, &mut offset, Endianness::Big)?;
+    let sample_size = parse_u16(payload, &mut offset, Endianness::Big)?;
+    let samplerate = parse_extended(parse_slice(payload, &mut offset, 10)?);
+
+    let compression = if aifc {
+        parse_id(payload, &mut offset)?
+    } else {
+        b"NONE"
+    };
+
+    let (format, byte_order) = match compression {
+        b"NONE" | b"twos" => (SampleFormat::Int, Endianness::Big),
+        b"sowt" => (SampleFormat::Int, Endianness::Little),
+        b"fl32" | b"FL32" | b"fl64" | b"FL64" => (SampleFormat::Float, Endianness::Big),
+        b"ulaw" | b"ULAW" => (SampleFormat::MuLaw, Endianness::Big),
+        b"alaw" | b"ALAW" => (SampleFormat::ALaw, Endianness::Big),
+        _ => {
+            return Err(OndaError::UnsupportedCodec {
+                tag: u32::from_be_bytes(*compression),
+            })
+        }
+    };
+
+    // samples are stored left-justified in whole bytes
+    let bits_per_sample = match (format, compression) {
+        (SampleFormat::Float, b"fl64" | b"FL64") => 64,
+        (SampleFormat::Float, _) => 32,
+        (SampleFormat::ALaw | SampleFormat::MuLaw, _) => 8,
+        // out of range sizes are left as 0, which is rejected below
+        _ => sample_size.checked_next_multiple_of(8).unwrap_or(0),
+    };
+    let valid_bits_per_sample = match format {
+        SampleFormat::Int => sample_size,
+        _ => bits_per_sample,
+    };
+
+    if num_channels == 0 {
+        return Err(OndaError::NoChannels);
+    }
+
+    if format == SampleFormat::Int && !matches!(bits_per_sample, 8 | 16 | 24 | 32) {
+        return Err(OndaError::UnsupportedBitDepth(sample_size as u32));
+    }
+
+    let block_align = frame_size(num_channels, bits_per_sample)?;
+
+    let spec = Spec {
+        format,
+        num_channels,
+        samplerate,
+        block_align,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    Ok((spec, num_frames, byte_order))
+}
+
+/// Returns the sample bytes of an SSND chunk.
+fn parse_ssnd_chunk(payload: &[u8]) -> Result<&[u8]> {
+    if payload.len() < 8 {
+        return Err(OndaError::BadChunkSize { id: *b"SSND" });
+    }
+
+    // samples start after the offset field, the block size and then the offset itself
+    let mut offset = 0;
+    let data_offset = parse_u32(payload, &mut offset, Endianness::Big)? as usize;
+
+    match payload.get(8 + data_offset..) {
+        Some(data) => Ok(data),
+        None => Err(OndaError::BadChunkSize { id: *b"SSND" }),
+    }
+}
+
+/// Converts a sample rate to an 80-bit IEEE 754 extended precision number.
+fn encode_extended(samplerate: u32) -> [u8; 10] {
+    let mut bytes = [0; 10];
+    if samplerate == 0 {
+        return bytes;
+    }
+
+    // normalize the mantissa so its explicit integer bit is the highest bit
+    let shift = (samplerate as u64).leading_zeros();
+    let exponent = 16383 + 63 - shift as u16;
+    let mantissa = (samplerate as u64) << shift;
+
+    bytes[..2].copy_from_slice(&exponent.to_be_bytes());
+    bytes[2..].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+/// Converts an 80-bit IEEE 754 extended precision number, as used for AIFF sample rates.
+fn parse_extended(bytes: &[u8]) -> u32 {
+    let exponent = u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7FFF;
+
+    let mut mantissa_bytes = [0; 8];
+    mantissa_bytes.copy_from_slice(&bytes[2..10]);
+    let mantissa = u64::from_be_bytes(mantissa_bytes);
+
+    // the mantissa has an explicit integer bit, followed by 63 fraction bits
+    let shift = exponent as i32 - 16383 - 63;
+    let value = if shift >= 0 {
+        (mantissa as f64) * 2f64.powi(shift)
+    } else {
+        (mantissa as f64) / 2f64.powi(-shift)
+    };
+
+    value.round() as u32
+}
@@ -0,0 +1,64 @@
+//! Conversion of audio data to Arrow record batches, for loading into analytics engines.
+
+use crate::error::Result;
+use crate::WavData;
+use arrow_array::types::{Float32Type, Float64Type, Int16Type, Int32Type};
+use arrow_array::{ArrayRef, ArrowPrimitiveType, PrimitiveArray, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Sample types that can be stored in Arrow columns.
+pub trait ArrowSample: Copy {
+    /// Arrow type of a column of samples.
+    type ArrowType: ArrowPrimitiveType<Native = Self>;
+}
+
+macro_rules! impl_arrow_sample {
+    ($($sample:ty => $arrow_type:ty),*) => {
+        $(
+            impl ArrowSample for $sample {
+                type ArrowType = $arrow_type;
+            }
+        )*
+    };
+}
+
+impl_arrow_sample!(i16 => Int16Type, i32 => Int32Type, f32 => Float32Type, f64 => Float64Type);
+
+impl<S: ArrowSample> WavData<S> {
+    /// Returns the samples as a record batch with a `frame` column of frame indices, and
+    /// a `channel_0`, `channel_1`, ... column per channel. Channels longer than the shortest
+    /// one are truncated, as by `frames`. The sample rate and bit depth are stored in the
+    /// schema metadata as `samplerate` and `bits_per_sample`.
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        let num_frames = self.audiodata.iter().map(Vec::len).min().unwrap_or(0);
+        let sample_type = S::ArrowType::DATA_TYPE;
+
+        let mut fields = vec![Field::new("frame", DataType::UInt64, false)];
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(UInt64Array::from_iter_values(
+            0..num_frames as u64,
+        ))];
+        for (i, channel) in self.audiodata.iter().enumerate() {
+            fields.push(Field::new(
+                format!("channel_{i}"),
+                sample_type.clone(),
+                false,
+            ));
+            columns.push(Arc::new(PrimitiveArray::<S::ArrowType>::from_iter_values(
+                channel[..num_frames].iter().copied(),
+            )));
+        }
+
+        let metadata = HashMap::from([
+            ("samplerate".to_string(), self.samplerate.to_string()),
+            (
+                "bits_per_sample".to_string(),
+                self.bits_per_sample.to_string(),
+            ),
+        ]);
+        let schema = Schema::new_with_metadata(fields, metadata);
+
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+}
@@ -0,0 +1,167 @@
+//! Asynchronous reading and writing through tokio, for use within an async runtime.
+
+use crate::error::Result;
+use crate::metadata::WavMetadata;
+use crate::read::{
+    decode_samples_f32, decode_samples_f64, decode_samples_i16, decode_samples_i32, ParseOptions,
+    ParseWarning, Spec,
+};
+use crate::{create_bytes, parse_bytes, WavData, WavReader};
+use std::io::{self, Cursor};
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Reads an 8 or 16-bit file from the provided path without blocking, like
+/// [`read`](crate::read).
+pub async fn read_async(path: impl AsRef<Path>) -> Result<WavData> {
+    parse_bytes(tokio::fs::read(path).await?)
+}
+
+/// Writes audio data into a 16-bit WAV file without blocking, like [`write`](crate::write).
+pub async fn write_async<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    let bytes = create_bytes(audiodata, samplerate)?;
+    tokio::fs::write(path, bytes).await?;
+
+    Ok(())
+}
+
+/// Reader of RIFF WAV files from an async stream, like [`WavReader`]. Only the metadata
+/// chunks before the data chunk are read.
+pub struct AsyncWavReader<R> {
+    reader: R,
+    /// Parsed header, reading from nothing as samples come from `reader`.
+    inner: WavReader<io::Empty>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncWavReader<R> {
+    /// Reads the header of a WAV file, up to the start of its data chunk.
+    pub async fn new(reader: R) -> Result<Self> {
+        Self::with_options(reader, ParseOptions::default()).await
+    }
+
+    /// Reads the header of a WAV file like [`new`](Self::new), with the given options.
+    pub async fn with_options(mut reader: R, options: ParseOptions) -> Result<Self> {
+        let header = read_header(&mut reader).await?;
+        let inner = WavReader::with_options(Cursor::new(header), options)?.with_reader(io::empty());
+
+        Ok(Self { reader, inner })
+    }
+
+    pub fn num_channels(&self) -> u16 {
+        self.inner.num_channels()
+    }
+
+    pub fn samplerate(&self) -> u32 {
+        self.inner.samplerate()
+    }
+
+    /// Bit depth of the decoded samples, which is 16 for companded and compressed files.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.inner.bits_per_sample()
+    }
+
+    /// Metadata of the chunks before the data chunk.
+    pub fn metadata(&self) -> &WavMetadata {
+        self.inner.metadata()
+    }
+
+    /// Inconsistencies of the fmt chunk tolerated by lenient parsing.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.inner.warnings()
+    }
+
+    /// Reads up to `num_frames` frames of an 8 or 16-bit file, like
+    /// [`WavReader::read_frames`].
+    pub async fn read_frames(&mut self, num_frames: usize) -> Result<Vec<Vec<i16>>> {
+        self.read_samples(num_frames, decode_samples_i16).await
+    }
+
+    /// Reads up to `num_frames` frames of an 8, 16, 24 or 32-bit file into 32-bit samples.
+    pub async fn read_frames_i32(&mut self, num_frames: usize) -> Result<Vec<Vec<i32>>> {
+        self.read_samples(num_frames, decode_samples_i32).await
+    }
+
+    /// Reads up to `num_frames` frames into 32-bit float samples, normalizing integer
+    /// samples.
+    pub async fn read_frames_f32(&mut self, num_frames: usize) -> Result<Vec<Vec<f32>>> {
+        self.read_samples(num_frames, decode_samples_f32).await
+    }
+
+    /// Reads up to `num_frames` frames into 64-bit float samples, normalizing integer
+    /// samples.
+    pub async fn read_frames_f64(&mut self, num_frames: usize) -> Result<Vec<Vec<f64>>> {
+        self.read_samples(num_frames, decode_samples_f64).await
+    }
+
+    /// Returns the underlying reader, positioned within or after the data chunk.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    async fn read_samples<S>(
+        &mut self,
+        num_frames: usize,
+        decode: impl Fn(&[u8], &Spec) -> Result<Vec<Vec<S>>>,
+    ) -> Result<Vec<Vec<S>>> {
+        let size = self.inner.read_size(num_frames);
+
+        let mut data = vec![];
+        (&mut self.reader).take(size).read_to_end(&mut data).await?;
+
+        self.inner.decode_read(&data, size, decode)
+    }
+}
+
+/// Reads the chunks of a WAV file up to the end of the data chunk header, for parsing by
+/// [`WavReader`]. A stream that ends early returns what was read, which fails to parse.
+async fn read_header(reader: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>> {
+    let mut header = vec![];
+    if !read_chunk(reader, &mut header, 12).await? {
+        return Ok(header);
+    }
+
+    // RF64 files keep their sizes in the ds64 chunk that follows
+    let rf64 = header.starts_with(b"RF64") || header.starts_with(b"BW64");
+
+    loop {
+        let start = header.len();
+        if !read_chunk(reader, &mut header, 8).await? {
+            return Ok(header);
+        }
+
+        let id = &header[start..start + 4];
+        let size = u32::from_le_bytes(header[start + 4..start + 8].try_into().unwrap());
+        let size = match &header[..4] {
+            b"RIFX" => size.swap_bytes(),
+            _ => size,
+        };
+
+        if id == b"data" {
+            return Ok(header);
+        }
+
+        // chunks are padded to an even size, except the ds64 chunk read by WavReader::new
+        let padded_size = match id {
+            b"ds64" if rf64 => size as u64,
+            _ => size as u64 + size as u64 % 2,
+        };
+        if !read_chunk(reader, &mut header, padded_size).await? {
+            return Ok(header);
+        }
+    }
+}
+
+/// Appends `size` bytes of the stream to `buf`, and returns whether they were all read.
+async fn read_chunk(
+    reader: &mut (impl AsyncRead + Unpin),
+    buf: &mut Vec<u8>,
+    size: u64,
+) -> Result<bool> {
+    let len = (&mut *reader).take(size).read_to_end(buf).await?;
+
+    Ok(len as u64 == size)
+}
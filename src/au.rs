@@ -0,0 +1,165 @@
+//! Sun AU/SND support. Samples are big-endian, and are converted to the layout of WAV data
+//! chunks so they share the WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{frame_size, parse_id, parse_u32, to_wav_layout, Endianness, SampleFormat, Spec};
+use crate::write::{encode_mulaw, validate_channels, write_file};
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+const HEADER_SIZE: u32 = 24;
+
+const ENCODING_MULAW: u32 = 1;
+const ENCODING_LINEAR_16: u32 = 3;
+
+/// Creates a vector of 16-bit AU bytes from audio data.
+pub fn create_au_bytes<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    build_au_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        ENCODING_LINEAR_16,
+        2,
+        |buf, sample| buf.extend_from_slice(&sample.to_be_bytes()),
+    )
+}
+
+/// Creates a vector of G.711 µ-law AU bytes from 16-bit audio data.
+pub fn create_au_bytes_mulaw<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    build_au_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        ENCODING_MULAW,
+        1,
+        |buf, &sample| buf.push(encode_mulaw(sample)),
+    )
+}
+
+/// Writes audio data into a 16-bit AU file.
+pub fn write_au<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_au_bytes(audiodata, samplerate)?, path)
+}
+
+/// Writes 16-bit audio data into a G.711 µ-law AU file.
+pub fn write_au_mulaw<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_au_bytes_mulaw(audiodata, samplerate)?, path)
+}
+
+fn build_au_bytes<C: AsRef<[i16]>>(
+    audiodata: &[C],
+    samplerate: u32,
+    encoding: u32,
+    sample_size: usize,
+    encode: impl Fn(&mut Vec<u8>, &i16),
+) -> Result<Vec<u8>> {
+    let num_frames = validate_channels(audiodata)?;
+    let num_channels = audiodata.len() as u32;
+
+    // sizes that don't fit are marked as unknown, and readers take the rest of the file
+    let audiodata_size = (num_frames * num_channels as usize * sample_size)
+        .try_into()
+        .unwrap_or(u32::MAX);
+
+    let mut buf = Vec::with_capacity(HEADER_SIZE as usize + audiodata_size as usize);
+
+    write!(buf, ".snd")?;
+    buf.extend_from_slice(&HEADER_SIZE.to_be_bytes());
+    buf.extend_from_slice(&audiodata_size.to_be_bytes());
+    buf.extend_from_slice(&encoding.to_be_bytes());
+    buf.extend_from_slice(&samplerate.to_be_bytes());
+    buf.extend_from_slice(&num_channels.to_be_bytes());
+
+    for f in 0..num_frames {
+        for channel in audiodata {
+            encode(&mut buf, &channel.as_ref()[f]);
+        }
+    }
+
+    Ok(buf)
+}
+
+/// Parses an AU file and returns its spec along with the sample bytes, in the layout of a
+/// WAV data chunk.
+pub(crate) fn parse_au(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    if buf.len() < HEADER_SIZE as usize {
+        return Err(OndaError::Truncated {
+            offset: 0,
+            needed: HEADER_SIZE as u64,
+        });
+    }
+
+    let mut offset = 0;
+
+    if parse_id(buf, &mut offset)? != b".snd" {
+        return Err(OndaError::NotFormat("AU"));
+    }
+
+    let data_offset = parse_u32(buf, &mut offset, Endianness::Big)? as usize;
+    let data_size = parse_u32(buf, &mut offset, Endianness::Big)?;
+    let encoding = parse_u32(buf, &mut offset, Endianness::Big)?;
+    let samplerate = parse_u32(buf, &mut offset, Endianness::Big)?;
+    let num_channels = parse_u32(buf, &mut offset, Endianness::Big)?;
+
+    let (format, bits_per_sample) = match encoding {
+        ENCODING_MULAW => (SampleFormat::MuLaw, 8),
+        2 => (SampleFormat::Int, 8),
+        ENCODING_LINEAR_16 => (SampleFormat::Int, 16),
+        4 => (SampleFormat::Int, 24),
+        5 => (SampleFormat::Int, 32),
+        6 => (SampleFormat::Float, 32),
+        7 => (SampleFormat::Float, 64),
+        27 => (SampleFormat::ALaw, 8),
+        _ => return Err(OndaError::UnsupportedCodec { tag: encoding }),
+    };
+
+    let num_channels: u16 = match num_channels.try_into() {
+        Ok(0) | Err(_) => return Err(OndaError::UnsupportedChannels(num_channels)),
+        Ok(num_channels) => num_channels,
+    };
+
+    let Some(data) = buf.get(data_offset..) else {
+        return Err(OndaError::Truncated {
+            offset: data_offset as u64,
+            needed: 0,
+        });
+    };
+
+    // an unknown size means the data runs to the end of the file
+    let data = match data_size {
+        u32::MAX => data,
+        size => &data[..(size as usize).min(data.len())],
+    };
+
+    let spec = Spec {
+        format,
+        num_channels,
+        samplerate,
+        block_align: frame_size(num_channels, bits_per_sample)?,
+        bits_per_sample,
+        valid_bits_per_sample: bits_per_sample,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    let data = to_wav_layout(data, &spec, Endianness::Big, true)?;
+
+    Ok((spec, data))
+}
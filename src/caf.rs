@@ -0,0 +1,168 @@
+//! Apple Core Audio Format support. Chunk fields are big-endian while samples may have
+//! either byte order, and are converted to the layout of WAV data chunks so they share the
+//! WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{
+    frame_size, parse_id, parse_u32, parse_u64, to_wav_layout, Endianness, SampleFormat, Spec,
+};
+use std::borrow::Cow;
+
+const FORMAT_FLAG_IS_FLOAT: u32 = 1 << 0;
+const FORMAT_FLAG_IS_LITTLE_ENDIAN: u32 = 1 << 1;
+
+/// Parses a CAF file and returns its spec along with the sample bytes of the data chunk, in
+/// the layout of a WAV data chunk.
+pub(crate) fn parse_caf(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    if buf.len() < 8 {
+        return Err(OndaError::Truncated {
+            offset: 0,
+            needed: 8,
+        });
+    }
+
+    let mut offset = 0;
+
+    if parse_id(buf, &mut offset)? != b"caff" {
+        return Err(OndaError::NotFormat("CAF"));
+    }
+
+    // ignore version and flags
+    offset += 4;
+
+    let mut desc = None;
+    let mut data = None;
+
+    while offset + 12 <= buf.len() {
+        let id = parse_id(buf, &mut offset)?;
+        let size = parse_u64(buf, &mut offset, Endianness::Big)?;
+
+        // a size of -1 marks a data chunk running to the end of the file
+        let end = match size {
+            u64::MAX if id == b"data" => buf.len(),
+            size => match usize::try_from(size)
+                .ok()
+                .and_then(|s| offset.checked_add(s))
+            {
+                Some(end) if end <= buf.len() => end,
+                _ => {
+                    return Err(OndaError::Truncated {
+                        offset: offset as u64,
+                        needed: size,
+                    })
+                }
+            },
+        };
+        let payload = &buf[offset..end];
+
+        match id {
+            b"desc" => desc = Some(parse_desc_chunk(payload)?),
+            b"data" => data = Some(parse_data_chunk(payload)?),
+            _ => {}
+        }
+
+        offset = end;
+    }
+
+    let Some((spec, byte_order)) = desc else {
+        return Err(OndaError::MissingChunk { id: *b"desc" });
+    };
+    let Some(data) = data else {
+        return Err(OndaError::MissingChunk { id: *b"data" });
+    };
+
+    let size = data.len() - data.len() % spec.block_align as usize;
+    let data = to_wav_layout(&data[..size], &spec, byte_order, true)?;
+
+    Ok((spec, data))
+}
+
+/// Parses the desc chunk into a spec, and returns it along with the byte order of the
+/// samples.
+fn parse_desc_chunk(payload: &[u8]) -> Result<(Spec, Endianness)> {
+    if payload.len() < 32 {
+        return Err(OndaError::BadChunkSize { id: *b"desc" });
+    }
+
+    let mut offset = 0;
+    let samplerate = f64::from_bits(parse_u64(payload, &mut offset, Endianness::Big)?);
+    let format_id = parse_id(payload, &mut offset)?;
+    let format_flags = parse_u32(payload, &mut offset, Endianness::Big)?;
+    let bytes_per_packet = parse_u32(payload, &mut offset, Endianness::Big)?;
+    let frames_per_packet = parse_u32(payload, &mut offset, Endianness::Big)?;
+    let channels_per_frame = parse_u32(payload, &mut offset, Endianness::Big)?;
+    let bits_per_channel = parse_u32(payload, &mut offset, Endianness::Big)?;
+
+    let format = match format_id {
+        b"lpcm" if format_flags & FORMAT_FLAG_IS_FLOAT != 0 => SampleFormat::Float,
+        b"lpcm" => SampleFormat::Int,
+        b"ulaw" => SampleFormat::MuLaw,
+        b"alaw" => SampleFormat::ALaw,
+        _ => {
+            return Err(OndaError::UnsupportedCodec {
+                tag: u32::from_be_bytes(*format_id),
+            })
+        }
+    };
+    let byte_order = if format_flags & FORMAT_FLAG_IS_LITTLE_ENDIAN != 0 {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    };
+
+    let num_channels: u16 = match channels_per_frame.try_into() {
+        Ok(0) | Err(_) => return Err(OndaError::UnsupportedChannels(channels_per_frame)),
+        Ok(num_channels) => num_channels,
+    };
+
+    // companded samples are always a single byte
+    let bits_per_sample = match format {
+        SampleFormat::ALaw | SampleFormat::MuLaw => 8,
+        _ => u16::try_from(bits_per_channel).unwrap_or(0),
+    };
+
+    let supported = match format {
+        SampleFormat::Int => matches!(bits_per_sample, 8 | 16 | 24 | 32),
+        SampleFormat::Float => matches!(bits_per_sample, 32 | 64),
+        _ => true,
+    };
+    if !supported {
+        return Err(OndaError::UnsupportedBitDepth(bits_per_channel));
+    }
+
+    // packed samples only, without padding or variable-size packets
+    let block_align = frame_size(num_channels, bits_per_sample)?;
+    if frames_per_packet != 1 || bytes_per_packet != block_align as u32 {
+        return Err(OndaError::Unsupported("CAF packet layout"));
+    }
+
+    if !samplerate.is_finite() || samplerate < 1.0 || samplerate > u32::MAX as f64 {
+        return Err(OndaError::Unsupported("CAF sample rate"));
+    }
+
+    let spec = Spec {
+        format,
+        num_channels,
+        samplerate: samplerate.round() as u32,
+        block_align,
+        bits_per_sample,
+        valid_bits_per_sample: bits_per_sample,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    Ok((spec, byte_order))
+}
+
+/// Returns the sample bytes of a data chunk.
+fn parse_data_chunk(payload: &[u8]) -> Result<&[u8]> {
+    // samples follow the edit count
+    match payload.get(4..) {
+        Some(data) => Ok(data),
+        None => Err(OndaError::BadChunkSize { id: *b"data" }),
+    }
+}
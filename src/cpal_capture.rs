@@ -0,0 +1,232 @@
+//! Recording from cpal input devices into 16-bit WAV files.
+
+use crate::error::{OndaError, Result};
+use crate::WavWriter;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, StreamError};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::io::{self, ErrorKind, Seek, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often `record` moves samples from the ring buffer to the file.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Time past the recording duration after which `record` gives up on a stalled device.
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Records the default input config of a device into a 16-bit WAV file at the provided
+/// path, e.g. `onda::record(&host.default_input_device().unwrap(), duration, "x.wav")`.
+/// Samples of other formats are converted to 16 bits. If the stream fails or stops
+/// delivering samples, the file is finalized with the frames recorded so far before the
+/// error is returned, and frames dropped because the file fell behind are an error too.
+pub fn record(device: &cpal::Device, duration: Duration, path: impl AsRef<Path>) -> Result<()> {
+    let config = device.default_input_config()?;
+    let num_channels = config.channels();
+    let samplerate = config.sample_rate().0;
+    let len_frames = (duration.as_nanos() * samplerate as u128 / 1_000_000_000) as u64;
+
+    let writer = WavWriter::create(path, num_channels, samplerate)?;
+    // a second of audio, so a slow disk doesn't drop frames
+    let capacity = samplerate as usize * num_channels as usize;
+    let (producer, mut ring) = ring_writer(writer, capacity);
+
+    let error = Arc::new(Mutex::new(None));
+    let stream_config = config.config();
+    let stream = match config.sample_format() {
+        SampleFormat::I8 => build_stream::<i8>(device, &stream_config, producer, &error),
+        SampleFormat::I16 => build_stream::<i16>(device, &stream_config, producer, &error),
+        SampleFormat::I32 => build_stream::<i32>(device, &stream_config, producer, &error),
+        SampleFormat::I64 => build_stream::<i64>(device, &stream_config, producer, &error),
+        SampleFormat::U8 => build_stream::<u8>(device, &stream_config, producer, &error),
+        SampleFormat::U16 => build_stream::<u16>(device, &stream_config, producer, &error),
+        SampleFormat::U32 => build_stream::<u32>(device, &stream_config, producer, &error),
+        SampleFormat::U64 => build_stream::<u64>(device, &stream_config, producer, &error),
+        SampleFormat::F32 => build_stream::<f32>(device, &stream_config, producer, &error),
+        SampleFormat::F64 => build_stream::<f64>(device, &stream_config, producer, &error),
+        _ => Err(OndaError::Unsupported("input sample format")),
+    };
+
+    let result = stream.and_then(|stream| {
+        stream.play()?;
+        capture(
+            &mut ring,
+            len_frames,
+            &error,
+            Instant::now() + duration + STALL_TIMEOUT,
+        )
+    });
+
+    let dropped_frames = ring.dropped_frames();
+    ring.finalize()?;
+    result?;
+
+    match dropped_frames {
+        0 => Ok(()),
+        n => Err(OndaError::DroppedFrames(n)),
+    }
+}
+
+/// Drains the ring buffer into the file until it holds `len_frames`, the stream reports an
+/// error or the deadline passes.
+fn capture<W: Write + Seek>(
+    ring: &mut RingWriter<W>,
+    len_frames: u64,
+    error: &Mutex<Option<StreamError>>,
+    deadline: Instant,
+) -> Result<()> {
+    while ring.num_frames() < len_frames {
+        if let Some(e) = error.lock().unwrap().take() {
+            return Err(e.into());
+        }
+        if Instant::now() > deadline {
+            let e = io::Error::new(
+                ErrorKind::TimedOut,
+                "input device stopped delivering samples",
+            );
+            return Err(e.into());
+        }
+        thread::sleep(DRAIN_INTERVAL);
+        ring.drain((len_frames - ring.num_frames()) as usize)?;
+    }
+
+    Ok(())
+}
+
+fn build_stream<T: SizedSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: RingProducer,
+    error: &Arc<Mutex<Option<StreamError>>>,
+) -> Result<cpal::Stream>
+where
+    i16: FromSample<T>,
+{
+    let error = error.clone();
+
+    Ok(device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            producer.push(data);
+        },
+        move |e| {
+            error.lock().unwrap().get_or_insert(e);
+        },
+        None,
+    )?)
+}
+
+/// Splits a [`WavWriter`] into the two ends of a ring buffer holding `capacity` samples:
+/// a [`RingProducer`] for the audio callback, and a [`RingWriter`] that moves the samples
+/// into the file on another thread.
+pub fn ring_writer<W: Write + Seek>(
+    writer: WavWriter<W>,
+    capacity: usize,
+) -> (RingProducer, RingWriter<W>) {
+    let num_channels = writer.num_channels() as usize;
+    // room for at least one frame
+    let capacity = capacity.max(num_channels);
+    let (producer, consumer) = HeapRb::new(capacity).split();
+    let dropped_frames = Arc::new(AtomicU64::new(0));
+
+    let producer = RingProducer {
+        producer,
+        num_channels,
+        dropped_frames: dropped_frames.clone(),
+    };
+    let ring = RingWriter {
+        consumer,
+        writer,
+        buf: vec![0; capacity - capacity % num_channels],
+        dropped_frames,
+    };
+
+    (producer, ring)
+}
+
+/// Audio callback end of [`ring_writer`].
+pub struct RingProducer {
+    producer: HeapProd<i16>,
+    num_channels: usize,
+    dropped_frames: Arc<AtomicU64>,
+}
+
+impl RingProducer {
+    /// Pushes interleaved samples, converted to 16 bits, and returns the number of frames
+    /// pushed. It neither blocks nor allocates, so it can run in an audio callback. Frames
+    /// that don't fit in the ring buffer are dropped, and a trailing partial frame ignored.
+    pub fn push<T: cpal::Sample>(&mut self, samples: &[T]) -> usize
+    where
+        i16: FromSample<T>,
+    {
+        let frames = samples.len() / self.num_channels;
+        let vacant = self.producer.vacant_len() / self.num_channels;
+        let pushed = frames.min(vacant);
+
+        self.producer.push_iter(
+            samples[..pushed * self.num_channels]
+                .iter()
+                .map(|&sample| i16::from_sample_(sample)),
+        );
+        if pushed < frames {
+            self.dropped_frames
+                .fetch_add((frames - pushed) as u64, Ordering::Relaxed);
+        }
+
+        pushed
+    }
+}
+
+/// Writer end of [`ring_writer`].
+pub struct RingWriter<W: Write + Seek> {
+    consumer: HeapCons<i16>,
+    writer: WavWriter<W>,
+    /// Whole frames popped from the ring buffer before being written.
+    buf: Vec<i16>,
+    dropped_frames: Arc<AtomicU64>,
+}
+
+impl<W: Write + Seek> RingWriter<W> {
+    /// Writes up to `max_frames` of the frames in the ring buffer, and returns the number
+    /// written.
+    pub fn drain(&mut self, max_frames: usize) -> Result<usize> {
+        let num_channels = self.writer.num_channels() as usize;
+        let mut written = 0;
+
+        while written < max_frames {
+            let frames = (self.consumer.occupied_len() / num_channels)
+                .min(self.buf.len() / num_channels)
+                .min(max_frames - written);
+            if frames == 0 {
+                break;
+            }
+
+            let buf = &mut self.buf[..frames * num_channels];
+            self.consumer.pop_slice(buf);
+            self.writer.write_interleaved(buf)?;
+            written += frames;
+        }
+
+        Ok(written)
+    }
+
+    /// Number of frames written to the file so far.
+    pub fn num_frames(&self) -> u64 {
+        self.writer.num_frames()
+    }
+
+    /// Number of frames dropped because the ring buffer was full.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Finalizes the file and returns the underlying writer. Frames still in the ring
+    /// buffer are discarded, so `drain` them first to keep them.
+    pub fn finalize(self) -> Result<W> {
+        self.writer.finalize()
+    }
+}
@@ -0,0 +1,44 @@
+//! Conversions between audio data and dasp signals and frames.
+
+use crate::error::Result;
+use crate::metadata::WavMetadata;
+use crate::WavData;
+use dasp::{Frame, Sample, Signal};
+
+impl<S: Sample> WavData<S> {
+    /// Returns the frames as a dasp signal of `N` channels, which must be the number of
+    /// channels. The signal yields silence once the frames are exhausted.
+    pub fn signal<const N: usize>(&self) -> Result<impl Signal<Frame = [S; N]> + '_>
+    where
+        [S; N]: Frame<Sample = S>,
+    {
+        Ok(dasp::signal::from_iter(self.frames::<N>()?))
+    }
+
+    /// Collects dasp frames into audio data with the given sample rate, e.g. the frames of
+    /// a signal after `take`. The bit depth is the size of `S`.
+    pub fn from_frames<F: Frame<Sample = S>>(
+        frames: impl IntoIterator<Item = F>,
+        samplerate: u32,
+    ) -> Self {
+        let mut audiodata: Vec<Vec<S>> = (0..F::CHANNELS).map(|_| vec![]).collect();
+        for frame in frames {
+            for (channel, sample) in audiodata.iter_mut().zip(frame.channels()) {
+                channel.push(sample);
+            }
+        }
+
+        let bits_per_sample = (std::mem::size_of::<S>() * 8) as u16;
+
+        WavData {
+            num_channels: F::CHANNELS as u16,
+            samplerate,
+            bits_per_sample,
+            valid_bits_per_sample: bits_per_sample,
+            channel_mask: 0,
+            audiodata,
+            metadata: WavMetadata::default(),
+            warnings: vec![],
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! DSF and DSDIFF (DFF) support. DSD streams are 1-bit samples at a high rate, converted to
+//! PCM by low-pass filtering and decimating them.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{parse_id, parse_u16, parse_u32, parse_u64, read_file, Endianness, WavData};
+use std::path::Path;
+
+/// Block size per channel of DSF files, the only one the specification allows.
+const DSF_BLOCK_SIZE: usize = 4096;
+
+/// Container of a DSD stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DsdContainer {
+    Dsf,
+    Dff,
+}
+
+/// Stream parameters of a DSD file. `samplerate` is the 1-bit sample rate, e.g. 2822400
+/// for DSD64, and `num_samples` the number of 1-bit samples per channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DsdInfo {
+    pub container: DsdContainer,
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub num_samples: u64,
+}
+
+/// Parses the stream parameters of a DSF or DFF file without converting its samples.
+pub fn probe_dsd(buf: impl AsRef<[u8]>) -> Result<DsdInfo> {
+    parse_stream(buf.as_ref()).map(|(info, _)| info)
+}
+
+/// Converts a DSF or DFF file to float PCM, at `1 / decimation` of the DSD sample rate.
+/// For example a decimation of 64 turns DSD64 into 44.1 kHz audio.
+pub fn parse_dsd(buf: impl AsRef<[u8]>, decimation: u32) -> Result<WavData<f32>> {
+    if decimation == 0 {
+        return Err(OndaError::InvalidArgument("decimation must be at least 1"));
+    }
+
+    let (info, channels) = parse_stream(buf.as_ref())?;
+
+    let audiodata = channels
+        .iter()
+        .map(|bits| decimate(bits, info.num_samples as usize, decimation as usize))
+        .collect();
+
+    Ok(WavData {
+        num_channels: info.num_channels,
+        samplerate: info.samplerate / decimation,
+        bits_per_sample: 32,
+        valid_bits_per_sample: 32,
+        channel_mask: 0,
+        audiodata,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    })
+}
+
+/// Reads the stream parameters of a DSF or DFF file from the provided path.
+pub fn read_dsd_info(path: impl AsRef<Path>) -> Result<DsdInfo> {
+    probe_dsd(read_file(path)?)
+}
+
+/// Reads a DSF or DFF file from the provided path and converts it to float PCM.
+pub fn read_dsd(path: impl AsRef<Path>, decimation: u32) -> Result<WavData<f32>> {
+    parse_dsd(read_file(path)?, decimation)
+}
+
+/// Parses a DSF or DFF file into its stream parameters and the bytes of each channel, most
+/// significant bit first.
+fn parse_stream(buf: &[u8]) -> Result<(DsdInfo, Vec<Vec<u8>>)> {
+    if buf.starts_with(b"DSD ") {
+        parse_dsf(buf)
+    } else if buf.starts_with(b"FRM8") {
+        parse_dff(buf)
+    } else {
+        Err(OndaError::NotFormat("DSF or DSDIFF"))
+    }
+}
+
+fn parse_dsf(buf: &[u8]) -> Result<(DsdInfo, Vec<Vec<u8>>)> {
+    if buf.len() < 28 + 52 + 12 {
+        return Err(OndaError::Truncated {
+            offset: 0,
+            needed: 28 + 52 + 12,
+        });
+    }
+
+    // skip the DSD chunk, which holds the file size and a metadata pointer
+    let mut offset = 28;
+
+    if parse_id(buf, &mut offset)? != b"fmt " {
+        return Err(OndaError::MissingChunk { id: *b"fmt " });
+    }
+    let fmt_size = parse_u64(buf, &mut offset, Endianness::Little)? as usize;
+    let fmt_end = (offset - 12).saturating_add(fmt_size);
+
+    // ignore format version
+    offset += 4;
+    let format_id = parse_u32(buf, &mut offset, Endianness::Little)?;
+    // ignore channel type
+    offset += 4;
+    let num_channels = parse_u32(buf, &mut offset, Endianness::Little)?;
+    let samplerate = parse_u32(buf, &mut offset, Endianness::Little)?;
+    let bits_per_sample = parse_u32(buf, &mut offset, Endianness::Little)?;
+    let num_samples = parse_u64(buf, &mut offset, Endianness::Little)?;
+    let block_size = parse_u32(buf, &mut offset, Endianness::Little)? as usize;
+
+    if format_id != 0 {
+        return Err(OndaError::UnsupportedCodec { tag: format_id });
+    }
+    if !matches!(num_channels, 1..=6) {
+        return Err(OndaError::UnsupportedChannels(num_channels));
+    }
+    if block_size != DSF_BLOCK_SIZE {
+        return Err(OndaError::Unsupported("DSF block size"));
+    }
+    let lsb_first = match bits_per_sample {
+        1 => true,
+        8 => false,
+        _ => return Err(OndaError::UnsupportedBitDepth(bits_per_sample)),
+    };
+
+    offset = fmt_end;
+    if offset.saturating_add(12) > buf.len() || parse_id(buf, &mut offset)? != b"data" {
+        return Err(OndaError::MissingChunk { id: *b"data" });
+    }
+    let data_size = parse_u64(buf, &mut offset, Endianness::Little)? as usize;
+    let Some(data) = buf.get(offset..(offset - 12).saturating_add(data_size)) else {
+        return Err(OndaError::Truncated {
+            offset: offset as u64,
+            needed: data_size.saturating_sub(12) as u64,
+        });
+    };
+
+    // channels take turns with a block each
+    let num_channels = num_channels as usize;
+    let mut channels = vec![Vec::with_capacity(data.len() / num_channels); num_channels];
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        channels[i % num_channels].extend(block.iter().map(|&byte| {
+            if lsb_first {
+                byte.reverse_bits()
+            } else {
+                byte
+            }
+        }));
+    }
+
+    let info = DsdInfo {
+        container: DsdContainer::Dsf,
+        num_channels: num_channels as u16,
+        samplerate,
+        num_samples,
+    };
+
+    Ok((info, channels))
+}
+
+fn parse_dff(buf: &[u8]) -> Result<(DsdInfo, Vec<Vec<u8>>)> {
+    if buf.len() < 16 {
+        return Err(OndaError::Truncated {
+            offset: 0,
+            needed: 16,
+        });
+    }
+
+    let mut offset = 12;
+
+    if parse_id(buf, &mut offset)? != b"DSD " {
+        return Err(OndaError::NotFormat("DSDIFF"));
+    }
+
+    let mut samplerate = None;
+    let mut num_channels = None;
+    let mut data = None;
+
+    let chunks = iff_chunks(buf, offset)?;
+    for (id, payload) in chunks {
+        match &id {
+            b"PROP" if payload.starts_with(b"SND ") => {
+                for (id, payload) in iff_chunks(payload, 4)? {
+                    match &id {
+                        b"FS  " if payload.len() >= 4 => {
+                            samplerate = Some(parse_u32(payload, &mut 0, Endianness::Big)?);
+                        }
+                        b"CHNL" if payload.len() >= 2 => {
+                            num_channels = Some(parse_u16(payload, &mut 0, Endianness::Big)?);
+                        }
+                        b"CMPR" if !payload.starts_with(b"DSD ") => {
+                            return Err(OndaError::Unsupported("compressed DSDIFF"));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            b"DSD " => data = Some(payload),
+            b"DST " => return Err(OndaError::Unsupported("compressed DSDIFF")),
+            _ => {}
+        }
+    }
+
+    let Some(samplerate) = samplerate else {
+        return Err(OndaError::MissingChunk { id: *b"FS  " });
+    };
+    let Some(num_channels) = num_channels else {
+        return Err(OndaError::MissingChunk { id: *b"CHNL" });
+    };
+    let Some(data) = data else {
+        return Err(OndaError::MissingChunk { id: *b"DSD " });
+    };
+
+    if num_channels == 0 {
+        return Err(OndaError::NoChannels);
+    }
+
+    // channels take turns with a byte each
+    let mut channels =
+        vec![Vec::with_capacity(data.len() / num_channels as usize); num_channels as usize];
+    for frame in data.chunks_exact(num_channels as usize) {
+        for (channel, &byte) in channels.iter_mut().zip(frame) {
+            channel.push(byte);
+        }
+    }
+
+    let info = DsdInfo {
+        container: DsdContainer::Dff,
+        num_channels,
+        samplerate,
+        num_samples: channels[0].len() as u64 * 8,
+    };
+
+    Ok((info, channels))
+}
+
+/// Splits the contents of a DSDIFF chunk into its sub-chunks, which have 64-bit big-endian
+/// sizes and are padded to an even size.
+fn iff_chunks(buf: &[u8], mut offset: usize) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut chunks = vec![];
+
+    while offset + 12 <= buf.len() {
+        let id = *parse_id(buf, &mut offset)?;
+        let size = parse_u64(buf, &mut offset, Endianness::Big)? as usize;
+
+        let Some(payload) = buf.get(offset..offset.saturating_add(size)) else {
+            return Err(OndaError::Truncated {
+                offset: offset as u64,
+                needed: size as u64,
+            });
+        };
+        chunks.push((id, payload));
+
+        offset += size + size % 2;
+    }
+
+    Ok(chunks)
+}
+
+/// Low-pass filters a 1-bit stream and keeps every `decimation`th sample. The filter is a
+/// triangular window spanning two output samples, i.e. a second-order CIC filter.
+fn decimate(bytes: &[u8], num_samples: usize, decimation: usize) -> Vec<f32> {
+    let num_samples = num_samples.min(bytes.len() * 8);
+    let bit = |i: usize| -> f32 {
+        if i >= num_samples {
+            0.0
+        } else if bytes[i / 8] & (0x80 >> (i % 8)) != 0 {
+            1.0
+        } else {
+            -1.0
+        }
+    };
+
+    let gain = 1.0 / (decimation * decimation) as f32;
+
+    (0..num_samples / decimation)
+        .map(|n| {
+            let start = n * decimation;
+            let sum: f32 = (0..2 * decimation - 1)
+                .map(|k| bit(start + k) * (k + 1).min(2 * decimation - 1 - k) as f32)
+                .sum();
+            sum * gain
+        })
+        .collect()
+}
@@ -0,0 +1,336 @@
+//! Editing WAV files in place: changing their metadata without rewriting their audio, and
+//! appending audio to them.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::{self, MetadataPart, Peak, WavMetadata};
+use crate::read::{
+    parse_fmt_payload, sample_type_mismatch, Endianness, ParseOptions, SampleFormat,
+};
+use crate::{Sample, WavData};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// A chunk of a WAV file being edited.
+struct Chunk {
+    id: [u8; 4],
+    /// Offset of the chunk header in the file.
+    offset: u64,
+    /// Size of the payload, taken from the ds64 chunk for the data chunk of RF64 files.
+    size: u64,
+    /// Payload of the chunk, left empty for the data chunk.
+    payload: Vec<u8>,
+}
+
+/// The chunks of a little-endian WAV file, in the order they appear.
+struct ChunkLayout {
+    /// Offset of the ds64 payload of RF64 files.
+    ds64: Option<u64>,
+    chunks: Vec<Chunk>,
+    /// Index of the data chunk in `chunks`.
+    data: usize,
+    /// End of the last chunk, whose padding byte may be missing.
+    end: u64,
+}
+
+impl ChunkLayout {
+    /// Checks the RIFF header of a file and walks all of its chunks.
+    fn read(file: &mut File) -> Result<Self> {
+        let file_size = file.metadata()?.len();
+
+        let mut header = [0; 12];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        let rf64 = match &header[..4] {
+            b"RIFF" => false,
+            b"RF64" | b"BW64" => true,
+            b"RIFX" => return Err(OndaError::Unsupported("big-endian")),
+            _ => return Err(OndaError::NotRiff),
+        };
+        if &header[8..] != b"WAVE" {
+            return Err(OndaError::NotWave);
+        }
+
+        let mut chunks = vec![];
+        let mut ds64 = None;
+        let mut ds64_size = None;
+
+        let mut offset = 12;
+        while offset + 8 <= file_size {
+            let mut chunk_header = [0; 8];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut chunk_header)?;
+
+            let id: [u8; 4] = chunk_header[..4].try_into().unwrap();
+            let mut size = u32::from_le_bytes(chunk_header[4..].try_into().unwrap()) as u64;
+            // RF64 files mark sizes kept in the ds64 chunk with the maximum value
+            if let (b"data", u32::MAX, Some(ds64_size)) = (&id, size as u32, ds64_size) {
+                size = ds64_size;
+            }
+            if size > file_size - offset - 8 {
+                return Err(OndaError::Truncated {
+                    offset: offset + 8,
+                    needed: size,
+                });
+            }
+
+            let mut payload = vec![];
+            if &id != b"data" {
+                payload.resize(size as usize, 0);
+                file.read_exact(&mut payload)?;
+            }
+            if &id == b"ds64" && payload.len() >= 24 {
+                ds64 = Some(offset + 8);
+                ds64_size = Some(u64::from_le_bytes(payload[8..16].try_into().unwrap()));
+            }
+
+            chunks.push(Chunk {
+                id,
+                offset,
+                size,
+                payload,
+            });
+            offset += 8 + size + size % 2;
+        }
+
+        let Some(data) = chunks.iter().position(|chunk| &chunk.id == b"data") else {
+            return Err(OndaError::MissingChunk { id: *b"data" });
+        };
+        if rf64 && ds64.is_none() {
+            return Err(OndaError::MissingChunk { id: *b"ds64" });
+        }
+
+        Ok(Self {
+            ds64,
+            chunks,
+            data,
+            end: offset,
+        })
+    }
+
+    fn find(&self, id: &[u8; 4]) -> Option<&Chunk> {
+        self.chunks.iter().find(|chunk| &chunk.id == id)
+    }
+
+    /// Writes the size of the RIFF chunk, which RF64 files keep first in the ds64 chunk.
+    fn write_riff_size(&self, file: &mut File, riff_size: u64) -> Result<()> {
+        match self.ds64 {
+            Some(ds64) => {
+                file.seek(SeekFrom::Start(ds64))?;
+                file.write_all(&riff_size.to_le_bytes())?;
+            }
+            None => {
+                file.seek(SeekFrom::Start(4))?;
+                file.write_all(&(riff_size as u32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turns a chunk into a JUNK chunk of zeros, which readers skip.
+fn blank_chunk(file: &mut File, offset: u64, size: u64) -> Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(b"JUNK")?;
+    file.seek(SeekFrom::Current(4))?;
+    io::copy(&mut io::repeat(0).take(size), file)?;
+    Ok(())
+}
+
+/// Reads the metadata of a WAV file, lets `edit` change it, and writes it back. Only the
+/// chunks of the metadata that changed are replaced, e.g. LIST(INFO) for a new title, and
+/// all other chunks stay as they are, including the parts that onda doesn't read. The
+/// replacements are written after the last chunk before the chunks they replace are
+/// blanked into JUNK chunks, so the file stays readable if writing is interrupted. The
+/// data chunk stays where it is.
+pub fn edit_metadata(path: impl AsRef<Path>, edit: impl FnOnce(&mut WavMetadata)) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let layout = ChunkLayout::read(&mut file)?;
+
+    // a copy of the file without its audio, for parsing the metadata
+    let mut headers = b"RIFF\0\0\0\0WAVE".to_vec();
+    for chunk in &layout.chunks {
+        headers.extend_from_slice(&chunk.id);
+        headers.extend_from_slice(&(chunk.payload.len() as u32).to_le_bytes());
+        headers.extend_from_slice(&chunk.payload);
+        if chunk.payload.len() % 2 == 1 {
+            headers.push(0);
+        }
+    }
+    let samplerate = layout
+        .find(b"fmt ")
+        .filter(|fmt| fmt.payload.len() >= 8)
+        .map_or(0, |fmt| {
+            u32::from_le_bytes(fmt.payload[4..8].try_into().unwrap())
+        });
+
+    let old_metadata = metadata::parse_metadata(&headers, Endianness::Little, None);
+    let mut new_metadata = old_metadata.clone();
+    edit(&mut new_metadata);
+
+    let changed: Vec<MetadataPart> = MetadataPart::ALL
+        .into_iter()
+        .filter(|part| part.extract(&old_metadata) != part.extract(&new_metadata))
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+
+    let mut chunks = vec![];
+    for part in &changed {
+        let metadata = part.extract(&new_metadata);
+        chunks.extend(metadata::build_leading_chunks(&metadata)?);
+        chunks.extend(metadata::build_metadata_chunks(&metadata, samplerate)?);
+    }
+    // the new chunks go after the last one
+    let riff_size = layout.end + chunks.len() as u64 - 8;
+    if layout.ds64.is_none() && riff_size > u32::MAX as u64 {
+        return Err(OndaError::TooLarge("metadata for a RIFF file"));
+    }
+
+    file.set_len(layout.end)?;
+    file.seek(SeekFrom::Start(layout.end))?;
+    file.write_all(&chunks)?;
+    layout.write_riff_size(&mut file, riff_size)?;
+    file.sync_data()?;
+
+    for chunk in &layout.chunks {
+        let part = MetadataPart::of_chunk(&chunk.id, &chunk.payload);
+        if part.is_some_and(|part| changed.contains(&part)) {
+            blank_chunk(&mut file, chunk.offset, chunk.size)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Appends audio data to the data chunk of a WAV file, for recorders that flush to the
+/// same file repeatedly. The audio data must match the channels, sample rate, bit depth and
+/// sample type of the file, which must be integer or float PCM. The chunks after the data
+/// chunk are moved after the new audio, and the sizes of the header are patched. A PEAK
+/// chunk is updated with the peaks of the new audio, or blanked into a JUNK chunk if it
+/// can't be updated in place.
+pub fn append<S: Sample>(path: impl AsRef<Path>, data: &WavData<S>) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_size = file.metadata()?.len();
+    let layout = ChunkLayout::read(&mut file)?;
+    let data_chunk = &layout.chunks[layout.data];
+
+    let Some(fmt) = layout.find(b"fmt ") else {
+        return Err(OndaError::MissingChunk { id: *b"fmt " });
+    };
+    let spec = parse_fmt_payload(
+        &fmt.payload,
+        &mut 0,
+        fmt.payload.len(),
+        Endianness::Little,
+        ParseOptions::default(),
+    )?;
+
+    if !matches!(spec.format, SampleFormat::Int | SampleFormat::Float) {
+        return Err(OndaError::Unsupported("appending to compressed files"));
+    }
+    if data.audiodata.len() != spec.num_channels as usize {
+        return Err(OndaError::ChannelMismatch {
+            expected: spec.num_channels as usize,
+            found: data.audiodata.len(),
+        });
+    }
+    if data.samplerate != spec.samplerate {
+        return Err(OndaError::InvalidArgument(
+            "sample rate differs from the file",
+        ));
+    }
+    let float = spec.format == SampleFormat::Float;
+    if data.bits_per_sample != spec.bits_per_sample || S::FLOAT != float {
+        return Err(sample_type_mismatch(float, spec.bits_per_sample));
+    }
+
+    let audiodata = S::encode_data(&data.audiodata, spec.bits_per_sample)?;
+
+    // the chunks after the data chunk follow its padding byte, which may be missing
+    let data_end = data_chunk.offset + 8 + data_chunk.size;
+    let trailing_start = (data_end + data_chunk.size % 2).min(file_size);
+    let mut trailing_chunks = vec![];
+    file.seek(SeekFrom::Start(trailing_start))?;
+    file.read_to_end(&mut trailing_chunks)?;
+
+    let new_data_size = data_chunk.size + audiodata.len() as u64;
+    let padding = new_data_size % 2;
+    let new_trailing_start = data_end + audiodata.len() as u64 + padding;
+    let riff_size = new_trailing_start + trailing_chunks.len() as u64 - 8;
+    if layout.ds64.is_none() && riff_size > u32::MAX as u64 {
+        return Err(OndaError::TooLarge("audio data for a RIFF file"));
+    }
+    let old_frames = data_chunk.size / spec.block_align as u64;
+    let num_frames = new_data_size / spec.block_align as u64;
+
+    file.seek(SeekFrom::Start(data_end))?;
+    file.write_all(&audiodata)?;
+    if padding == 1 {
+        file.write_all(&[0])?;
+    }
+    file.write_all(&trailing_chunks)?;
+    file.set_len(riff_size + 8)?;
+
+    layout.write_riff_size(&mut file, riff_size)?;
+    file.seek(SeekFrom::Start(data_chunk.offset + 4))?;
+    match layout.ds64 {
+        Some(ds64) => {
+            // the data size and sample count of RF64 files follow the RIFF size in ds64
+            file.write_all(&u32::MAX.to_le_bytes())?;
+            file.seek(SeekFrom::Start(ds64 + 8))?;
+            file.write_all(&new_data_size.to_le_bytes())?;
+            file.write_all(&num_frames.to_le_bytes())?;
+        }
+        None => {
+            file.write_all(&(new_data_size as u32).to_le_bytes())?;
+            if let Some(fact) = layout.find(b"fact").filter(|fact| fact.size >= 4) {
+                file.seek(SeekFrom::Start(fact.offset + 8))?;
+                file.write_all(&(num_frames as u32).to_le_bytes())?;
+            }
+        }
+    }
+
+    if let Some(peak) = layout.find(b"PEAK") {
+        // chunks after the data chunk moved with the new audio
+        let offset = match peak.offset > data_chunk.offset {
+            true => peak.offset - trailing_start + new_trailing_start,
+            false => peak.offset,
+        };
+        match appended_peak(&peak.payload, data, old_frames) {
+            Some(payload) => {
+                file.seek(SeekFrom::Start(offset + 8))?;
+                file.write_all(&payload)?;
+            }
+            None => blank_chunk(&mut file, offset, peak.size)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the payload of a PEAK chunk updated with the peaks of audio appended after
+/// `old_frames` frames, or `None` if the chunk doesn't hold one peak per channel or the new
+/// payload has a different size.
+fn appended_peak<S: Sample>(payload: &[u8], data: &WavData<S>, old_frames: u64) -> Option<Vec<u8>> {
+    let old = metadata::parse_peak_chunk(payload, Endianness::Little)?;
+    let mut new = data.compute_peak();
+    if old.peaks.len() != new.peaks.len() {
+        return None;
+    }
+    for (peak, old_peak) in new.peaks.iter_mut().zip(old.peaks) {
+        *peak = match old_peak.value >= peak.value {
+            true => old_peak,
+            false => Peak {
+                value: peak.value,
+                position: u32::try_from(old_frames + peak.position as u64).ok()?,
+            },
+        };
+    }
+
+    let mut chunk = vec![];
+    metadata::write_peak_chunk(&mut chunk, &new).ok()?;
+    let payload_size = payload.len();
+    (chunk.len() - 8 == payload_size).then(|| chunk.split_off(8))
+}
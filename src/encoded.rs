@@ -0,0 +1,89 @@
+//! Passthrough of encoded WAV data, for formats that are copied rather than decoded.
+
+use crate::error::{OndaError, Result};
+use crate::read::{
+    find_chunk, parse_data_chunk, parse_riff_chunk, parse_slice, parse_u16, parse_u32, read_file,
+};
+use crate::{SUBFORMAT_GUID_SUFFIX, WAVE_FORMAT_EXTENSIBLE};
+use std::path::Path;
+
+/// Undecoded contents of a WAV file. Fields are copied from the fmt chunk, and `data` holds
+/// the data chunk as stored in the file.
+#[derive(Clone, Debug)]
+pub struct EncodedWav {
+    /// Codec of the data chunk, e.g. 7 for µ-law or 0x11 for IMA ADPCM. Extensible files
+    /// report the tag of their subformat.
+    pub format_tag: u16,
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub byterate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    /// Codec-specific bytes following the common fmt fields, without their size field.
+    pub fmt_extension: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// Parses a WAV file from a byte slice buffer without decoding its samples, so any codec is
+/// accepted.
+pub fn parse_encoded(buf: impl AsRef<[u8]>) -> Result<EncodedWav> {
+    let buf = buf.as_ref();
+    let mut offset = 0;
+
+    let (endian, data_size) = parse_riff_chunk(buf, &mut offset)?;
+
+    find_chunk(buf, &mut offset, endian, b"fmt ")?;
+    let size = parse_u32(buf, &mut offset, endian)? as usize;
+    let fmt = parse_slice(buf, &mut offset, size)?;
+    if size < 16 {
+        return Err(OndaError::BadChunkSize { id: *b"fmt " });
+    }
+
+    let mut fmt_offset = 0;
+    let mut format_tag = parse_u16(fmt, &mut fmt_offset, endian)?;
+    let num_channels = parse_u16(fmt, &mut fmt_offset, endian)?;
+    let samplerate = parse_u32(fmt, &mut fmt_offset, endian)?;
+    let byterate = parse_u32(fmt, &mut fmt_offset, endian)?;
+    let block_align = parse_u16(fmt, &mut fmt_offset, endian)?;
+    let bits_per_sample = parse_u16(fmt, &mut fmt_offset, endian)?;
+
+    // the extension size is followed by that many bytes
+    let fmt_extension = match fmt.get(16..18) {
+        Some(_) => {
+            let extension_size = parse_u16(fmt, &mut fmt_offset, endian)? as usize;
+            fmt[18..]
+                .get(..extension_size)
+                .unwrap_or(&fmt[18..])
+                .to_vec()
+        }
+        None => vec![],
+    };
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        match fmt_extension.get(6..22) {
+            Some(subformat) if subformat[2..] == SUBFORMAT_GUID_SUFFIX => {
+                format_tag = u16::from_le_bytes([subformat[0], subformat[1]]);
+            }
+            _ => return Err(OndaError::BadFmtChunk("unknown extensible subformat")),
+        }
+    }
+
+    find_chunk(buf, &mut offset, endian, b"data")?;
+    let data = parse_data_chunk(buf, &mut offset, endian, data_size)?;
+
+    Ok(EncodedWav {
+        format_tag,
+        num_channels,
+        samplerate,
+        byterate,
+        block_align,
+        bits_per_sample,
+        fmt_extension,
+        data: data.to_vec(),
+    })
+}
+
+/// Reads a WAV file from the provided path without decoding its samples.
+pub fn read_encoded(path: impl AsRef<Path>) -> Result<EncodedWav> {
+    parse_encoded(read_file(path)?)
+}
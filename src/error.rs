@@ -0,0 +1,281 @@
+//! The error type of onda, so callers can tell failure modes apart.
+
+use std::fmt;
+use std::io;
+
+/// Shorthand for results whose error is [`OndaError`].
+pub type Result<T, E = OndaError> = std::result::Result<T, E>;
+
+/// Reasons reading, writing or editing audio can fail. Chunk IDs are the four bytes as
+/// they appear in the file, e.g. `*b"fmt "`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum OndaError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// The data does not start with a RIFF, RIFX, RF64 or BW64 header.
+    NotRiff,
+    /// The RIFF form type is not WAVE.
+    NotWave,
+    /// The data is not in the named container format, e.g. `"AIFF"`.
+    NotFormat(&'static str),
+    /// A header or chunk at `offset` needs `needed` bytes, which the input does not hold.
+    Truncated { offset: u64, needed: u64 },
+    /// A required chunk was not found.
+    MissingChunk { id: [u8; 4] },
+    /// A chunk is too small for its contents.
+    BadChunkSize { id: [u8; 4] },
+    /// The fmt chunk is malformed, or its fields contradict each other.
+    BadFmtChunk(&'static str),
+    /// The audio format tag, encoding or format ID of the file is not supported. CAF
+    /// format IDs are stored as big-endian numbers.
+    UnsupportedCodec { tag: u32 },
+    /// The bit depth of the samples is not supported.
+    UnsupportedBitDepth(u32),
+    /// The number of channels is not supported.
+    UnsupportedChannels(u32),
+    /// The file or audio data has no channels.
+    NoChannels,
+    /// A feature of the file is not supported, e.g. compressed big-endian samples.
+    Unsupported(&'static str),
+    /// The audio data contradicts the header, or is otherwise corrupt.
+    Malformed(&'static str),
+    /// The samples cannot be decoded into the requested sample type.
+    SampleTypeMismatch { float: bool, bits_per_sample: u16 },
+    /// The audio data has a different number of channels than expected.
+    ChannelMismatch { expected: usize, found: usize },
+    /// A sample does not fit in the given bit depth.
+    SampleOutOfRange { bits_per_sample: u16 },
+    /// The audio data or metadata does not fit in the named field or container.
+    TooLarge(&'static str),
+    /// A text field of a metadata chunk is longer than its fixed size.
+    TextTooLong {
+        chunk: &'static str,
+        field: &'static str,
+        max_len: usize,
+    },
+    /// A custom chunk has the ID of a chunk that onda writes itself.
+    ReservedChunk { id: [u8; 4] },
+    /// An argument is out of its valid range.
+    InvalidArgument(&'static str),
+    /// Decoding a FLAC stream failed.
+    #[cfg(feature = "flac")]
+    Flac(claxon::Error),
+    /// Decoding an Ogg Vorbis stream failed.
+    #[cfg(feature = "ogg")]
+    Ogg(lewton::VorbisError),
+    /// Decoding an MP3 stream failed.
+    #[cfg(feature = "mp3")]
+    Mp3(minimp3::Error),
+    /// Reading or writing an ID3v2 tag failed.
+    #[cfg(feature = "id3")]
+    Id3(id3::Error),
+    /// Reading samples through hound failed.
+    #[cfg(feature = "hound")]
+    Hound(hound::Error),
+    /// Opening or running a cpal audio stream failed.
+    #[cfg(feature = "cpal")]
+    Cpal(Box<dyn std::error::Error + Send + Sync>),
+    /// Frames were dropped while recording, because the file could not keep up.
+    #[cfg(feature = "cpal")]
+    DroppedFrames(u64),
+    /// Decoding a stream through Symphonia failed.
+    #[cfg(feature = "symphonia")]
+    Symphonia(symphonia::core::errors::Error),
+    /// Building an Arrow record batch failed.
+    #[cfg(feature = "arrow")]
+    Arrow(arrow_schema::ArrowError),
+    /// Setting up or running the sinc resampler of rubato failed.
+    #[cfg(feature = "hq-resample")]
+    Resample(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for OndaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::NotRiff => write!(f, "not a RIFF file"),
+            Self::NotWave => write!(f, "not a WAVE file"),
+            Self::NotFormat(format) => write!(f, "not in {format} format"),
+            Self::Truncated { offset, needed } => {
+                write!(f, "{needed} bytes at offset {offset} exceed file size")
+            }
+            Self::MissingChunk { id } => write!(f, "{} chunk not found", chunk_name(id)),
+            Self::BadChunkSize { id } => write!(f, "{} chunk wrong size", chunk_name(id)),
+            Self::BadFmtChunk(reason) => write!(f, "bad fmt chunk: {reason}"),
+            Self::UnsupportedCodec { tag } => write!(f, "unsupported audio format: {tag}"),
+            Self::UnsupportedBitDepth(bits) => write!(f, "unsupported bit depth: {bits}"),
+            Self::UnsupportedChannels(n) => write!(f, "unsupported number of channels: {n}"),
+            Self::NoChannels => write!(f, "audio data has no channels"),
+            Self::Unsupported(feature) => write!(f, "{feature} not supported"),
+            Self::Malformed(reason) => write!(f, "malformed file: {reason}"),
+            Self::SampleTypeMismatch {
+                float,
+                bits_per_sample,
+            } => {
+                let kind = if *float { "float" } else { "integer" };
+                write!(
+                    f,
+                    "{bits_per_sample}-bit {kind} samples do not fit the requested sample type"
+                )
+            }
+            Self::ChannelMismatch { expected, found } => {
+                write!(f, "audio data has {found} channels, not {expected}")
+            }
+            Self::SampleOutOfRange { bits_per_sample } => {
+                write!(f, "sample out of range for {bits_per_sample}-bit audio")
+            }
+            Self::TooLarge(what) => write!(f, "{what} too large"),
+            Self::TextTooLong {
+                chunk,
+                field,
+                max_len,
+            } => write!(f, "{chunk} {field} longer than {max_len} bytes"),
+            Self::ReservedChunk { id } => {
+                write!(f, "{} chunk is written by onda", chunk_name(id))
+            }
+            Self::InvalidArgument(reason) => write!(f, "invalid argument: {reason}"),
+            #[cfg(feature = "flac")]
+            Self::Flac(e) => write!(f, "{e}"),
+            #[cfg(feature = "ogg")]
+            Self::Ogg(e) => write!(f, "{e}"),
+            #[cfg(feature = "mp3")]
+            Self::Mp3(e) => write!(f, "{e}"),
+            #[cfg(feature = "id3")]
+            Self::Id3(e) => write!(f, "{e}"),
+            #[cfg(feature = "hound")]
+            Self::Hound(e) => write!(f, "{e}"),
+            #[cfg(feature = "cpal")]
+            Self::Cpal(e) => write!(f, "{e}"),
+            #[cfg(feature = "cpal")]
+            Self::DroppedFrames(n) => write!(f, "{n} frames dropped while recording"),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(e) => write!(f, "{e}"),
+            #[cfg(feature = "arrow")]
+            Self::Arrow(e) => write!(f, "{e}"),
+            #[cfg(feature = "hq-resample")]
+            Self::Resample(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for OndaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "flac")]
+            Self::Flac(e) => Some(e),
+            #[cfg(feature = "ogg")]
+            Self::Ogg(e) => Some(e),
+            #[cfg(feature = "mp3")]
+            Self::Mp3(e) => Some(e),
+            #[cfg(feature = "id3")]
+            Self::Id3(e) => Some(e),
+            #[cfg(feature = "hound")]
+            Self::Hound(e) => Some(e),
+            #[cfg(feature = "cpal")]
+            Self::Cpal(e) => Some(e.as_ref()),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(e) => Some(e),
+            #[cfg(feature = "arrow")]
+            Self::Arrow(e) => Some(e),
+            #[cfg(feature = "hq-resample")]
+            Self::Resample(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for OndaError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "flac")]
+impl From<claxon::Error> for OndaError {
+    fn from(e: claxon::Error) -> Self {
+        Self::Flac(e)
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl From<lewton::VorbisError> for OndaError {
+    fn from(e: lewton::VorbisError) -> Self {
+        Self::Ogg(e)
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl From<minimp3::Error> for OndaError {
+    fn from(e: minimp3::Error) -> Self {
+        Self::Mp3(e)
+    }
+}
+
+#[cfg(feature = "id3")]
+impl From<id3::Error> for OndaError {
+    fn from(e: id3::Error) -> Self {
+        Self::Id3(e)
+    }
+}
+
+#[cfg(feature = "hound")]
+impl From<hound::Error> for OndaError {
+    fn from(e: hound::Error) -> Self {
+        Self::Hound(e)
+    }
+}
+
+macro_rules! impl_from_cpal_error {
+    ($($error:ty),*) => {
+        $(
+            #[cfg(feature = "cpal")]
+            impl From<$error> for OndaError {
+                fn from(e: $error) -> Self {
+                    Self::Cpal(Box::new(e))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_cpal_error!(
+    cpal::DefaultStreamConfigError,
+    cpal::BuildStreamError,
+    cpal::PlayStreamError,
+    cpal::StreamError
+);
+
+#[cfg(feature = "symphonia")]
+impl From<symphonia::core::errors::Error> for OndaError {
+    fn from(e: symphonia::core::errors::Error) -> Self {
+        Self::Symphonia(e)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow_schema::ArrowError> for OndaError {
+    fn from(e: arrow_schema::ArrowError) -> Self {
+        Self::Arrow(e)
+    }
+}
+
+#[cfg(feature = "hq-resample")]
+impl From<rubato::ResamplerConstructionError> for OndaError {
+    fn from(e: rubato::ResamplerConstructionError) -> Self {
+        Self::Resample(Box::new(e))
+    }
+}
+
+#[cfg(feature = "hq-resample")]
+impl From<rubato::ResampleError> for OndaError {
+    fn from(e: rubato::ResampleError) -> Self {
+        Self::Resample(Box::new(e))
+    }
+}
+
+/// Chunk ID without its trailing padding spaces, for error messages.
+fn chunk_name(id: &[u8; 4]) -> String {
+    String::from_utf8_lossy(id).trim_end().to_string()
+}
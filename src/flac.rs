@@ -0,0 +1,54 @@
+//! FLAC support through claxon. Decoded samples are packed into the layout of a WAV data
+//! chunk so they share the WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{SampleFormat, Spec};
+use std::borrow::Cow;
+
+/// Decodes a FLAC file and returns its spec along with the samples, in the layout of a WAV
+/// data chunk.
+pub(crate) fn parse_flac(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    let mut reader = claxon::FlacReader::new(buf)?;
+    let info = reader.streaminfo();
+
+    // samples are stored left-justified in whole bytes
+    let valid_bits_per_sample = info.bits_per_sample as u16;
+    let bits_per_sample = valid_bits_per_sample.next_multiple_of(8);
+    let shift = bits_per_sample - valid_bits_per_sample;
+    let sample_size = bits_per_sample as usize / 8;
+
+    if info.channels == 0 || info.channels > u16::MAX as u32 {
+        return Err(OndaError::UnsupportedChannels(info.channels));
+    }
+    let num_channels = info.channels as u16;
+
+    let capacity = info.samples.unwrap_or(0) as usize * num_channels as usize * sample_size;
+    let mut data = Vec::with_capacity(capacity);
+
+    for sample in reader.samples() {
+        let sample = sample? << shift;
+
+        // 8-bit samples are stored as offset binary
+        match bits_per_sample {
+            8 => data.push(sample as u8 ^ 0x80),
+            _ => data.extend_from_slice(&sample.to_le_bytes()[..sample_size]),
+        }
+    }
+
+    let spec = Spec {
+        format: SampleFormat::Int,
+        num_channels,
+        samplerate: info.sample_rate,
+        block_align: num_channels * bits_per_sample / 8,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    Ok((spec, Cow::Owned(data)))
+}
@@ -0,0 +1,94 @@
+//! Iteration over the frames of audio data, whatever its number of channels.
+
+use crate::WavData;
+use std::ops::Index;
+
+/// A frame of [`WavData`], holding one sample per channel, borrowed from its channels.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'a, S> {
+    audiodata: &'a [Vec<S>],
+    index: usize,
+}
+
+impl<'a, S: Copy> Frame<'a, S> {
+    /// Number of samples, i.e. channels.
+    pub fn len(&self) -> usize {
+        self.audiodata.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.audiodata.is_empty()
+    }
+
+    /// Sample of channel `channel`, or `None` if there is no such channel.
+    pub fn get(&self, channel: usize) -> Option<S> {
+        self.audiodata
+            .get(channel)
+            .map(|samples| samples[self.index])
+    }
+
+    /// Returns an iterator over the samples of each channel in turn.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = S> + 'a {
+        let index = self.index;
+        self.audiodata.iter().map(move |samples| samples[index])
+    }
+
+    pub fn to_vec(&self) -> Vec<S> {
+        self.iter().collect()
+    }
+}
+
+impl<S> Index<usize> for Frame<'_, S> {
+    type Output = S;
+
+    fn index(&self, channel: usize) -> &S {
+        &self.audiodata[channel][self.index]
+    }
+}
+
+/// Iterator over the frames of [`WavData`], returned by iterating over a reference to it.
+/// Channels longer than the shortest one are truncated, as by `frames`.
+#[derive(Clone, Debug)]
+pub struct FrameIter<'a, S> {
+    audiodata: &'a [Vec<S>],
+    index: usize,
+    num_frames: usize,
+}
+
+impl<'a, S> Iterator for FrameIter<'a, S> {
+    type Item = Frame<'a, S>;
+
+    fn next(&mut self) -> Option<Frame<'a, S>> {
+        if self.index == self.num_frames {
+            return None;
+        }
+
+        let frame = Frame {
+            audiodata: self.audiodata,
+            index: self.index,
+        };
+        self.index += 1;
+
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.num_frames - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<S> ExactSizeIterator for FrameIter<'_, S> {}
+
+impl<'a, S> IntoIterator for &'a WavData<S> {
+    type Item = Frame<'a, S>;
+    type IntoIter = FrameIter<'a, S>;
+
+    fn into_iter(self) -> FrameIter<'a, S> {
+        FrameIter {
+            audiodata: &self.audiodata,
+            index: 0,
+            num_frames: self.audiodata.iter().map(Vec::len).min().unwrap_or(0),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Level adjustment of audio data.
+
+use crate::error::{OndaError, Result};
+use crate::{Sample, SharedWavData, WavData};
+use std::f64::consts::FRAC_PI_2;
+use std::time::Duration;
+
+/// Level above which [`Overflow::SoftLimit`] bends samples towards full scale, about
+/// -2.5 dBFS.
+const SOFT_LIMIT_KNEE: f64 = 0.75;
+
+/// What [`apply_gain_db`](WavData::apply_gain_db) does with samples that the gain takes
+/// beyond full scale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Clip them to full scale.
+    Clamp,
+    /// Fail with [`OndaError::SampleOutOfRange`], leaving the audio data unchanged.
+    Error,
+    /// Compress samples above -2.5 dBFS smoothly, so they approach full scale without
+    /// reaching it. Quieter samples are only scaled.
+    SoftLimit,
+}
+
+/// Shape of the gain curve of a fade, from silence to full level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// Gain rising in proportion to time, which sounds abrupt at the quiet end.
+    Linear,
+    /// Quarter sine gain, whose fade in and fade out sum to constant power, for
+    /// crossfades between uncorrelated audio.
+    EqualPower,
+    /// Gain rising by the same number of decibels per unit of time over 60 dB, which
+    /// sounds even to the ear.
+    Exponential,
+}
+
+impl Curve {
+    /// Gain at `position` within the fade, from 0.0 at its start to 1.0 at its end.
+    fn gain(self, position: f64) -> f64 {
+        match self {
+            Curve::Linear => position,
+            Curve::EqualPower => (position * FRAC_PI_2).sin(),
+            Curve::Exponential => {
+                // 60 dB of range, shifted and scaled to start at silence
+                const RANGE: f64 = 1000.0;
+                (RANGE.powf(position) - 1.0) / (RANGE - 1.0)
+            }
+        }
+    }
+}
+
+impl<S: Sample> WavData<S> {
+    /// Scales all samples by `gain_db` decibels, e.g. -6.0 to halve the level, handling
+    /// samples taken beyond full scale as `overflow` says, rather than letting integer
+    /// samples wrap. Full scale of float samples is ±1.0.
+    pub fn apply_gain_db(&mut self, gain_db: f32, overflow: Overflow) -> Result<()> {
+        let gain = Gain::new::<S>(gain_db, overflow, self.bits_per_sample)?;
+        gain.check(self.channels())?;
+        self.channels_mut().for_each(|channel| gain.apply(channel));
+
+        Ok(())
+    }
+
+    /// Fades the start of the audio data in from silence over `duration`, shaped by
+    /// `curve`, to avoid a click where it begins. Durations longer than the audio data
+    /// fade it in its entirety.
+    pub fn fade_in(&mut self, duration: Duration, curve: Curve) {
+        let (bits, len) = (self.bits_per_sample, self.frame_at(duration));
+        for channel in self.channels_mut() {
+            fade(channel.iter_mut(), len, curve, bits);
+        }
+    }
+
+    /// Fades the end of the audio data out to silence over `duration`, shaped by `curve`
+    /// like [`fade_in`](Self::fade_in) in reverse.
+    pub fn fade_out(&mut self, duration: Duration, curve: Curve) {
+        let (bits, len) = (self.bits_per_sample, self.frame_at(duration));
+        for channel in self.channels_mut() {
+            fade(channel.iter_mut().rev(), len, curve, bits);
+        }
+    }
+
+    /// Subtracts the mean of each channel from its samples, so a channel biased by the
+    /// recording chain is centred on zero again and regains headroom. The bias is assumed
+    /// constant over the audio data, as [`stats`](Self::stats) measures it.
+    pub fn remove_dc_offset(&mut self) {
+        let bits = self.bits_per_sample;
+        self.channels_mut()
+            .for_each(|channel| remove_mean(channel, bits));
+    }
+}
+
+impl<S: Sample> SharedWavData<S> {
+    /// Scales all samples like [`WavData::apply_gain_db`], copying the channels that are
+    /// shared with a clone.
+    pub fn apply_gain_db(&mut self, gain_db: f32, overflow: Overflow) -> Result<()> {
+        let gain = Gain::new::<S>(gain_db, overflow, self.bits_per_sample)?;
+        gain.check(self.channels())?;
+        self.channels_mut().for_each(|channel| gain.apply(channel));
+
+        Ok(())
+    }
+
+    /// Fades the start in like [`WavData::fade_in`], copying the channels that are shared
+    /// with a clone.
+    pub fn fade_in(&mut self, duration: Duration, curve: Curve) {
+        let (bits, len) = (self.bits_per_sample, self.frame_at(duration));
+        for channel in self.channels_mut() {
+            fade(channel.iter_mut(), len, curve, bits);
+        }
+    }
+
+    /// Fades the end out like [`WavData::fade_out`], copying the channels that are shared
+    /// with a clone.
+    pub fn fade_out(&mut self, duration: Duration, curve: Curve) {
+        let (bits, len) = (self.bits_per_sample, self.frame_at(duration));
+        for channel in self.channels_mut() {
+            fade(channel.iter_mut().rev(), len, curve, bits);
+        }
+    }
+
+    /// Subtracts the mean of each channel like [`WavData::remove_dc_offset`], copying the
+    /// channels that are shared with a clone.
+    pub fn remove_dc_offset(&mut self) {
+        let bits = self.bits_per_sample;
+        self.channels_mut()
+            .for_each(|channel| remove_mean(channel, bits));
+    }
+}
+
+/// A gain with its overflow policy, checked once and applied channel by channel.
+struct Gain {
+    gain: f64,
+    overflow: Overflow,
+    bits: u16,
+    /// The highest positive sample, as integer samples stop one step below 1.0.
+    max: f64,
+}
+
+impl Gain {
+    fn new<S: Sample>(gain_db: f32, overflow: Overflow, bits: u16) -> Result<Self> {
+        if !gain_db.is_finite() {
+            return Err(OndaError::InvalidArgument("gain is not finite"));
+        }
+
+        Ok(Gain {
+            gain: 10f64.powf(gain_db as f64 / 20.0),
+            overflow,
+            bits,
+            max: S::from_f64(1.0, bits).to_f64(bits),
+        })
+    }
+
+    /// Fails if the policy is [`Overflow::Error`] and the gain takes a sample beyond full
+    /// scale, before any channel is changed.
+    fn check<'a, S: Sample + 'a>(&self, mut channels: impl Iterator<Item = &'a [S]>) -> Result<()> {
+        if self.overflow != Overflow::Error {
+            return Ok(());
+        }
+
+        let overflows = channels.any(|channel| {
+            channel
+                .iter()
+                .any(|s| !(-1.0..=self.max).contains(&(s.to_f64(self.bits) * self.gain)))
+        });
+        if overflows {
+            return Err(OndaError::SampleOutOfRange {
+                bits_per_sample: self.bits,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply<S: Sample>(&self, channel: &mut [S]) {
+        for sample in channel {
+            let value = sample.to_f64(self.bits) * self.gain;
+            let value = match self.overflow {
+                Overflow::Clamp | Overflow::Error => value.clamp(-1.0, self.max),
+                Overflow::SoftLimit => soft_limit(value).clamp(-1.0, self.max),
+            };
+            *sample = S::from_f64(value, self.bits);
+        }
+    }
+}
+
+/// Fades the first `len` of `samples` from silence, which are in reverse for fade outs.
+fn fade<'a, S: Sample + 'a>(
+    samples: impl Iterator<Item = &'a mut S>,
+    len: usize,
+    curve: Curve,
+    bits: u16,
+) {
+    for (i, sample) in samples.take(len).enumerate() {
+        let gain = curve.gain(i as f64 / len as f64);
+        *sample = S::from_f64(sample.to_f64(bits) * gain, bits);
+    }
+}
+
+/// Subtracts the mean of the samples from each of them.
+fn remove_mean<S: Sample>(channel: &mut [S], bits: u16) {
+    if channel.is_empty() {
+        return;
+    }
+
+    let sum: f64 = channel.iter().map(|s| s.to_f64(bits)).sum();
+    let offset = sum / channel.len() as f64;
+    for sample in channel {
+        *sample = S::from_f64(sample.to_f64(bits) - offset, bits);
+    }
+}
+
+/// Leaves values up to the knee alone and maps louder ones onto a tanh curve, which keeps
+/// the slope continuous at the knee and approaches full scale.
+fn soft_limit(value: f64) -> f64 {
+    let magnitude = value.abs();
+    if magnitude <= SOFT_LIMIT_KNEE {
+        return value;
+    }
+
+    let headroom = 1.0 - SOFT_LIMIT_KNEE;
+    let limited = SOFT_LIMIT_KNEE + headroom * ((magnitude - SOFT_LIMIT_KNEE) / headroom).tanh();
+    limited.copysign(value)
+}
@@ -0,0 +1,62 @@
+//! Conversions from and to hound types, for moving code from hound to onda piece by piece.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::{WavData, WavSpec};
+use std::io::Read;
+
+impl From<hound::WavSpec> for WavSpec {
+    fn from(spec: hound::WavSpec) -> Self {
+        Self {
+            num_channels: spec.channels,
+            samplerate: spec.sample_rate,
+            bits_per_sample: spec.bits_per_sample,
+            float: spec.sample_format == hound::SampleFormat::Float,
+        }
+    }
+}
+
+impl From<WavSpec> for hound::WavSpec {
+    fn from(spec: WavSpec) -> Self {
+        Self {
+            channels: spec.num_channels,
+            sample_rate: spec.samplerate,
+            bits_per_sample: spec.bits_per_sample,
+            sample_format: match spec.float {
+                true => hound::SampleFormat::Float,
+                false => hound::SampleFormat::Int,
+            },
+        }
+    }
+}
+
+/// Reads the remaining samples of a hound reader into [`WavData`]. Samples are converted
+/// as by `hound::WavReader::samples`, so `S` must be wide enough for the file.
+pub fn from_hound_reader<S: hound::Sample, R: Read>(
+    mut reader: hound::WavReader<R>,
+) -> Result<WavData<S>> {
+    let spec = reader.spec();
+    let num_channels = spec.channels as usize;
+    if num_channels == 0 {
+        return Err(OndaError::NoChannels);
+    }
+
+    let mut audiodata: Vec<Vec<S>> = (0..num_channels)
+        .map(|_| Vec::with_capacity(reader.len() as usize / num_channels))
+        .collect();
+    for (i, sample) in reader.samples::<S>().enumerate() {
+        audiodata[i % num_channels].push(sample?);
+    }
+
+    // hound reports the valid bits of samples stored in whole bytes
+    Ok(WavData {
+        num_channels: spec.channels,
+        samplerate: spec.sample_rate,
+        bits_per_sample: spec.bits_per_sample.next_multiple_of(8),
+        valid_bits_per_sample: spec.bits_per_sample,
+        channel_mask: 0,
+        audiodata,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    })
+}
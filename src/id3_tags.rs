@@ -0,0 +1,71 @@
+//! ID3v2 tags through the id3 crate, for the contents of `id3 ` chunks.
+
+use crate::error::Result;
+use id3::frame::{Picture, PictureType};
+use id3::{Tag, TagLike, Version};
+
+/// Common tags of an ID3v2 tag. Tags missing from it are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Id3Tags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Front cover picture, or the first picture if there is no front cover.
+    pub artwork: Option<Artwork>,
+}
+
+/// An image embedded in an ID3v2 tag.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Artwork {
+    /// MIME type of the image, e.g. `image/jpeg`.
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Parses the common tags of an ID3v2 tag, such as the contents of
+/// [`WavMetadata::id3`](crate::WavMetadata::id3).
+pub fn parse_id3(bytes: impl AsRef<[u8]>) -> Result<Id3Tags> {
+    let tag = Tag::read_from2(std::io::Cursor::new(bytes.as_ref()))?;
+
+    let artwork = tag
+        .pictures()
+        .find(|p| p.picture_type == PictureType::CoverFront)
+        .or_else(|| tag.pictures().next())
+        .map(|p| Artwork {
+            mime_type: p.mime_type.clone(),
+            data: p.data.clone(),
+        });
+
+    Ok(Id3Tags {
+        title: tag.title().map(str::to_string),
+        artist: tag.artist().map(str::to_string),
+        artwork,
+    })
+}
+
+/// Creates an ID3v2.4 tag from the given tags, to be stored in
+/// [`WavMetadata::id3`](crate::WavMetadata::id3).
+pub fn create_id3_bytes(tags: &Id3Tags) -> Result<Vec<u8>> {
+    let mut tag = Tag::new();
+
+    if let Some(title) = &tags.title {
+        tag.set_title(title);
+    }
+    if let Some(artist) = &tags.artist {
+        tag.set_artist(artist);
+    }
+    if let Some(artwork) = &tags.artwork {
+        tag.add_frame(Picture {
+            mime_type: artwork.mime_type.clone(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: artwork.data.clone(),
+        });
+    }
+
+    let mut buf = vec![];
+    tag.write_to(&mut buf, Version::Id3v24)?;
+
+    Ok(buf)
+}
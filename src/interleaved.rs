@@ -0,0 +1,155 @@
+//! Audio data with interleaved samples, the layout of the data chunk and of most audio APIs.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{
+    decode_adpcm, decode_alaw, decode_f32, decode_i16, decode_mulaw, decode_samples_f32, decode_u8,
+    parse_wav, read_file, sample_type_mismatch, ParseOptions, SampleFormat, Spec,
+};
+use crate::{Sample, WavData};
+use std::path::Path;
+
+/// WAV info and interleaved audio data. `samples` holds the samples of each frame in turn,
+/// e.g. left then right for stereo. The other fields are those of [`WavData`].
+#[derive(Clone, Debug)]
+pub struct InterleavedWavData<S = i16> {
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub bits_per_sample: u16,
+    pub valid_bits_per_sample: u16,
+    pub channel_mask: u32,
+    pub samples: Vec<S>,
+    pub metadata: WavMetadata,
+}
+
+impl<S: Copy> InterleavedWavData<S> {
+    /// Returns the samples split into one vector per channel.
+    pub fn deinterleaved(&self) -> Vec<Vec<S>> {
+        let num_channels = self.num_channels as usize;
+
+        (0..num_channels)
+            .map(|c| {
+                self.samples
+                    .chunks_exact(num_channels)
+                    .map(|frame| frame[c])
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl<S: Copy> WavData<S> {
+    /// Returns the samples of all channels interleaved frame by frame.
+    pub fn interleaved(&self) -> Vec<S> {
+        interleave(&self.audiodata)
+    }
+}
+
+impl<S: Sample> WavData<S> {
+    /// Creates audio data from interleaved samples of `num_channels` channels, like
+    /// [`new`](Self::new). The number of samples must be a multiple of `num_channels`.
+    pub fn from_interleaved(samples: &[S], num_channels: u16, samplerate: u32) -> Result<Self> {
+        if num_channels == 0 {
+            return Err(OndaError::NoChannels);
+        }
+        if !samples.len().is_multiple_of(num_channels as usize) {
+            return Err(OndaError::InvalidArgument(
+                "sample count is not a multiple of the channel count",
+            ));
+        }
+
+        let mut wavdata = Self::with_capacity(
+            samplerate,
+            num_channels,
+            samples.len() / num_channels as usize,
+        );
+        for frame in samples.chunks_exact(num_channels as usize) {
+            for (channel, &sample) in wavdata.audiodata.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+        }
+
+        Ok(wavdata)
+    }
+}
+
+/// Parses an 8 or 16-bit file from a byte slice buffer into interleaved samples, like
+/// [`parse_bytes`](crate::parse_bytes).
+pub fn parse_bytes_interleaved(buf: impl AsRef<[u8]>) -> Result<InterleavedWavData> {
+    let (spec, data) = parse_wav(buf.as_ref(), ParseOptions::default())?;
+    let samples = decode_interleaved_i16(&data, &spec)?;
+
+    Ok(interleaved_wavdata(&spec, samples))
+}
+
+/// Parses a file from a byte slice buffer into interleaved 32-bit float samples, like
+/// [`parse_bytes_f32`](crate::parse_bytes_f32).
+pub fn parse_bytes_interleaved_f32(buf: impl AsRef<[u8]>) -> Result<InterleavedWavData<f32>> {
+    let (spec, data) = parse_wav(buf.as_ref(), ParseOptions::default())?;
+
+    let samples = match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => split(&data, &spec, decode_f32),
+        _ => interleave(&decode_samples_f32(&data, &spec)?),
+    };
+
+    Ok(interleaved_wavdata(&spec, samples))
+}
+
+/// Reads an 8 or 16-bit file from the provided path into interleaved samples.
+pub fn read_interleaved(path: impl AsRef<Path>) -> Result<InterleavedWavData> {
+    parse_bytes_interleaved(read_file(path)?)
+}
+
+/// Reads a file from the provided path into interleaved 32-bit float samples.
+pub fn read_interleaved_f32(path: impl AsRef<Path>) -> Result<InterleavedWavData<f32>> {
+    parse_bytes_interleaved_f32(read_file(path)?)
+}
+
+/// Decodes the data chunk of an 8 or 16-bit file into interleaved 16-bit samples.
+pub(crate) fn decode_interleaved_i16(data: &[u8], spec: &Spec) -> Result<Vec<i16>> {
+    let samples = match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Int, 8) => split(data, spec, |b| decode_u8(b) as i16),
+        (SampleFormat::Int, 16) => split(data, spec, decode_i16),
+        (SampleFormat::Int, bits) => return Err(sample_type_mismatch(false, bits)),
+        (SampleFormat::ALaw, _) => split(data, spec, |b| decode_alaw(b[0])),
+        (SampleFormat::MuLaw, _) => split(data, spec, |b| decode_mulaw(b[0])),
+        // compressed blocks hold the samples of each channel together
+        (SampleFormat::ImaAdpcm | SampleFormat::MsAdpcm, _) => {
+            interleave(&decode_adpcm(data, spec)?)
+        }
+        (SampleFormat::Float, bits) => return Err(sample_type_mismatch(true, bits)),
+    };
+
+    Ok(samples)
+}
+
+fn interleaved_wavdata<S: Sample>(spec: &Spec, samples: Vec<S>) -> InterleavedWavData<S> {
+    let (bits_per_sample, valid_bits_per_sample) = spec.decoded_bits_as::<S>();
+
+    InterleavedWavData {
+        num_channels: spec.num_channels,
+        samplerate: spec.samplerate,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask: spec.channel_mask,
+        samples,
+        metadata: spec.metadata.clone(),
+    }
+}
+
+/// Decodes interleaved sample bytes, dropping a trailing partial frame.
+fn split<S>(data: &[u8], spec: &Spec, decode: impl Fn(&[u8]) -> S) -> Vec<S> {
+    let sample_size = spec.bits_per_sample as usize / 8;
+    let frame_size = sample_size * spec.num_channels as usize;
+    let size = data.len() / frame_size * frame_size;
+
+    data[..size].chunks_exact(sample_size).map(decode).collect()
+}
+
+fn interleave<S: Copy>(channels: &[Vec<S>]) -> Vec<S> {
+    let num_frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+
+    (0..num_frames)
+        .flat_map(|f| channels.iter().map(move |channel| channel[f]))
+        .collect()
+}
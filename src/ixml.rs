@@ -0,0 +1,98 @@
+//! A minimal model of iXML, the production metadata that field recorders embed in WAV
+//! files. Only the common elements are read, with a search for each tag rather than a full
+//! XML parser.
+
+/// Production details of an iXML document. Elements missing from the document are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IxmlInfo {
+    pub project: Option<String>,
+    pub scene: Option<String>,
+    pub take: Option<String>,
+    pub tape: Option<String>,
+    pub note: Option<String>,
+    /// Timecode rate, e.g. `25/1` or `30000/1001`.
+    pub timecode_rate: Option<String>,
+    /// Position of the first sample, in samples since midnight.
+    pub timestamp: Option<u64>,
+    pub tracks: Vec<IxmlTrack>,
+}
+
+/// A track of the iXML track list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IxmlTrack {
+    /// 1-based index of the channel in the data chunk.
+    pub channel_index: Option<u16>,
+    pub name: Option<String>,
+}
+
+/// Parses the common elements of an iXML document, such as the contents of
+/// [`WavMetadata::ixml`](crate::WavMetadata::ixml).
+pub fn parse_ixml(xml: &str) -> IxmlInfo {
+    let text = |tag| element(xml, tag).map(unescape);
+
+    // the timestamp is split into two 32-bit halves
+    let half = |tag| element(xml, tag)?.trim().parse::<u64>().ok();
+    let timestamp = match (
+        half("TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI"),
+        half("TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO"),
+    ) {
+        (Some(hi), Some(lo)) => Some(hi << 32 | lo),
+        _ => None,
+    };
+
+    let tracks = element(xml, "TRACK_LIST")
+        .map(|list| {
+            elements(list, "TRACK")
+                .map(|track| IxmlTrack {
+                    channel_index: element(track, "CHANNEL_INDEX")
+                        .and_then(|index| index.trim().parse().ok()),
+                    name: element(track, "NAME").map(unescape),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    IxmlInfo {
+        project: text("PROJECT"),
+        scene: text("SCENE"),
+        take: text("TAKE"),
+        tape: text("TAPE"),
+        note: text("NOTE"),
+        timecode_rate: text("TIMECODE_RATE"),
+        timestamp,
+        tracks,
+    }
+}
+
+/// Returns the contents of the first element with the given tag.
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    elements(xml, tag).next()
+}
+
+/// Returns the contents of the elements with the given tag, in document order.
+fn elements<'a>(xml: &'a str, tag: &str) -> impl Iterator<Item = &'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let end = start + rest[start..].find(&close)?;
+
+        let contents = &rest[start..end];
+        rest = &rest[end + close.len()..];
+        Some(contents)
+    })
+}
+
+/// Replaces the predefined XML entities with the characters they stand for.
+fn unescape(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -1,8 +1,121 @@
-//! Simple PCM-16-bit-integer only WAV file reader and writer.
+//! Simple PCM integer and IEEE float WAV file reader and writer.
 //! Spec source: http://tiny.systems/software/soundProgrammer/WavFormatDocs.pdf
 
+mod adpcm;
+mod aiff;
+#[cfg(feature = "arrow")]
+mod arrow_interop;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod au;
+mod caf;
+#[cfg(feature = "cpal")]
+mod cpal_capture;
+#[cfg(feature = "dasp")]
+mod dasp_interop;
+#[cfg(feature = "dsd")]
+mod dsd;
+mod edit;
+mod encoded;
+mod error;
+#[cfg(feature = "flac")]
+mod flac;
+mod frame;
+mod gain;
+#[cfg(feature = "hound")]
+mod hound_interop;
+#[cfg(feature = "hq-resample")]
+mod hq_resample;
+#[cfg(feature = "id3")]
+mod id3_tags;
+mod interleaved;
+mod ixml;
+mod loudness;
+mod markers;
+mod metadata;
+mod mix;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mp3")]
+mod mp3;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+mod npy;
+#[cfg(feature = "ogg")]
+mod ogg;
+mod probe;
+mod raw;
 mod read;
+mod reader;
+mod resample;
+#[cfg(feature = "rodio")]
+mod rodio_source;
+mod sample;
+#[cfg(feature = "serde")]
+mod serialize;
+mod shared;
+mod silence;
+mod stats;
+#[cfg(feature = "symphonia")]
+mod symphonia_bridge;
+mod validate;
+#[cfg(feature = "wasm-bindgen")]
+mod wasm;
 mod write;
 
+pub use aiff::*;
+#[cfg(feature = "arrow")]
+pub use arrow_interop::*;
+#[cfg(feature = "tokio")]
+pub use async_io::*;
+pub use au::*;
+#[cfg(feature = "cpal")]
+pub use cpal_capture::*;
+#[cfg(feature = "dsd")]
+pub use dsd::*;
+pub use edit::*;
+pub use encoded::*;
+pub use error::*;
+pub use frame::*;
+pub use gain::*;
+#[cfg(feature = "hound")]
+pub use hound_interop::*;
+#[cfg(feature = "id3")]
+pub use id3_tags::*;
+pub use interleaved::*;
+pub use ixml::*;
+pub use loudness::*;
+pub use markers::*;
+pub use metadata::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
+pub use npy::*;
+pub use probe::*;
+pub use raw::*;
 pub use read::*;
+pub use reader::*;
+#[cfg(feature = "rodio")]
+pub use rodio_source::*;
+pub use sample::*;
+#[cfg(feature = "serde")]
+pub use serialize::*;
+pub use shared::*;
+pub use stats::*;
+pub use validate::*;
+#[cfg(feature = "wasm-bindgen")]
+pub use wasm::*;
 pub use write::*;
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_MS_ADPCM: u16 = 2;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_ALAW: u16 = 6;
+const WAVE_FORMAT_MULAW: u16 = 7;
+const WAVE_FORMAT_IMA_ADPCM: u16 = 0x11;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Trailing 14 bytes of the KSDATAFORMAT_SUBTYPE GUIDs used by extensible fmt chunks. The
+/// first two bytes are the format tag of the subformat.
+const SUBFORMAT_GUID_SUFFIX: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
@@ -0,0 +1,279 @@
+//! Loudness measurement after ITU-R BS.1770 and EBU R128, and loudness normalization.
+
+use crate::error::{OndaError, Result};
+use crate::gain::Overflow;
+use crate::resample::check_rates;
+use crate::write::{default_channel_mask, validate_channels};
+use crate::{Sample, WavData};
+use std::f64::consts::PI;
+
+/// Gating blocks of integrated loudness last 4 steps of 100 ms, short-term blocks 30.
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+/// Blocks quieter than this are left out of both measurements.
+const ABSOLUTE_GATE: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+/// Taps per phase of the interpolation filter of the true peak meter.
+const TRUE_PEAK_TAPS: usize = 16;
+
+/// Loudness of audio data, as measured by [`loudness`](WavData::loudness).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Loudness {
+    /// Gated loudness of the whole audio data in LUFS, or negative infinity if it is
+    /// silent or shorter than 400 ms.
+    pub integrated: f32,
+    /// Spread between the quiet and loud parts in LU, following EBU Tech 3342, or 0.0 if
+    /// the audio data is shorter than 3 s.
+    pub range: f32,
+    /// Highest level of the signal between samples in dBTP, or negative infinity if it is
+    /// silent.
+    pub true_peak: f32,
+}
+
+impl<S: Sample> WavData<S> {
+    /// Measures the integrated loudness, loudness range and true peak, as EBU R128
+    /// specifies. Channels are weighted after their speaker positions in the channel mask,
+    /// or the default positions for their number if it has none, so the LFE channel is left
+    /// out and surround channels count 1.5 dB more. Channels must be of equal length.
+    pub fn loudness(&self) -> Result<Loudness> {
+        check_rates(self.samplerate, self.samplerate)?;
+        let num_frames = validate_channels(&self.audiodata)?;
+
+        // power of the K-weighted channels in each 100 ms step, and its length
+        let num_steps = num_frames * 10 / self.samplerate as usize;
+        let step_start = |step: usize| step * self.samplerate as usize / 10;
+        let mut step_power = vec![0.0; num_steps];
+        let mut true_peak: f64 = 0.0;
+        let weights = self.channel_weights();
+
+        for (channel, weight) in self.audiodata.iter().zip(weights) {
+            let samples: Vec<f64> = channel
+                .iter()
+                .map(|s| s.to_f64(self.bits_per_sample))
+                .collect();
+            true_peak = true_peak.max(peak(&samples, self.samplerate));
+            if weight == 0.0 {
+                continue;
+            }
+
+            let mut filter = KWeighting::new(self.samplerate);
+            let mut samples = samples.into_iter();
+            for (step, power) in step_power.iter_mut().enumerate() {
+                let len = step_start(step + 1) - step_start(step);
+                let sum: f64 = samples
+                    .by_ref()
+                    .take(len)
+                    .map(|s| filter.process(s).powi(2))
+                    .sum();
+                *power += weight * sum;
+            }
+        }
+
+        let blocks = |steps: usize| -> Vec<f64> {
+            (0..(num_steps + 1).saturating_sub(steps))
+                .map(|first| {
+                    let len = step_start(first + steps) - step_start(first);
+                    step_power[first..first + steps].iter().sum::<f64>() / len as f64
+                })
+                .collect()
+        };
+
+        let momentary = gate(&blocks(MOMENTARY_STEPS), INTEGRATED_RELATIVE_GATE);
+        let integrated = if momentary.is_empty() {
+            f64::NEG_INFINITY
+        } else {
+            loudness(momentary.iter().sum::<f64>() / momentary.len() as f64)
+        };
+
+        let mut short_term: Vec<f64> = gate(&blocks(SHORT_TERM_STEPS), RANGE_RELATIVE_GATE)
+            .into_iter()
+            .map(loudness)
+            .collect();
+        short_term.sort_by(f64::total_cmp);
+        let range = match short_term.len() {
+            0 => 0.0,
+            len => {
+                let percentile = |p: f64| short_term[((len - 1) as f64 * p).round() as usize];
+                percentile(0.95) - percentile(0.10)
+            }
+        };
+
+        Ok(Loudness {
+            integrated: integrated as f32,
+            range: range as f32,
+            true_peak: (20.0 * true_peak.log10()) as f32,
+        })
+    }
+
+    /// Scales the audio data to an integrated loudness of `target_lufs`, e.g. -16.0 for
+    /// podcasts or -23.0 for EBU R128 broadcast. Samples the gain takes beyond full scale
+    /// are clipped, so check the true peak of [`loudness`](Self::loudness) first, or apply
+    /// the gain with [`Overflow::SoftLimit`] yourself where that matters.
+    pub fn normalize_lufs(&mut self, target_lufs: f32) -> Result<()> {
+        let integrated = self.loudness()?.integrated;
+        if integrated == f32::NEG_INFINITY {
+            return Err(OndaError::InvalidArgument(
+                "audio data is too quiet or short to measure",
+            ));
+        }
+
+        self.apply_gain_db(target_lufs - integrated, Overflow::Clamp)
+    }
+
+    /// Weight of each channel in the sum of powers, after its speaker position.
+    fn channel_weights(&self) -> Vec<f64> {
+        const LOW_FREQUENCY: u32 = 0x8;
+        const SURROUND: u32 = 0x10 | 0x20 | 0x200 | 0x400;
+
+        let num_channels = self.audiodata.len();
+        let mask = if self.channel_mask.count_ones() as usize == num_channels {
+            self.channel_mask
+        } else {
+            default_channel_mask(num_channels as u16)
+        };
+        if mask == 0 {
+            return vec![1.0; num_channels];
+        }
+
+        (0..32)
+            .map(|bit| 1 << bit)
+            .filter(|speaker| mask & speaker != 0)
+            .map(|speaker| match speaker {
+                LOW_FREQUENCY => 0.0,
+                s if s & SURROUND != 0 => 1.41,
+                _ => 1.0,
+            })
+            .collect()
+    }
+}
+
+/// Loudness in LUFS of a weighted mean square.
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Powers of the blocks above the absolute gate and the gate relative to their mean.
+fn gate(blocks: &[f64], relative_gate: f64) -> Vec<f64> {
+    let audible: Vec<f64> = blocks
+        .iter()
+        .copied()
+        .filter(|&power| loudness(power) > ABSOLUTE_GATE)
+        .collect();
+    if audible.is_empty() {
+        return audible;
+    }
+
+    let threshold = loudness(audible.iter().sum::<f64>() / audible.len() as f64) + relative_gate;
+    audible
+        .into_iter()
+        .filter(|&power| loudness(power) > threshold)
+        .collect()
+}
+
+/// Highest magnitude of the signal oversampled to at least 176.4 kHz by windowed sinc
+/// interpolation, so peaks between samples are found.
+fn peak(samples: &[f64], samplerate: u32) -> f64 {
+    let factor = match samplerate {
+        0..96000 => 4,
+        96000..192000 => 2,
+        _ => 1,
+    };
+    let half = TRUE_PEAK_TAPS as isize / 2;
+    let kernels: Vec<Vec<f64>> = (1..factor)
+        .map(|phase| {
+            (1 - half..=half)
+                .map(|tap| {
+                    let t = tap as f64 - phase as f64 / factor as f64;
+                    let x = PI * t;
+                    // Blackman window spanning the taps
+                    let w = 2.0 * PI * (t + half as f64) / (2 * half) as f64;
+                    x.sin() / x * (0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos())
+                })
+                .collect()
+        })
+        .collect();
+
+    let sample = |i: isize| samples.get(i as usize).copied().unwrap_or(0.0);
+    let mut max = samples.iter().fold(0.0, |max: f64, s| max.max(s.abs()));
+    for i in 0..samples.len() as isize {
+        for kernel in &kernels {
+            let value: f64 = kernel
+                .iter()
+                .zip(i + 1 - half..)
+                .map(|(k, j)| k * sample(j))
+                .sum();
+            max = max.max(value.abs());
+        }
+    }
+
+    max
+}
+
+/// The K-weighting filter of BS.1770, a high shelf modelling the head followed by a high
+/// pass, as two biquads designed for the sample rate.
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(samplerate: u32) -> Self {
+        let rate = samplerate as f64;
+
+        let k = (PI * 1681.974450955533 / rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let k = (PI * 38.13547087602444 / rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        KWeighting {
+            stages: [shelf, high_pass],
+        }
+    }
+
+    fn process(&mut self, sample: f64) -> f64 {
+        self.stages
+            .iter_mut()
+            .fold(sample, |sample, stage| stage.process(sample))
+    }
+}
+
+/// A second order IIR filter in transposed direct form II.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad {
+            b,
+            a,
+            state: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
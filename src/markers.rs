@@ -0,0 +1,127 @@
+//! A single list of markers and regions over the cue, LIST(adtl) and smpl chunks.
+
+use crate::metadata::{CuePoint, SampleLoop, SamplerInfo, WavMetadata};
+
+/// A named position or range of the audio. Positions are in frames from the start of the
+/// data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    pub name: Option<String>,
+    pub start: u32,
+    /// Length of the region in frames, or 0 for a single position.
+    pub length: u32,
+    pub kind: MarkerKind,
+}
+
+/// Where a marker is stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarkerKind {
+    /// A cue point, with its name and length in the LIST(adtl) chunk.
+    #[default]
+    Cue,
+    /// A loop of the smpl chunk, with the loop type and play count of [`SampleLoop`]. Loops
+    /// are named by a cue point at their start.
+    Loop { loop_type: u32, play_count: u32 },
+}
+
+impl WavMetadata {
+    /// Returns the cue points and loops as markers, ordered by their start. Cue points that
+    /// only name a loop are merged into it.
+    pub fn markers(&self) -> Vec<Marker> {
+        let loops = self.sampler.as_ref().map_or(&[][..], |s| &s.loops);
+        let label = |id: u32| {
+            self.cue_points
+                .iter()
+                .find(|c| c.id == id)
+                .and_then(|c| c.label.clone())
+        };
+
+        let cues = self
+            .cue_points
+            .iter()
+            .filter(|c| !loops.iter().any(|l| l.cue_point_id == c.id))
+            .map(|c| Marker {
+                name: c.label.clone(),
+                start: c.sample_offset,
+                length: c.length,
+                kind: MarkerKind::Cue,
+            });
+
+        let loops = loops.iter().map(|l| Marker {
+            name: label(l.cue_point_id),
+            start: l.start,
+            // the end frame is played as well
+            length: l.end.saturating_sub(l.start) + 1,
+            kind: MarkerKind::Loop {
+                loop_type: l.loop_type,
+                play_count: l.play_count,
+            },
+        });
+
+        let mut markers: Vec<_> = cues.chain(loops).collect();
+        markers.sort_by_key(|m| m.start);
+
+        markers
+    }
+
+    /// Replaces the cue points and loops with the given markers. Cue points are numbered
+    /// from 1, and a sampler with middle C as unity note is added for loops if there is
+    /// none.
+    pub fn set_markers(&mut self, markers: &[Marker]) {
+        let mut cue_points = vec![];
+        let mut loops = vec![];
+
+        for (id, marker) in (1..).zip(markers) {
+            match marker.kind {
+                MarkerKind::Cue => cue_points.push(CuePoint {
+                    id,
+                    sample_offset: marker.start,
+                    length: marker.length,
+                    label: marker.name.clone(),
+                    note: None,
+                }),
+                MarkerKind::Loop {
+                    loop_type,
+                    play_count,
+                } => {
+                    // only named loops need a cue point
+                    let cue_point_id = match &marker.name {
+                        Some(name) => {
+                            cue_points.push(CuePoint {
+                                id,
+                                sample_offset: marker.start,
+                                label: Some(name.clone()),
+                                ..Default::default()
+                            });
+                            id
+                        }
+                        None => 0,
+                    };
+
+                    loops.push(SampleLoop {
+                        cue_point_id,
+                        loop_type,
+                        start: marker.start,
+                        end: marker.start + marker.length.max(1) - 1,
+                        play_count,
+                    });
+                }
+            }
+        }
+
+        self.cue_points = cue_points;
+        match &mut self.sampler {
+            Some(sampler) => sampler.loops = loops,
+            None if !loops.is_empty() => {
+                self.sampler = Some(SamplerInfo {
+                    midi_unity_note: 60,
+                    midi_pitch_fraction: 0,
+                    loops,
+                });
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,1391 @@
+//! Metadata chunks of RIFF files, which sit around the fmt and data chunks.
+
+use crate::error::{OndaError, Result};
+use crate::read::{parse_u16, parse_u32, Endianness, WavData};
+use crate::sample::Sample;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Text tags and other metadata of a WAV file. Tags come from the LIST(INFO) chunk, and
+/// are `None` when the file doesn't have them.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WavMetadata {
+    /// `IART` tag.
+    pub artist: Option<String>,
+    /// `INAM` tag.
+    pub title: Option<String>,
+    /// `ICRD` tag, usually a `YYYY-MM-DD` date.
+    pub creation_date: Option<String>,
+    /// `ICMT` tag.
+    pub comment: Option<String>,
+    /// `ISFT` tag, naming the software that created the file.
+    pub software: Option<String>,
+    /// `ICOP` tag.
+    pub copyright: Option<String>,
+    /// `IGNR` tag.
+    pub genre: Option<String>,
+    /// The other tags of the LIST(INFO) chunk, e.g. `IPRD` or `IENG`, with their IDs, in
+    /// the order they are stored.
+    pub other_tags: Vec<([u8; 4], String)>,
+    /// Text of the DISP chunk, which files from Windows often use for their title instead
+    /// of the `INAM` tag.
+    pub display_title: Option<String>,
+    /// Markers of the cue chunk, in the order they are stored.
+    pub cue_points: Vec<CuePoint>,
+    /// Pitch and loops of the smpl chunk, for samplers.
+    pub sampler: Option<SamplerInfo>,
+    /// Playback settings of the inst chunk.
+    pub instrument: Option<InstrumentInfo>,
+    /// Tempo and beats of the acid chunk, for loops.
+    pub acid: Option<AcidInfo>,
+    /// Broadcast Wave description of the bext chunk.
+    pub bext: Option<BextChunk>,
+    /// Radio playout settings of the cart chunk.
+    pub cart: Option<CartChunk>,
+    /// Peak of each channel from the PEAK chunk, which [`compute_peak`] creates.
+    pub peak: Option<PeakChunk>,
+    /// Raw XML document of the iXML chunk, which [`parse_ixml`](crate::parse_ixml) reads.
+    pub ixml: Option<String>,
+    /// Raw ADM XML document of the axml chunk, describing the audio objects of the file.
+    pub axml: Option<String>,
+    /// Mapping of tracks to ADM audio objects, from the chna chunk.
+    pub chna: Option<ChnaChunk>,
+    /// Raw ID3v2 tag of the id3 chunk. With the `id3` feature, `parse_id3` reads it and
+    /// `create_id3_bytes` creates one.
+    pub id3: Option<Vec<u8>>,
+    /// Chunks that onda doesn't read, kept as stored so that writing the metadata back
+    /// preserves them.
+    pub unknown_chunks: Vec<RawChunk>,
+}
+
+/// A chunk with its ID and contents, without its size or padding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawChunk {
+    /// Four-character ID of the chunk.
+    pub id: [u8; 4],
+    /// Contents of the chunk.
+    pub data: Vec<u8>,
+}
+
+/// A marker of the cue chunk. Its label and note come from the `labl` and `note` entries
+/// of the LIST(adtl) chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CuePoint {
+    /// Unique ID of the marker within the file.
+    pub id: u32,
+    /// Position of the marker, in frames from the start of the data chunk.
+    pub sample_offset: u32,
+    /// Length in frames of the region starting at the marker, from the `ltxt` entry of the
+    /// LIST(adtl) chunk, or 0 for a single position.
+    pub length: u32,
+    pub label: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Sampler settings of the smpl chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SamplerInfo {
+    /// MIDI note at which the sample plays back at its original pitch, 60 being middle C.
+    pub midi_unity_note: u8,
+    /// Fine tuning above the unity note, in units of 1/2^32 of a semitone.
+    pub midi_pitch_fraction: u32,
+    pub loops: Vec<SampleLoop>,
+}
+
+/// A loop of the smpl chunk. `start` and `end` are frame positions, and both are played.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SampleLoop {
+    /// ID of the cue point naming the loop, or 0 if there is none.
+    pub cue_point_id: u32,
+    /// 0 loops forward, 1 alternates between forward and backward, and 2 loops backward.
+    pub loop_type: u32,
+    pub start: u32,
+    pub end: u32,
+    /// Number of times the loop is played, with 0 meaning forever.
+    pub play_count: u32,
+}
+
+/// Playback settings of the inst chunk. Notes are MIDI note numbers, and the sample
+/// should play for notes and velocities within the inclusive ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstrumentInfo {
+    /// Note at which the sample plays back at its original pitch.
+    pub base_note: u8,
+    /// Pitch shift for playback, in cents.
+    pub detune: i8,
+    /// Gain for playback, in dB.
+    pub gain: i8,
+    pub low_note: u8,
+    pub high_note: u8,
+    pub low_velocity: u8,
+    pub high_velocity: u8,
+}
+
+/// Loop settings of the acid chunk.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AcidInfo {
+    /// Whether the file plays once rather than looping.
+    pub one_shot: bool,
+    /// Whether the file is time-stretched to the tempo of the project.
+    pub stretch: bool,
+    /// MIDI note of the key of the loop, if it has one.
+    pub root_note: Option<u8>,
+    pub num_beats: u32,
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+    /// Tempo in beats per minute.
+    pub tempo: f32,
+}
+
+/// Broadcast Wave description of the bext chunk, following EBU Tech 3285. Loudness values
+/// are in hundredths of LUFS, LU or dBTP, and are 0 in files prior to version 2.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BextChunk {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    /// Date in `yyyy-mm-dd` form.
+    pub origination_date: String,
+    /// Time in `hh:mm:ss` form.
+    pub origination_time: String,
+    /// Position of the first sample, in samples since midnight.
+    pub time_reference: u64,
+    pub version: u16,
+    /// SMPTE UMID, with the last 32 bytes unused for basic UMIDs.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::umid"))]
+    pub umid: [u8; 64],
+    pub loudness_value: i16,
+    pub loudness_range: i16,
+    pub max_true_peak_level: i16,
+    pub max_momentary_loudness: i16,
+    pub max_short_term_loudness: i16,
+    pub coding_history: String,
+}
+
+impl Default for BextChunk {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            time_reference: 0,
+            version: 0,
+            umid: [0; 64],
+            loudness_value: 0,
+            loudness_range: 0,
+            max_true_peak_level: 0,
+            max_momentary_loudness: 0,
+            max_short_term_loudness: 0,
+            coding_history: String::new(),
+        }
+    }
+}
+
+/// Radio playout settings of the cart chunk, following AES46. Dates are in `yyyy-mm-dd`
+/// form and times in `hh:mm:ss` form.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CartChunk {
+    /// Version of the chunk, as 4 digits like `0101` for version 1.01.
+    pub version: String,
+    pub title: String,
+    pub artist: String,
+    /// ID of the cut, the unit of audio played out.
+    pub cut_id: String,
+    pub client_id: String,
+    pub category: String,
+    pub classification: String,
+    /// Text of the last words or sound of the cut.
+    pub out_cue: String,
+    pub start_date: String,
+    pub start_time: String,
+    pub end_date: String,
+    pub end_time: String,
+    pub producer_app_id: String,
+    pub producer_app_version: String,
+    pub user_def: String,
+    /// Sample value of 0 dB on the reference meter.
+    pub level_reference: i32,
+    /// Timers in the cut, at most 8.
+    pub post_timers: Vec<CartTimer>,
+    pub url: String,
+    /// Free text, with lines ending in CR LF.
+    pub tag_text: String,
+}
+
+/// A timer of the cart chunk, such as `SEG1` for the start of a segue.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CartTimer {
+    /// Four character code of the timer's usage.
+    pub usage: [u8; 4],
+    /// Position in samples from the start of the data.
+    pub value: u32,
+}
+
+/// Peak of each channel of the PEAK chunk, for displaying waveforms without scanning the
+/// data.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeakChunk {
+    /// Time the peaks were computed, in seconds since 1970.
+    pub timestamp: u32,
+    /// Peaks in channel order.
+    pub peaks: Vec<Peak>,
+}
+
+/// The largest absolute sample of a channel.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Peak {
+    /// Value of the sample, with 1.0 being full scale.
+    pub value: f32,
+    /// Frame position of the sample.
+    pub position: u32,
+}
+
+/// Mapping of tracks to the audio track UIDs of the axml document, following ITU-R BS.2076.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChnaChunk {
+    /// Number of tracks used, which may be less than the number of channels.
+    pub num_tracks: u16,
+    /// Entries in the order of the chunk, leaving out unused ones.
+    pub audio_ids: Vec<AudioId>,
+}
+
+/// An entry of the chna chunk, linking a track to ADM elements by their IDs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioId {
+    /// Track number, starting from 1.
+    pub track_index: u16,
+    /// `audioTrackUID`, e.g. `ATU_00000001`.
+    pub uid: String,
+    /// `audioTrackFormatID`, e.g. `AT_00010001_01`.
+    pub track_ref: String,
+    /// `audioPackFormatID`, e.g. `AP_00010002`.
+    pub pack_ref: String,
+}
+
+/// Size of an entry of the chna chunk.
+const AUDIO_ID_SIZE: usize = 40;
+
+/// Size of the bext chunk without its coding history.
+const BEXT_SIZE: usize = 602;
+
+/// Size of the cart chunk without its tag text.
+const CART_SIZE: usize = 2048;
+
+const CART_TIMERS: usize = 8;
+
+/// Clipboard format of DISP chunks holding text.
+const CF_TEXT: u32 = 1;
+
+const ACID_ONE_SHOT: u32 = 0x01;
+const ACID_ROOT_NOTE: u32 = 0x02;
+const ACID_STRETCH: u32 = 0x04;
+
+impl WavMetadata {
+    /// Returns the text of an unknown chunk with the given ID, up to its first null byte.
+    /// This reads the simple text chunks that onda has no field for.
+    pub fn text_chunk(&self, id: &[u8; 4]) -> Option<String> {
+        self.unknown_chunks
+            .iter()
+            .find(|chunk| &chunk.id == id)
+            .and_then(|chunk| parse_text(&chunk.data))
+    }
+
+    /// Sets the text of an unknown chunk with the given ID, replacing the first chunk with
+    /// that ID or adding one. The text is null-terminated.
+    pub fn set_text_chunk(&mut self, id: [u8; 4], text: &str) {
+        let data = [text.as_bytes(), &[0]].concat();
+
+        match self.unknown_chunks.iter_mut().find(|chunk| chunk.id == id) {
+            Some(chunk) => chunk.data = data,
+            None => self.unknown_chunks.push(RawChunk { id, data }),
+        }
+    }
+
+    /// Returns the INFO tag IDs along with their values.
+    fn info_tags(&self) -> [(&'static [u8; 4], &Option<String>); 7] {
+        [
+            (b"IART", &self.artist),
+            (b"INAM", &self.title),
+            (b"ICRD", &self.creation_date),
+            (b"ICMT", &self.comment),
+            (b"ISFT", &self.software),
+            (b"ICOP", &self.copyright),
+            (b"IGNR", &self.genre),
+        ]
+    }
+
+    /// Returns the INFO tag IDs along with the fields they are stored in.
+    fn info_tags_mut(&mut self) -> [(&'static [u8; 4], &mut Option<String>); 7] {
+        [
+            (b"IART", &mut self.artist),
+            (b"INAM", &mut self.title),
+            (b"ICRD", &mut self.creation_date),
+            (b"ICMT", &mut self.comment),
+            (b"ISFT", &mut self.software),
+            (b"ICOP", &mut self.copyright),
+            (b"IGNR", &mut self.genre),
+        ]
+    }
+}
+
+/// Whether a chunk makes up the layout of the file rather than holding metadata. These are
+/// rebuilt by the writer, and padding is dropped.
+pub(crate) fn is_layout_chunk(id: &[u8]) -> bool {
+    matches!(
+        id,
+        b"ds64" | b"fmt " | b"fact" | b"data" | b"JUNK" | b"junk" | b"PAD " | b"FLLR"
+    )
+}
+
+/// Parts of the metadata that are stored in chunks of their own, so a file can be edited
+/// by rewriting only the chunks of the parts that changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MetadataPart {
+    Info,
+    DisplayTitle,
+    Markers,
+    Sampler,
+    Instrument,
+    Acid,
+    Bext,
+    Cart,
+    Peak,
+    Ixml,
+    Axml,
+    Chna,
+    Id3,
+    Unknown,
+}
+
+impl MetadataPart {
+    pub(crate) const ALL: [MetadataPart; 14] = [
+        MetadataPart::Info,
+        MetadataPart::DisplayTitle,
+        MetadataPart::Markers,
+        MetadataPart::Sampler,
+        MetadataPart::Instrument,
+        MetadataPart::Acid,
+        MetadataPart::Bext,
+        MetadataPart::Cart,
+        MetadataPart::Peak,
+        MetadataPart::Ixml,
+        MetadataPart::Axml,
+        MetadataPart::Chna,
+        MetadataPart::Id3,
+        MetadataPart::Unknown,
+    ];
+
+    /// The part that a chunk of a little-endian file holds, as `parse_metadata` reads it, or
+    /// `None` for layout chunks. Chunks that fail to parse are unknown.
+    pub(crate) fn of_chunk(id: &[u8], payload: &[u8]) -> Option<Self> {
+        let endian = Endianness::Little;
+        let part = match id {
+            b"LIST" if payload.starts_with(b"INFO") => MetadataPart::Info,
+            b"LIST" if payload.starts_with(b"adtl") => MetadataPart::Markers,
+            b"cue " if parse_cue_chunk(payload, endian).is_ok() => MetadataPart::Markers,
+            b"smpl" if parse_smpl_chunk(payload, endian).is_some() => MetadataPart::Sampler,
+            b"inst" if parse_inst_chunk(payload).is_some() => MetadataPart::Instrument,
+            b"acid" if parse_acid_chunk(payload, endian).is_some() => MetadataPart::Acid,
+            b"bext" if parse_bext_chunk(payload, endian).is_some() => MetadataPart::Bext,
+            b"cart" if parse_cart_chunk(payload, endian).is_some() => MetadataPart::Cart,
+            b"PEAK" if parse_peak_chunk(payload, endian).is_some() => MetadataPart::Peak,
+            b"iXML" if parse_text(payload).is_some() => MetadataPart::Ixml,
+            b"axml" if parse_text(payload).is_some() => MetadataPart::Axml,
+            b"chna" if parse_chna_chunk(payload, endian).is_some() => MetadataPart::Chna,
+            b"id3 " | b"ID3 " => MetadataPart::Id3,
+            b"DISP"
+                if payload.starts_with(&CF_TEXT.to_le_bytes())
+                    && parse_text(&payload[4..]).is_some() =>
+            {
+                MetadataPart::DisplayTitle
+            }
+            _ if is_layout_chunk(id) => return None,
+            _ => MetadataPart::Unknown,
+        };
+
+        Some(part)
+    }
+
+    /// Returns metadata holding only the fields of this part, for comparing or writing the
+    /// part on its own.
+    pub(crate) fn extract(self, metadata: &WavMetadata) -> WavMetadata {
+        let mut part = WavMetadata::default();
+        match self {
+            MetadataPart::Info => {
+                for ((_, to), (_, from)) in
+                    part.info_tags_mut().into_iter().zip(metadata.info_tags())
+                {
+                    to.clone_from(from);
+                }
+                part.other_tags.clone_from(&metadata.other_tags);
+            }
+            MetadataPart::DisplayTitle => part.display_title.clone_from(&metadata.display_title),
+            MetadataPart::Markers => part.cue_points.clone_from(&metadata.cue_points),
+            MetadataPart::Sampler => part.sampler.clone_from(&metadata.sampler),
+            MetadataPart::Instrument => part.instrument.clone_from(&metadata.instrument),
+            MetadataPart::Acid => part.acid.clone_from(&metadata.acid),
+            MetadataPart::Bext => part.bext.clone_from(&metadata.bext),
+            MetadataPart::Cart => part.cart.clone_from(&metadata.cart),
+            MetadataPart::Peak => part.peak.clone_from(&metadata.peak),
+            MetadataPart::Ixml => part.ixml.clone_from(&metadata.ixml),
+            MetadataPart::Axml => part.axml.clone_from(&metadata.axml),
+            MetadataPart::Chna => part.chna.clone_from(&metadata.chna),
+            MetadataPart::Id3 => part.id3.clone_from(&metadata.id3),
+            MetadataPart::Unknown => part.unknown_chunks.clone_from(&metadata.unknown_chunks),
+        }
+
+        part
+    }
+}
+
+/// Collects the metadata of a RIFF file by walking all its chunks. Metadata is optional,
+/// so malformed chunks end the walk rather than failing the whole file.
+pub(crate) fn parse_metadata(
+    buf: &[u8],
+    endian: Endianness,
+    ds64_size: Option<u64>,
+) -> WavMetadata {
+    let mut metadata = WavMetadata::default();
+    let mut adtl = vec![];
+
+    // skip the RIFF header
+    let mut offset = 12;
+
+    while offset + 8 <= buf.len() {
+        let id = &buf[offset..offset + 4];
+        offset += 4;
+        let Ok(size) = parse_u32(buf, &mut offset, endian) else {
+            break;
+        };
+        let size = match (size, ds64_size) {
+            // RF64 files mark sizes kept in the ds64 chunk with the maximum value
+            (u32::MAX, Some(size)) if id == b"data" => size as usize,
+            (size, _) => size as usize,
+        };
+
+        let Some(payload) = offset
+            .checked_add(size)
+            .and_then(|end| buf.get(offset..end))
+        else {
+            break;
+        };
+
+        // chunks that fail to parse are kept as stored, so that writing them back loses
+        // nothing
+        let known = match id {
+            b"LIST" if payload.starts_with(b"INFO") => {
+                parse_info_list(&payload[4..], endian, &mut metadata);
+                true
+            }
+            b"LIST" if payload.starts_with(b"adtl") => {
+                adtl.push(&payload[4..]);
+                true
+            }
+            b"cue " => match parse_cue_chunk(payload, endian) {
+                Ok(cue_points) => {
+                    metadata.cue_points = cue_points;
+                    true
+                }
+                Err(_) => false,
+            },
+            b"smpl" => store(&mut metadata.sampler, parse_smpl_chunk(payload, endian)),
+            b"inst" => store(&mut metadata.instrument, parse_inst_chunk(payload)),
+            b"acid" => store(&mut metadata.acid, parse_acid_chunk(payload, endian)),
+            b"bext" => store(&mut metadata.bext, parse_bext_chunk(payload, endian)),
+            b"cart" => store(&mut metadata.cart, parse_cart_chunk(payload, endian)),
+            b"PEAK" => store(&mut metadata.peak, parse_peak_chunk(payload, endian)),
+            b"iXML" => store(&mut metadata.ixml, parse_text(payload)),
+            b"axml" => store(&mut metadata.axml, parse_text(payload)),
+            b"chna" => store(&mut metadata.chna, parse_chna_chunk(payload, endian)),
+            b"id3 " | b"ID3 " => store(&mut metadata.id3, Some(payload.to_vec())),
+            b"DISP" if payload.starts_with(&CF_TEXT.to_le_bytes()) => {
+                store(&mut metadata.display_title, parse_text(&payload[4..]))
+            }
+            _ if is_layout_chunk(id) => true,
+            _ => false,
+        };
+
+        if !known {
+            metadata.unknown_chunks.push(RawChunk {
+                id: id.try_into().unwrap(),
+                data: payload.to_vec(),
+            });
+        }
+
+        // chunks are padded to an even size
+        offset += size + size % 2;
+    }
+
+    // labels refer to markers by ID, and may come before the cue chunk
+    for list in adtl {
+        parse_adtl_list(list, endian, &mut metadata.cue_points);
+    }
+
+    metadata
+}
+
+/// Splits the contents of a LIST chunk, after its list type, into its sub-chunks.
+fn list_items(list: &[u8], endian: Endianness) -> Vec<(&[u8], &[u8])> {
+    let mut items = vec![];
+    let mut offset = 0;
+
+    while offset + 8 <= list.len() {
+        let id = &list[offset..offset + 4];
+        offset += 4;
+        let Ok(size) = parse_u32(list, &mut offset, endian) else {
+            break;
+        };
+        let size = size as usize;
+
+        let Some(payload) = list.get(offset..offset.saturating_add(size)) else {
+            break;
+        };
+        items.push((id, payload));
+
+        offset += size + size % 2;
+    }
+
+    items
+}
+
+/// Stores a parsed chunk in its field, returning whether it parsed.
+fn store<T>(field: &mut Option<T>, parsed: Option<T>) -> bool {
+    match parsed {
+        Some(value) => {
+            *field = Some(value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Decodes a null-terminated string, or `None` if it's empty.
+fn parse_text(bytes: &[u8]) -> Option<String> {
+    let text = bytes.split(|&b| b == 0).next().unwrap_or_default();
+    (!text.is_empty()).then(|| String::from_utf8_lossy(text).into_owned())
+}
+
+/// Parses the tags of a LIST(INFO) chunk.
+fn parse_info_list(list: &[u8], endian: Endianness, metadata: &mut WavMetadata) {
+    for (id, value) in list_items(list, endian) {
+        let Some(value) = parse_text(value) else {
+            continue;
+        };
+
+        let field = metadata
+            .info_tags_mut()
+            .into_iter()
+            .find(|(tag, _)| tag[..] == *id);
+        match field {
+            Some((_, field)) => *field = Some(value),
+            None => {
+                if let Ok(id) = id.try_into() {
+                    metadata.other_tags.push((id, value));
+                }
+            }
+        }
+    }
+}
+
+/// Parses the labels and notes of a LIST(adtl) chunk into the markers they refer to.
+fn parse_adtl_list(list: &[u8], endian: Endianness, cue_points: &mut [CuePoint]) {
+    for (id, payload) in list_items(list, endian) {
+        let Ok(cue_id) = parse_u32(payload, &mut 0, endian) else {
+            continue;
+        };
+        let Some(cue_point) = cue_points.iter_mut().find(|c| c.id == cue_id) else {
+            continue;
+        };
+
+        match id {
+            b"labl" => cue_point.label = parse_text(&payload[4..]),
+            b"note" => cue_point.note = parse_text(&payload[4..]),
+            b"ltxt" => {
+                if let Ok(length) = parse_u32(payload, &mut 4, endian) {
+                    cue_point.length = length;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses the markers of a cue chunk. Only their sample offsets are kept, as files without
+/// a wave list have a single data chunk for every marker to point into.
+fn parse_cue_chunk(payload: &[u8], endian: Endianness) -> Result<Vec<CuePoint>> {
+    const CUE_POINT_SIZE: usize = 24;
+
+    let num_cue_points = parse_u32(payload, &mut 0, endian)? as usize;
+
+    payload[4..]
+        .chunks_exact(CUE_POINT_SIZE)
+        .take(num_cue_points)
+        .map(|cue_point| {
+            let mut offset = 0;
+            let id = parse_u32(cue_point, &mut offset, endian)?;
+            // ignore play order position, data chunk ID, chunk start and block start
+            offset += 16;
+            let sample_offset = parse_u32(cue_point, &mut offset, endian)?;
+
+            Ok(CuePoint {
+                id,
+                sample_offset,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Parses the pitch and loops of a smpl chunk, ignoring the manufacturer specific fields.
+fn parse_smpl_chunk(payload: &[u8], endian: Endianness) -> Option<SamplerInfo> {
+    const HEADER_SIZE: usize = 36;
+    const LOOP_SIZE: usize = 24;
+
+    if payload.len() < HEADER_SIZE {
+        return None;
+    }
+
+    // ignore manufacturer, product and sample period
+    let mut offset = 12;
+    let midi_unity_note = parse_u32(payload, &mut offset, endian).ok()?.min(127) as u8;
+    let midi_pitch_fraction = parse_u32(payload, &mut offset, endian).ok()?;
+    // ignore SMPTE format and offset
+    offset += 8;
+    let num_loops = parse_u32(payload, &mut offset, endian).ok()? as usize;
+
+    let loops = payload[HEADER_SIZE..]
+        .chunks_exact(LOOP_SIZE)
+        .take(num_loops)
+        .map(|sample_loop| {
+            let mut offset = 0;
+            let cue_point_id = parse_u32(sample_loop, &mut offset, endian).ok()?;
+            let loop_type = parse_u32(sample_loop, &mut offset, endian).ok()?;
+            let start = parse_u32(sample_loop, &mut offset, endian).ok()?;
+            let end = parse_u32(sample_loop, &mut offset, endian).ok()?;
+            // ignore fraction
+            offset += 4;
+            let play_count = parse_u32(sample_loop, &mut offset, endian).ok()?;
+
+            Some(SampleLoop {
+                cue_point_id,
+                loop_type,
+                start,
+                end,
+                play_count,
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    Some(SamplerInfo {
+        midi_unity_note,
+        midi_pitch_fraction,
+        loops,
+    })
+}
+
+fn parse_inst_chunk(payload: &[u8]) -> Option<InstrumentInfo> {
+    let &[base_note, detune, gain, low_note, high_note, low_velocity, high_velocity, ..] = payload
+    else {
+        return None;
+    };
+
+    Some(InstrumentInfo {
+        base_note,
+        detune: detune as i8,
+        gain: gain as i8,
+        low_note,
+        high_note,
+        low_velocity,
+        high_velocity,
+    })
+}
+
+fn parse_acid_chunk(payload: &[u8], endian: Endianness) -> Option<AcidInfo> {
+    const CHUNKSIZE: usize = 24;
+
+    if payload.len() < CHUNKSIZE {
+        return None;
+    }
+
+    let mut offset = 0;
+    let flags = parse_u32(payload, &mut offset, endian).ok()?;
+    let root_note = parse_u16(payload, &mut offset, endian).ok()?;
+    // ignore two unknown fields
+    offset += 6;
+    let num_beats = parse_u32(payload, &mut offset, endian).ok()?;
+    let meter_denominator = parse_u16(payload, &mut offset, endian).ok()?;
+    let meter_numerator = parse_u16(payload, &mut offset, endian).ok()?;
+    let tempo = f32::from_bits(parse_u32(payload, &mut offset, endian).ok()?);
+
+    Some(AcidInfo {
+        one_shot: flags & ACID_ONE_SHOT != 0,
+        stretch: flags & ACID_STRETCH != 0,
+        root_note: (flags & ACID_ROOT_NOTE != 0).then_some(root_note.min(127) as u8),
+        num_beats,
+        meter_denominator,
+        meter_numerator,
+        tempo,
+    })
+}
+
+fn parse_bext_chunk(payload: &[u8], endian: Endianness) -> Option<BextChunk> {
+    if payload.len() < BEXT_SIZE {
+        return None;
+    }
+
+    let mut offset = 0;
+    let mut text = |len: usize| {
+        let text = parse_text(&payload[offset..offset + len]).unwrap_or_default();
+        offset += len;
+        text
+    };
+
+    let description = text(256);
+    let originator = text(32);
+    let originator_reference = text(32);
+    let origination_date = text(10);
+    let origination_time = text(8);
+
+    let time_reference_low = parse_u32(payload, &mut offset, endian).ok()? as u64;
+    let time_reference_high = parse_u32(payload, &mut offset, endian).ok()? as u64;
+    let version = parse_u16(payload, &mut offset, endian).ok()?;
+
+    let mut umid = [0; 64];
+    umid.copy_from_slice(&payload[offset..offset + 64]);
+    offset += 64;
+
+    let mut loudness = || parse_u16(payload, &mut offset, endian).map(|value| value as i16);
+    let loudness_value = loudness().ok()?;
+    let loudness_range = loudness().ok()?;
+    let max_true_peak_level = loudness().ok()?;
+    let max_momentary_loudness = loudness().ok()?;
+    let max_short_term_loudness = loudness().ok()?;
+
+    Some(BextChunk {
+        description,
+        originator,
+        originator_reference,
+        origination_date,
+        origination_time,
+        time_reference: time_reference_high << 32 | time_reference_low,
+        version,
+        umid,
+        loudness_value,
+        loudness_range,
+        max_true_peak_level,
+        max_momentary_loudness,
+        max_short_term_loudness,
+        coding_history: parse_text(&payload[BEXT_SIZE..]).unwrap_or_default(),
+    })
+}
+
+fn parse_cart_chunk(payload: &[u8], endian: Endianness) -> Option<CartChunk> {
+    if payload.len() < CART_SIZE {
+        return None;
+    }
+
+    let mut offset = 0;
+    let mut text = |len: usize| {
+        let text = parse_text(&payload[offset..offset + len]).unwrap_or_default();
+        offset += len;
+        text
+    };
+
+    let version = text(4);
+    let title = text(64);
+    let artist = text(64);
+    let cut_id = text(64);
+    let client_id = text(64);
+    let category = text(64);
+    let classification = text(64);
+    let out_cue = text(64);
+    let start_date = text(10);
+    let start_time = text(8);
+    let end_date = text(10);
+    let end_time = text(8);
+    let producer_app_id = text(64);
+    let producer_app_version = text(64);
+    let user_def = text(64);
+
+    let level_reference = parse_u32(payload, &mut offset, endian).ok()? as i32;
+
+    // unused timers have no usage
+    let mut post_timers = vec![];
+    for _ in 0..CART_TIMERS {
+        let mut usage = [0; 4];
+        usage.copy_from_slice(&payload[offset..offset + 4]);
+        offset += 4;
+        let value = parse_u32(payload, &mut offset, endian).ok()?;
+
+        if usage != [0; 4] {
+            post_timers.push(CartTimer { usage, value });
+        }
+    }
+
+    // skip the reserved bytes
+    offset += 276;
+
+    Some(CartChunk {
+        version,
+        title,
+        artist,
+        cut_id,
+        client_id,
+        category,
+        classification,
+        out_cue,
+        start_date,
+        start_time,
+        end_date,
+        end_time,
+        producer_app_id,
+        producer_app_version,
+        user_def,
+        level_reference,
+        post_timers,
+        url: parse_text(&payload[offset..offset + 1024]).unwrap_or_default(),
+        tag_text: parse_text(&payload[CART_SIZE..]).unwrap_or_default(),
+    })
+}
+
+pub(crate) fn parse_peak_chunk(payload: &[u8], endian: Endianness) -> Option<PeakChunk> {
+    const HEADER_SIZE: usize = 8;
+    const PEAK_SIZE: usize = 8;
+
+    if payload.len() < HEADER_SIZE {
+        return None;
+    }
+
+    // skip the version
+    let mut offset = 4;
+    let timestamp = parse_u32(payload, &mut offset, endian).ok()?;
+
+    let num_peaks = (payload.len() - HEADER_SIZE) / PEAK_SIZE;
+    let peaks = (0..num_peaks)
+        .map(|_| {
+            Some(Peak {
+                value: f32::from_bits(parse_u32(payload, &mut offset, endian).ok()?),
+                position: parse_u32(payload, &mut offset, endian).ok()?,
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    Some(PeakChunk { timestamp, peaks })
+}
+
+fn parse_chna_chunk(payload: &[u8], endian: Endianness) -> Option<ChnaChunk> {
+    const HEADER_SIZE: usize = 4;
+
+    if payload.len() < HEADER_SIZE {
+        return None;
+    }
+
+    let mut offset = 0;
+    let num_tracks = parse_u16(payload, &mut offset, endian).ok()?;
+    let num_uids = parse_u16(payload, &mut offset, endian).ok()? as usize;
+
+    // entries with a track index of 0 are unused
+    let audio_ids = payload[HEADER_SIZE..]
+        .chunks_exact(AUDIO_ID_SIZE)
+        .take(num_uids)
+        .filter_map(|entry| {
+            let text =
+                |range: std::ops::Range<usize>| parse_text(&entry[range]).unwrap_or_default();
+            let track_index = parse_u16(entry, &mut 0, endian).ok()?;
+
+            (track_index != 0).then(|| AudioId {
+                track_index,
+                uid: text(2..14),
+                track_ref: text(14..28),
+                pack_ref: text(28..39),
+            })
+        })
+        .collect();
+
+    Some(ChnaChunk {
+        num_tracks,
+        audio_ids,
+    })
+}
+
+/// Computes the peak of each channel of audio data of any sample type, to write as a PEAK
+/// chunk with [`write_with_metadata`](crate::write_with_metadata). Integer samples are
+/// taken to use the whole range of their type, so use [`WavData::compute_peak`] for e.g.
+/// 24-bit samples held in `i32`. The timestamp is the current time.
+pub fn compute_peak<S: Sample, C: AsRef<[S]>>(audiodata: impl AsRef<[C]>) -> PeakChunk {
+    peak_chunk(audiodata.as_ref(), S::BITS)
+}
+
+impl<S: Sample> WavData<S> {
+    /// Computes the peak of each channel like [`compute_peak`], with samples of the bit
+    /// depth of the audio data.
+    pub fn compute_peak(&self) -> PeakChunk {
+        peak_chunk(&self.audiodata, self.bits_per_sample)
+    }
+}
+
+fn peak_chunk<S: Sample, C: AsRef<[S]>>(audiodata: &[C], bits_per_sample: u16) -> PeakChunk {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as u32);
+
+    let peaks = audiodata
+        .iter()
+        .map(|channel| {
+            let (position, value) = channel
+                .as_ref()
+                .iter()
+                .map(|sample| sample.to_f64(bits_per_sample).abs())
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap_or_default();
+
+            Peak {
+                value: value as f32,
+                position: position as u32,
+            }
+        })
+        .collect();
+
+    PeakChunk { timestamp, peaks }
+}
+
+/// Serializes the metadata that precedes the fmt chunk, which is the bext chunk of
+/// Broadcast Wave files.
+pub(crate) fn build_leading_chunks(metadata: &WavMetadata) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+
+    if let Some(bext) = &metadata.bext {
+        write_bext_chunk(&mut buf, bext)?;
+    }
+
+    Ok(buf)
+}
+
+/// Serializes the metadata into the chunks that follow the data chunk: LIST(INFO) for the
+/// tags, DISP, cue and LIST(adtl) for the markers, then smpl, inst, acid, cart, PEAK, iXML,
+/// axml, chna, id3 and the unknown chunks. Empty chunks are left out.
+pub(crate) fn build_metadata_chunks(metadata: &WavMetadata, samplerate: u32) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+
+    write_info_list(&mut buf, metadata)?;
+    if let Some(display_title) = &metadata.display_title {
+        write_text_chunk(&mut buf, b"DISP", &CF_TEXT.to_le_bytes(), display_title);
+    }
+    write_cue_chunk(&mut buf, &metadata.cue_points)?;
+    write_adtl_list(&mut buf, &metadata.cue_points)?;
+    if let Some(sampler) = &metadata.sampler {
+        write_smpl_chunk(&mut buf, sampler, samplerate)?;
+    }
+    if let Some(instrument) = &metadata.instrument {
+        write_inst_chunk(&mut buf, instrument)?;
+    }
+    if let Some(acid) = &metadata.acid {
+        write_acid_chunk(&mut buf, acid)?;
+    }
+    if let Some(cart) = &metadata.cart {
+        write_cart_chunk(&mut buf, cart)?;
+    }
+    if let Some(peak) = &metadata.peak {
+        write_peak_chunk(&mut buf, peak)?;
+    }
+    if let Some(ixml) = &metadata.ixml {
+        write_text_chunk(&mut buf, b"iXML", &[], ixml);
+    }
+    if let Some(axml) = &metadata.axml {
+        write_text_chunk(&mut buf, b"axml", &[], axml);
+    }
+    if let Some(chna) = &metadata.chna {
+        write_chna_chunk(&mut buf, chna)?;
+    }
+    if let Some(id3) = &metadata.id3 {
+        write_raw_chunk(&mut buf, b"id3 ", id3);
+    }
+    for chunk in &metadata.unknown_chunks {
+        write_raw_chunk(&mut buf, &chunk.id, &chunk.data);
+    }
+
+    Ok(buf)
+}
+
+fn write_info_list(buf: &mut Vec<u8>, metadata: &WavMetadata) -> Result<()> {
+    let tags: Vec<_> = metadata
+        .info_tags()
+        .into_iter()
+        .filter_map(|(id, value)| Some((id, value.as_deref()?)))
+        .chain(
+            metadata
+                .other_tags
+                .iter()
+                .map(|(id, value)| (id, value.as_str())),
+        )
+        .collect();
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let start = begin_list(buf, "INFO")?;
+    for (id, value) in tags {
+        write_text_chunk(buf, id, &[], value);
+    }
+    patch_chunk_size(buf, start);
+
+    Ok(())
+}
+
+fn write_cue_chunk(buf: &mut Vec<u8>, cue_points: &[CuePoint]) -> Result<()> {
+    const CUE_POINT_SIZE: u32 = 24;
+
+    if cue_points.is_empty() {
+        return Ok(());
+    }
+
+    let chunksize = 4 + CUE_POINT_SIZE * cue_points.len() as u32;
+
+    write!(buf, "cue ")?;
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+    buf.extend_from_slice(&(cue_points.len() as u32).to_le_bytes());
+
+    for (position, cue_point) in cue_points.iter().enumerate() {
+        buf.extend_from_slice(&cue_point.id.to_le_bytes());
+        buf.extend_from_slice(&(position as u32).to_le_bytes());
+        write!(buf, "data")?;
+        // the only data chunk starts at the beginning of the data
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&cue_point.sample_offset.to_le_bytes());
+    }
+
+    Ok(())
+}
+
+fn write_adtl_list(buf: &mut Vec<u8>, cue_points: &[CuePoint]) -> Result<()> {
+    let texts: Vec<_> = cue_points
+        .iter()
+        .flat_map(|c| [(b"labl", c.id, &c.label), (b"note", c.id, &c.note)])
+        .filter_map(|(id, cue_id, text)| Some((id, cue_id, text.as_ref()?)))
+        .collect();
+    let regions: Vec<_> = cue_points.iter().filter(|c| c.length > 0).collect();
+    if texts.is_empty() && regions.is_empty() {
+        return Ok(());
+    }
+
+    let start = begin_list(buf, "adtl")?;
+    for (id, cue_id, text) in texts {
+        write_text_chunk(buf, id, &cue_id.to_le_bytes(), text);
+    }
+    for region in regions {
+        const LTXT_SIZE: u32 = 20;
+
+        write!(buf, "ltxt")?;
+        buf.extend_from_slice(&LTXT_SIZE.to_le_bytes());
+        buf.extend_from_slice(&region.id.to_le_bytes());
+        buf.extend_from_slice(&region.length.to_le_bytes());
+        write!(buf, "rgn ")?;
+        // no country, language, dialect or code page
+        buf.extend_from_slice(&[0; 8]);
+    }
+    patch_chunk_size(buf, start);
+
+    Ok(())
+}
+
+fn write_smpl_chunk(buf: &mut Vec<u8>, sampler: &SamplerInfo, samplerate: u32) -> Result<()> {
+    const HEADER_SIZE: u32 = 36;
+    const LOOP_SIZE: u32 = 24;
+
+    let chunksize = HEADER_SIZE + LOOP_SIZE * sampler.loops.len() as u32;
+    let sample_period = 1_000_000_000 / samplerate.max(1);
+
+    write!(buf, "smpl")?;
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+    // no manufacturer or product
+    buf.extend_from_slice(&[0; 8]);
+    buf.extend_from_slice(&sample_period.to_le_bytes());
+    buf.extend_from_slice(&(sampler.midi_unity_note as u32).to_le_bytes());
+    buf.extend_from_slice(&sampler.midi_pitch_fraction.to_le_bytes());
+    // no SMPTE format or offset
+    buf.extend_from_slice(&[0; 8]);
+    buf.extend_from_slice(&(sampler.loops.len() as u32).to_le_bytes());
+    // no sampler specific data
+    buf.extend_from_slice(&0u32.to_le_bytes());
+
+    for sample_loop in &sampler.loops {
+        buf.extend_from_slice(&sample_loop.cue_point_id.to_le_bytes());
+        buf.extend_from_slice(&sample_loop.loop_type.to_le_bytes());
+        buf.extend_from_slice(&sample_loop.start.to_le_bytes());
+        buf.extend_from_slice(&sample_loop.end.to_le_bytes());
+        // no fraction
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&sample_loop.play_count.to_le_bytes());
+    }
+
+    Ok(())
+}
+
+fn write_inst_chunk(buf: &mut Vec<u8>, instrument: &InstrumentInfo) -> Result<()> {
+    const CHUNKSIZE: u32 = 7;
+
+    write!(buf, "inst")?;
+    buf.extend_from_slice(&CHUNKSIZE.to_le_bytes());
+    buf.extend_from_slice(&[
+        instrument.base_note,
+        instrument.detune as u8,
+        instrument.gain as u8,
+        instrument.low_note,
+        instrument.high_note,
+        instrument.low_velocity,
+        instrument.high_velocity,
+    ]);
+    // pad to an even size
+    buf.push(0);
+
+    Ok(())
+}
+
+fn write_acid_chunk(buf: &mut Vec<u8>, acid: &AcidInfo) -> Result<()> {
+    const CHUNKSIZE: u32 = 24;
+
+    let mut flags = 0;
+    if acid.one_shot {
+        flags |= ACID_ONE_SHOT;
+    }
+    if acid.root_note.is_some() {
+        flags |= ACID_ROOT_NOTE;
+    }
+    if acid.stretch {
+        flags |= ACID_STRETCH;
+    }
+
+    write!(buf, "acid")?;
+    buf.extend_from_slice(&CHUNKSIZE.to_le_bytes());
+    buf.extend_from_slice(&flags.to_le_bytes());
+    buf.extend_from_slice(&(acid.root_note.unwrap_or(0) as u16).to_le_bytes());
+    // unknown fields, with the values ACID itself writes
+    buf.extend_from_slice(&0x8000u16.to_le_bytes());
+    buf.extend_from_slice(&0f32.to_le_bytes());
+    buf.extend_from_slice(&acid.num_beats.to_le_bytes());
+    buf.extend_from_slice(&acid.meter_denominator.to_le_bytes());
+    buf.extend_from_slice(&acid.meter_numerator.to_le_bytes());
+    buf.extend_from_slice(&acid.tempo.to_le_bytes());
+
+    Ok(())
+}
+
+/// Writes a version 2 bext chunk, whatever the version of `bext`.
+fn write_bext_chunk(buf: &mut Vec<u8>, bext: &BextChunk) -> Result<()> {
+    const VERSION: u16 = 2;
+
+    let chunksize = (BEXT_SIZE + bext.coding_history.len()) as u32;
+
+    write!(buf, "bext")?;
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+
+    let fields = [
+        ("description", bext.description.as_str(), 256),
+        ("originator", &bext.originator, 32),
+        ("originator reference", &bext.originator_reference, 32),
+        ("origination date", &bext.origination_date, 10),
+        ("origination time", &bext.origination_time, 8),
+    ];
+    write_fixed_texts(buf, "bext", &fields)?;
+
+    buf.extend_from_slice(&(bext.time_reference as u32).to_le_bytes());
+    buf.extend_from_slice(&((bext.time_reference >> 32) as u32).to_le_bytes());
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&bext.umid);
+    for loudness in [
+        bext.loudness_value,
+        bext.loudness_range,
+        bext.max_true_peak_level,
+        bext.max_momentary_loudness,
+        bext.max_short_term_loudness,
+    ] {
+        buf.extend_from_slice(&loudness.to_le_bytes());
+    }
+    // reserved
+    buf.extend_from_slice(&[0; 180]);
+
+    buf.extend_from_slice(bext.coding_history.as_bytes());
+    if chunksize % 2 == 1 {
+        buf.push(0);
+    }
+
+    Ok(())
+}
+
+/// Writes a version 1.01 cart chunk, whatever the version of `cart`.
+fn write_cart_chunk(buf: &mut Vec<u8>, cart: &CartChunk) -> Result<()> {
+    const VERSION: &str = "0101";
+
+    if cart.post_timers.len() > CART_TIMERS {
+        return Err(OndaError::TooLarge("cart timer list"));
+    }
+
+    // the tag text is null-terminated
+    let chunksize = (CART_SIZE + cart.tag_text.len() + 1) as u32;
+
+    write!(buf, "cart")?;
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+
+    let fields = [
+        ("version", VERSION, 4),
+        ("title", &cart.title, 64),
+        ("artist", &cart.artist, 64),
+        ("cut ID", &cart.cut_id, 64),
+        ("client ID", &cart.client_id, 64),
+        ("category", &cart.category, 64),
+        ("classification", &cart.classification, 64),
+        ("out cue", &cart.out_cue, 64),
+        ("start date", &cart.start_date, 10),
+        ("start time", &cart.start_time, 8),
+        ("end date", &cart.end_date, 10),
+        ("end time", &cart.end_time, 8),
+        ("producer app ID", &cart.producer_app_id, 64),
+        ("producer app version", &cart.producer_app_version, 64),
+        ("user def", &cart.user_def, 64),
+    ];
+    write_fixed_texts(buf, "cart", &fields)?;
+
+    buf.extend_from_slice(&cart.level_reference.to_le_bytes());
+    for timer in &cart.post_timers {
+        buf.extend_from_slice(&timer.usage);
+        buf.extend_from_slice(&timer.value.to_le_bytes());
+    }
+    buf.resize(buf.len() + 8 * (CART_TIMERS - cart.post_timers.len()), 0);
+    // reserved
+    buf.extend_from_slice(&[0; 276]);
+    write_fixed_texts(buf, "cart", &[("URL", &cart.url, 1024)])?;
+
+    buf.extend_from_slice(cart.tag_text.as_bytes());
+    buf.push(0);
+    if chunksize % 2 == 1 {
+        buf.push(0);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_peak_chunk(buf: &mut Vec<u8>, peak: &PeakChunk) -> Result<()> {
+    const VERSION: u32 = 1;
+
+    let chunksize = 8 + 8 * peak.peaks.len() as u32;
+
+    write!(buf, "PEAK")?;
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&peak.timestamp.to_le_bytes());
+    for channel_peak in &peak.peaks {
+        buf.extend_from_slice(&channel_peak.value.to_le_bytes());
+        buf.extend_from_slice(&channel_peak.position.to_le_bytes());
+    }
+
+    Ok(())
+}
+
+fn write_chna_chunk(buf: &mut Vec<u8>, chna: &ChnaChunk) -> Result<()> {
+    if chna.audio_ids.len() > u16::MAX as usize {
+        return Err(OndaError::TooLarge("chna audio ID list"));
+    }
+
+    let chunksize = 4 + AUDIO_ID_SIZE * chna.audio_ids.len();
+
+    write!(buf, "chna")?;
+    buf.extend_from_slice(&(chunksize as u32).to_le_bytes());
+    buf.extend_from_slice(&chna.num_tracks.to_le_bytes());
+    buf.extend_from_slice(&(chna.audio_ids.len() as u16).to_le_bytes());
+
+    for audio_id in &chna.audio_ids {
+        buf.extend_from_slice(&audio_id.track_index.to_le_bytes());
+        let fields = [
+            ("UID", audio_id.uid.as_str(), 12),
+            ("track reference", &audio_id.track_ref, 14),
+            ("pack reference", &audio_id.pack_ref, 11),
+        ];
+        write_fixed_texts(buf, "chna", &fields)?;
+        // pad to an even size
+        buf.push(0);
+    }
+
+    Ok(())
+}
+
+/// Writes text fields of fixed sizes, padded with nulls. `chunk` names the chunk in errors.
+fn write_fixed_texts(
+    buf: &mut Vec<u8>,
+    chunk: &'static str,
+    fields: &[(&'static str, &str, usize)],
+) -> Result<()> {
+    for &(name, text, len) in fields {
+        if text.len() > len {
+            return Err(OndaError::TextTooLong {
+                chunk,
+                field: name,
+                max_len: len,
+            });
+        }
+
+        buf.extend_from_slice(text.as_bytes());
+        buf.resize(buf.len() + len - text.len(), 0);
+    }
+
+    Ok(())
+}
+
+/// Writes the header of a LIST chunk and returns its offset, for patching its size once
+/// all items are written.
+fn begin_list(buf: &mut Vec<u8>, list_type: &str) -> Result<usize> {
+    let start = buf.len();
+
+    write!(buf, "LIST")?;
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    write!(buf, "{list_type}")?;
+
+    Ok(start)
+}
+
+fn patch_chunk_size(buf: &mut [u8], start: usize) {
+    let chunksize = (buf.len() - start - 8) as u32;
+    buf[start + 4..start + 8].copy_from_slice(&chunksize.to_le_bytes());
+}
+
+/// Writes a chunk holding `data`, padded to an even size.
+fn write_raw_chunk(buf: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    buf.extend_from_slice(id);
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
+/// Writes a chunk holding `prefix` followed by a null-terminated string, padded to an even
+/// size.
+fn write_text_chunk(buf: &mut Vec<u8>, id: &[u8; 4], prefix: &[u8], text: &str) {
+    let size = (prefix.len() + text.len() + 1) as u32;
+
+    buf.extend_from_slice(id);
+    buf.extend_from_slice(&size.to_le_bytes());
+    buf.extend_from_slice(prefix);
+    buf.extend_from_slice(text.as_bytes());
+    buf.push(0);
+    if size % 2 == 1 {
+        buf.push(0);
+    }
+}
@@ -0,0 +1,156 @@
+//! Changing the number of channels of audio data.
+
+use crate::error::{OndaError, Result};
+use crate::write::validate_channels;
+use crate::{Sample, SharedWavData, WavData};
+use std::sync::Arc;
+
+impl<S: Sample> WavData<S> {
+    /// Mixes the channels down to one by averaging them, so the mix can't clip however
+    /// many channels are summed. Sound in phase in all channels keeps its level, and sound
+    /// in a single channel drops accordingly, e.g. by 6 dB from stereo. Mono audio data is
+    /// copied as is. Channels must be of equal length.
+    pub fn to_mono(&self) -> Result<WavData<S>> {
+        validate_channels(&self.audiodata)?;
+        if self.audiodata.len() == 1 {
+            return Ok(self.with_num_channels(self.audiodata.clone()));
+        }
+
+        let matrix = mono_matrix(self.audiodata.len());
+        Ok(self.with_num_channels(mix(&self.audiodata, &matrix, self.bits_per_sample)))
+    }
+
+    /// Copies mono audio data to both channels of stereo audio data, or copies stereo
+    /// audio data as is. Audio data with more channels has to be mixed down with
+    /// [`to_mono`](Self::to_mono) first.
+    pub fn to_stereo(&self) -> Result<WavData<S>> {
+        validate_channels(&self.audiodata)?;
+        match self.audiodata.as_slice() {
+            [mono] => Ok(self.with_num_channels(vec![mono.clone(), mono.clone()])),
+            [_, _] => Ok(self.with_num_channels(self.audiodata.clone())),
+            channels => Err(OndaError::UnsupportedChannels(channels.len() as u32)),
+        }
+    }
+
+    /// Mixes the channels into new ones through a gain matrix with a row per output
+    /// channel, holding the gain of each input channel, e.g. for folding 5.1 down to
+    /// stereo:
+    ///
+    /// ```text
+    /// //   L    R    C      LFE  Ls     Rs
+    /// [vec![1.0, 0.0, 0.707, 0.0, 0.707, 0.0],
+    ///  vec![0.0, 1.0, 0.707, 0.0, 0.0, 0.707]]
+    /// ```
+    ///
+    /// Integer samples are clamped to full scale where the mix is louder, while float
+    /// samples are left as they are. Channels must be of equal length.
+    pub fn remix(&self, matrix: &[Vec<f32>]) -> Result<WavData<S>> {
+        let matrix = remix_matrix(&self.audiodata, matrix)?;
+        Ok(self.with_num_channels(mix(&self.audiodata, &matrix, self.bits_per_sample)))
+    }
+
+    /// Copies the other fields for audio data with a different number of channels,
+    /// leaving the channel mask to the writer.
+    fn with_num_channels(&self, audiodata: Vec<Vec<S>>) -> WavData<S> {
+        let mut wavdata = self.with_audiodata(audiodata);
+        wavdata.num_channels = wavdata.audiodata.len() as u16;
+        wavdata.channel_mask = 0;
+        wavdata
+    }
+}
+
+impl<S: Sample> SharedWavData<S> {
+    /// Mixes the channels down to one like [`WavData::to_mono`]. Mono audio data shares
+    /// its channel with the result.
+    pub fn to_mono(&self) -> Result<SharedWavData<S>> {
+        let channels: Vec<&[S]> = self.channels().collect();
+        validate_channels(&channels)?;
+        if channels.len() == 1 {
+            return Ok(self.with_num_channels(self.audiodata.clone()));
+        }
+
+        let matrix = mono_matrix(channels.len());
+        let mono = mix(&channels, &matrix, self.bits_per_sample);
+        Ok(self.with_num_channels(mono.into_iter().map(Arc::new).collect()))
+    }
+
+    /// Turns mono audio data into stereo like [`WavData::to_stereo`], sharing the mono
+    /// channel between both channels of the result.
+    pub fn to_stereo(&self) -> Result<SharedWavData<S>> {
+        validate_channels(&self.channels().collect::<Vec<_>>())?;
+        match self.audiodata.as_slice() {
+            [mono] => Ok(self.with_num_channels(vec![mono.clone(), mono.clone()])),
+            [_, _] => Ok(self.with_num_channels(self.audiodata.clone())),
+            channels => Err(OndaError::UnsupportedChannels(channels.len() as u32)),
+        }
+    }
+
+    /// Mixes the channels into new ones through a gain matrix like [`WavData::remix`].
+    pub fn remix(&self, matrix: &[Vec<f32>]) -> Result<SharedWavData<S>> {
+        let channels: Vec<&[S]> = self.channels().collect();
+        let matrix = remix_matrix(&channels, matrix)?;
+        let audiodata = mix(&channels, &matrix, self.bits_per_sample);
+        Ok(self.with_num_channels(audiodata.into_iter().map(Arc::new).collect()))
+    }
+
+    /// Copies the other fields for audio data with a different number of channels,
+    /// leaving the channel mask to the writer.
+    fn with_num_channels(&self, audiodata: Vec<Arc<Vec<S>>>) -> SharedWavData<S> {
+        let mut shared = self.with_audiodata(audiodata);
+        shared.num_channels = shared.audiodata.len() as u16;
+        shared.channel_mask = 0;
+        shared
+    }
+}
+
+/// Gain matrix averaging all channels into one.
+fn mono_matrix(num_channels: usize) -> Vec<Vec<f64>> {
+    vec![vec![1.0 / num_channels as f64; num_channels]]
+}
+
+/// Checks a gain matrix against the channels it mixes.
+fn remix_matrix<S, C: AsRef<[S]>>(audiodata: &[C], matrix: &[Vec<f32>]) -> Result<Vec<Vec<f64>>> {
+    validate_channels(audiodata)?;
+    if matrix.is_empty() {
+        return Err(OndaError::NoChannels);
+    }
+    if matrix.len() > u16::MAX as usize {
+        return Err(OndaError::UnsupportedChannels(matrix.len() as u32));
+    }
+    if let Some(row) = matrix.iter().find(|row| row.len() != audiodata.len()) {
+        return Err(OndaError::ChannelMismatch {
+            expected: audiodata.len(),
+            found: row.len(),
+        });
+    }
+
+    Ok(matrix
+        .iter()
+        .map(|row| row.iter().map(|&gain| gain as f64).collect())
+        .collect())
+}
+
+/// Mixes channels of equal length through a checked gain matrix.
+fn mix<S: Sample, C: AsRef<[S]>>(audiodata: &[C], matrix: &[Vec<f64>], bits: u16) -> Vec<Vec<S>> {
+    let input: Vec<Vec<f64>> = audiodata
+        .iter()
+        .map(|channel| channel.as_ref().iter().map(|s| s.to_f64(bits)).collect())
+        .collect();
+    let num_frames = input.first().map_or(0, Vec::len);
+
+    matrix
+        .iter()
+        .map(|row| {
+            (0..num_frames)
+                .map(|f| {
+                    let sum: f64 = row
+                        .iter()
+                        .zip(&input)
+                        .map(|(&gain, channel)| gain * channel[f])
+                        .sum();
+                    S::from_f64(sum, bits)
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! Memory-mapped files through memmap2, whose 16-bit samples can be borrowed without
+//! copying the file.
+
+use crate::error::Result;
+use crate::interleaved::decode_interleaved_i16;
+use crate::metadata::WavMetadata;
+use crate::read::{decode_i16, parse_wav, ParseOptions, SampleFormat, Spec};
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+/// Audio file mapped into memory. The file must not be modified while it is mapped.
+pub struct MappedWav {
+    mmap: Mmap,
+    spec: Spec,
+    data: MappedData,
+}
+
+/// Samples in the layout of a WAV data chunk, either within the mapping or converted from
+/// another layout.
+enum MappedData {
+    Mapped(Range<usize>),
+    Converted(Vec<u8>),
+}
+
+impl MappedWav {
+    /// Maps the audio file at the provided path, and parses its header.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and callers must not modify the file while it
+        // is mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let (spec, data) = parse_wav(&mmap, ParseOptions::default())?;
+        let data = match data {
+            Cow::Borrowed(data) => {
+                let start = data.as_ptr() as usize - mmap.as_ptr() as usize;
+                MappedData::Mapped(start..start + data.len())
+            }
+            Cow::Owned(data) => MappedData::Converted(data),
+        };
+
+        Ok(Self { mmap, spec, data })
+    }
+
+    pub fn num_channels(&self) -> u16 {
+        self.spec.num_channels
+    }
+
+    pub fn samplerate(&self) -> u32 {
+        self.spec.samplerate
+    }
+
+    /// Bit depth of the decoded samples, which is 16 for companded and compressed files.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.spec.decoded_bits().0
+    }
+
+    pub fn metadata(&self) -> &WavMetadata {
+        &self.spec.metadata
+    }
+
+    /// Interleaved samples of an 8 or 16-bit file. Little-endian 16-bit PCM samples that
+    /// are aligned in memory are borrowed from the mapping, and other files are decoded.
+    pub fn samples(&self) -> Result<Cow<'_, [i16]>> {
+        let data = match &self.data {
+            MappedData::Mapped(range) => &self.mmap[range.clone()],
+            MappedData::Converted(data) => data,
+        };
+
+        if self.spec.format != SampleFormat::Int || self.spec.bits_per_sample != 16 {
+            return decode_interleaved_i16(data, &self.spec).map(Cow::Owned);
+        }
+
+        // drop a trailing partial frame
+        let frame_size = self.spec.num_channels as usize * 2;
+        let data = &data[..data.len() / frame_size * frame_size];
+
+        if cfg!(target_endian = "little") {
+            // SAFETY: any two bytes are a valid i16
+            let (prefix, samples, suffix) = unsafe { data.align_to::<i16>() };
+            if prefix.is_empty() && suffix.is_empty() {
+                return Ok(Cow::Borrowed(samples));
+            }
+        }
+
+        Ok(Cow::Owned(data.chunks_exact(2).map(decode_i16).collect()))
+    }
+}
@@ -0,0 +1,70 @@
+//! MP3 support through minimp3. Decoded samples are packed into the layout of a 16-bit WAV
+//! data chunk so they share the WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{SampleFormat, Spec};
+use minimp3::{Decoder, Error, Frame};
+use std::borrow::Cow;
+
+/// Checks whether a buffer starts with an ID3v2 tag or an MPEG audio frame sync.
+pub(crate) fn is_mp3(buf: &[u8]) -> bool {
+    buf.starts_with(b"ID3") || (buf.len() >= 2 && buf[0] == 0xFF && buf[1] & 0xE0 == 0xE0)
+}
+
+/// Decodes an MP3 stream and returns its spec along with the 16-bit samples, in the layout
+/// of a WAV data chunk.
+pub(crate) fn parse_mp3(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    let mut decoder = Decoder::new(buf);
+
+    let mut format = None;
+    let mut data = Vec::new();
+
+    loop {
+        let Frame {
+            data: samples,
+            sample_rate,
+            channels,
+            ..
+        } = match decoder.next_frame() {
+            Ok(frame) => frame,
+            // tags and garbage between frames are skipped
+            Err(Error::SkippedData) => continue,
+            Err(Error::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        match format {
+            None => format = Some((channels, sample_rate)),
+            Some(f) if f != (channels, sample_rate) => {
+                return Err(OndaError::Unsupported("MP3 stream format change"))
+            }
+            Some(_) => {}
+        }
+
+        for sample in samples {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    let Some((num_channels, samplerate)) = format else {
+        return Err(OndaError::Malformed("no MP3 frames found"));
+    };
+    let num_channels = num_channels as u16;
+
+    let spec = Spec {
+        format: SampleFormat::Int,
+        num_channels,
+        samplerate: samplerate as u32,
+        block_align: num_channels * 2,
+        bits_per_sample: 16,
+        valid_bits_per_sample: 16,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    Ok((spec, Cow::Owned(data)))
+}
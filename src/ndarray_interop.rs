@@ -0,0 +1,47 @@
+//! Conversions between audio data and ndarray arrays.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::WavData;
+use ndarray::{Array2, ArrayBase, Data, Ix2};
+
+impl<S: Clone> WavData<S> {
+    /// Returns the samples as an array of shape `(num_channels, num_frames)`, with a row per
+    /// channel. Channels longer than the shortest one are truncated, as by `frames`.
+    pub fn to_ndarray(&self) -> Array2<S> {
+        let num_frames = self.audiodata.iter().map(Vec::len).min().unwrap_or(0);
+
+        Array2::from_shape_fn((self.audiodata.len(), num_frames), |(channel, frame)| {
+            self.audiodata[channel][frame].clone()
+        })
+    }
+
+    /// Collects an array of shape `(num_channels, num_frames)` into audio data with the
+    /// given sample rate, like the output of [`to_ndarray`](Self::to_ndarray). The bit depth
+    /// is the size of `S`.
+    pub fn from_ndarray<D: Data<Elem = S>>(
+        array: &ArrayBase<D, Ix2>,
+        samplerate: u32,
+    ) -> Result<Self> {
+        let num_channels = array.nrows();
+        if num_channels == 0 {
+            return Err(OndaError::NoChannels);
+        }
+        if num_channels > u16::MAX as usize {
+            return Err(OndaError::UnsupportedChannels(num_channels as u32));
+        }
+
+        let bits_per_sample = (std::mem::size_of::<S>() * 8) as u16;
+
+        Ok(WavData {
+            num_channels: num_channels as u16,
+            samplerate,
+            bits_per_sample,
+            valid_bits_per_sample: bits_per_sample,
+            channel_mask: 0,
+            audiodata: array.rows().into_iter().map(|row| row.to_vec()).collect(),
+            metadata: WavMetadata::default(),
+            warnings: vec![],
+        })
+    }
+}
@@ -0,0 +1,65 @@
+//! Export of audio data as NumPy `.npy` files, for Python pipelines.
+
+use crate::error::Result;
+use crate::write::{validate_channels, write_file};
+use crate::WavData;
+use std::path::Path;
+
+/// Sample types that can be stored in `.npy` files.
+pub trait NpySample: Copy {
+    /// NumPy dtype of the samples, e.g. `<i2` for little-endian 16-bit integers.
+    const DESCR: &'static str;
+
+    fn to_le_bytes(self, buf: &mut Vec<u8>);
+}
+
+macro_rules! impl_npy_sample {
+    ($($sample:ty => $descr:literal),*) => {
+        $(
+            impl NpySample for $sample {
+                const DESCR: &'static str = $descr;
+
+                fn to_le_bytes(self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&<$sample>::to_le_bytes(self));
+                }
+            }
+        )*
+    };
+}
+
+impl_npy_sample!(i16 => "<i2", i32 => "<i4", f32 => "<f4", f64 => "<f8");
+
+/// Creates the bytes of a version 1.0 `.npy` file holding the samples as an array of
+/// shape `(num_channels, num_frames)`, with a row per channel. Channels must be of equal
+/// length.
+pub fn create_npy_bytes<S: NpySample>(data: &WavData<S>) -> Result<Vec<u8>> {
+    let num_frames = validate_channels(&data.audiodata)?;
+    let num_channels = data.audiodata.len();
+
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({num_channels}, {num_frames}), }}",
+        S::DESCR
+    );
+    // magic, version and header length take 10 bytes, and the header ends with a newline
+    // padded so the array starts at a multiple of 64 bytes
+    let len = (10 + header.len() + 1).next_multiple_of(64) - 10;
+    header.extend(std::iter::repeat_n(' ', len - header.len() - 1));
+    header.push('\n');
+
+    let sample_size = std::mem::size_of::<S>();
+    let mut buf = Vec::with_capacity(10 + len + num_channels * num_frames * sample_size);
+    buf.extend_from_slice(b"\x93NUMPY\x01\x00");
+    buf.extend_from_slice(&(len as u16).to_le_bytes());
+    buf.extend_from_slice(header.as_bytes());
+    for &sample in data.audiodata.iter().flatten() {
+        sample.to_le_bytes(&mut buf);
+    }
+
+    Ok(buf)
+}
+
+/// Writes the samples to a `.npy` file at the provided path, like [`create_npy_bytes`],
+/// e.g. to load them with `numpy.load` as int16 or float32 arrays.
+pub fn export_npy<S: NpySample>(data: &WavData<S>, path: impl AsRef<Path>) -> Result<()> {
+    write_file(&create_npy_bytes(data)?, path)
+}
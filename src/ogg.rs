@@ -0,0 +1,45 @@
+//! Ogg Vorbis support through lewton. Decoded samples are packed into the layout of a 16-bit
+//! WAV data chunk so they share the WAV decoders.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{SampleFormat, Spec};
+use lewton::inside_ogg::OggStreamReader;
+use std::borrow::Cow;
+use std::io::Cursor;
+
+/// Decodes an Ogg Vorbis file and returns its spec along with the 16-bit samples, in the
+/// layout of a WAV data chunk.
+pub(crate) fn parse_ogg(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    let mut reader = OggStreamReader::new(Cursor::new(buf))?;
+
+    let num_channels = reader.ident_hdr.audio_channels as u16;
+    let samplerate = reader.ident_hdr.audio_sample_rate;
+
+    if num_channels == 0 {
+        return Err(OndaError::NoChannels);
+    }
+
+    let mut data = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl()? {
+        for sample in packet {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    let spec = Spec {
+        format: SampleFormat::Int,
+        num_channels,
+        samplerate,
+        block_align: num_channels * 2,
+        bits_per_sample: 16,
+        valid_bits_per_sample: 16,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    Ok((spec, Cow::Owned(data)))
+}
@@ -0,0 +1,170 @@
+//! Header-only inspection of audio files, without decoding their samples.
+
+use crate::error::{OndaError, Result};
+use crate::read::{
+    parse_fmt_payload, parse_u32, parse_u64, parse_wav, Endianness, ParseOptions, Spec,
+};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Format and layout of an audio file, as reported by [`probe`]. `bits_per_sample` is the
+/// bit depth of the decoded samples, as in [`WavData`](crate::WavData).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WavInfo {
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub bits_per_sample: u16,
+    pub num_frames: u64,
+    /// Chunks of RIFF files in the order they are stored, and empty for other containers.
+    pub chunks: Vec<ChunkInfo>,
+}
+
+/// Location of a chunk within a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkInfo {
+    pub id: [u8; 4],
+    /// Offset of the chunk header from the start of the file.
+    pub offset: u64,
+    /// Size of the chunk contents, without header or padding.
+    pub size: u64,
+}
+
+impl WavInfo {
+    /// Duration of the audio data, or zero if the sample rate is 0.
+    pub fn duration(&self) -> Duration {
+        if self.samplerate == 0 {
+            return Duration::ZERO;
+        }
+
+        let samplerate = self.samplerate as u64;
+        let nanos = (self.num_frames % samplerate) * 1_000_000_000 / samplerate;
+
+        Duration::new(self.num_frames / samplerate, nanos as u32)
+    }
+}
+
+/// Reads the format and chunk layout of a file from the provided path. RIFF files are read
+/// only up to their chunk headers, while other containers are read and parsed whole.
+pub fn probe(path: impl AsRef<Path>) -> Result<WavInfo> {
+    let mut file = BufReader::new(File::open(path)?);
+    let file_size = file.get_ref().metadata()?.len();
+
+    let mut header = vec![];
+    file.by_ref().take(12).read_to_end(&mut header)?;
+    let endian = match header.get(..4) {
+        Some(b"RIFF" | b"RF64" | b"BW64") if header.len() == 12 => Endianness::Little,
+        Some(b"RIFX") if header.len() == 12 => Endianness::Big,
+        _ => {
+            let mut buf = vec![];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_to_end(&mut buf)?;
+
+            let (spec, data) = parse_wav(&buf, ParseOptions::default())?;
+            return Ok(wav_info(&spec, data.len() as u64, None, vec![]));
+        }
+    };
+    if &header[8..] != b"WAVE" {
+        return Err(OndaError::NotWave);
+    }
+
+    let mut chunks = vec![];
+    let mut spec = None;
+    let mut ds64_size = None;
+    let mut data_size = None;
+    let mut fact_frames = None;
+
+    let mut offset: u64 = 12;
+    while offset.saturating_add(8) <= file_size {
+        let mut chunk_header = [0; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut chunk_header)?;
+
+        let mut id = [0; 4];
+        id.copy_from_slice(&chunk_header[..4]);
+        let mut size = parse_u32(&chunk_header, &mut 4, endian)? as u64;
+
+        match &id {
+            b"data" => {
+                // RF64 files mark sizes kept in the ds64 chunk with the maximum value
+                if let (u32::MAX, Some(ds64_size)) = (size as u32, ds64_size) {
+                    size = ds64_size;
+                }
+                data_size = Some(size);
+            }
+            b"ds64" | b"fmt " | b"fact" => {
+                let mut payload = vec![];
+                file.by_ref().take(size).read_to_end(&mut payload)?;
+                if (payload.len() as u64) < size {
+                    return Err(OndaError::Truncated {
+                        offset: offset + 8,
+                        needed: size,
+                    });
+                }
+
+                match &id {
+                    b"ds64" if size >= 16 => ds64_size = Some(parse_u64(&payload, &mut 8, endian)?),
+                    b"fmt " => {
+                        spec = Some(parse_fmt_payload(
+                            &payload,
+                            &mut 0,
+                            size as usize,
+                            endian,
+                            ParseOptions::default(),
+                        )?)
+                    }
+                    b"fact" if size >= 4 => {
+                        fact_frames = Some(parse_u32(&payload, &mut 0, endian)? as u64);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        chunks.push(ChunkInfo { id, offset, size });
+
+        // chunks are padded to an even size
+        offset = offset.saturating_add(size).saturating_add(8 + size % 2);
+    }
+
+    let Some(spec) = spec else {
+        return Err(OndaError::MissingChunk { id: *b"fmt " });
+    };
+    let Some(data_size) = data_size else {
+        return Err(OndaError::MissingChunk { id: *b"data" });
+    };
+
+    // RF64 files may keep the frame count in the ds64 chunk as well
+    let fact_frames =
+        fact_frames.filter(|&frames| ds64_size.is_none() || frames != u32::MAX as u64);
+
+    Ok(wav_info(&spec, data_size, fact_frames, chunks))
+}
+
+fn wav_info(
+    spec: &Spec,
+    data_size: u64,
+    fact_frames: Option<u64>,
+    chunks: Vec<ChunkInfo>,
+) -> WavInfo {
+    let num_blocks = data_size / spec.block_align.max(1) as u64;
+
+    // compressed files count their frames in a fact chunk, as the last block may be padded
+    let num_frames = match (spec.frames_per_block(), fact_frames) {
+        (Some(_), Some(num_frames)) => num_frames,
+        (Some(frames_per_block), None) => num_blocks * frames_per_block as u64,
+        (None, _) => num_blocks,
+    };
+
+    WavInfo {
+        num_channels: spec.num_channels,
+        samplerate: spec.samplerate,
+        bits_per_sample: spec.decoded_bits().0,
+        num_frames,
+        chunks,
+    }
+}
@@ -0,0 +1,130 @@
+//! Headerless PCM support, for sample dumps whose layout is known up front.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::WavMetadata;
+use crate::read::{deinterleave, frame_size, wavdata, Endianness, SampleFormat, Spec, WavData};
+use crate::write::validate_channels;
+use crate::BitDepth;
+
+/// How integer samples encode their sign.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signedness {
+    /// Two's complement.
+    Signed,
+    /// Offset binary, with silence at the midpoint of the range.
+    Unsigned,
+}
+
+/// Layout of headerless PCM data read by [`parse_raw`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawSpec {
+    pub samplerate: u32,
+    pub channels: u16,
+    pub bit_depth: BitDepth,
+    pub endianness: Endianness,
+    pub signedness: Signedness,
+}
+
+/// Layout of headerless PCM data written by [`create_raw_bytes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawLayout {
+    pub bit_depth: BitDepth,
+    pub endianness: Endianness,
+    pub signedness: Signedness,
+}
+
+/// Parses interleaved headerless PCM data into 32-bit samples. Samples keep the range of
+/// their bit depth, as with [`parse_bytes_i32`](crate::parse_bytes_i32), and a trailing
+/// partial frame is ignored.
+pub fn parse_raw(buf: impl AsRef<[u8]>, raw_spec: RawSpec) -> Result<WavData<i32>> {
+    if raw_spec.channels == 0 {
+        return Err(OndaError::NoChannels);
+    }
+
+    let bits = raw_spec.bit_depth.bits();
+
+    let spec = Spec {
+        format: SampleFormat::Int,
+        num_channels: raw_spec.channels,
+        samplerate: raw_spec.samplerate,
+        block_align: frame_size(raw_spec.channels, bits)?,
+        bits_per_sample: bits,
+        valid_bits_per_sample: bits,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings: vec![],
+    };
+
+    let decode = |bytes: &[u8]| {
+        let value = match raw_spec.endianness {
+            Endianness::Little => bytes.iter().rev().fold(0, |v, &b| v << 8 | b as u32),
+            Endianness::Big => bytes.iter().fold(0, |v, &b| v << 8 | b as u32),
+        };
+
+        // move the sample to the top bits, so shifting back down extends the sign
+        let shift = 32 - bits;
+        let value = (value << shift) as i32;
+        match raw_spec.signedness {
+            Signedness::Signed => value >> shift,
+            Signedness::Unsigned => (value ^ i32::MIN) >> shift,
+        }
+    };
+
+    let audiodata = deinterleave(buf.as_ref(), &spec, decode);
+
+    Ok(wavdata(&spec, audiodata))
+}
+
+/// Creates interleaved headerless PCM bytes from integer audio data. Samples are rescaled
+/// from `bits_per_sample` to the bit depth of `layout`, dropping the low bits when
+/// narrowing.
+pub fn create_raw_bytes<S: Copy + Into<i32>>(
+    wavdata: &WavData<S>,
+    layout: RawLayout,
+) -> Result<Vec<u8>> {
+    let num_frames = validate_channels(&wavdata.audiodata)?;
+
+    let source_bits = wavdata.bits_per_sample as u32;
+    if !(1..=32).contains(&source_bits) {
+        return Err(OndaError::UnsupportedBitDepth(source_bits));
+    }
+
+    let bits = layout.bit_depth.bits() as u32;
+    let sample_size = bits as usize / 8;
+
+    let max = (1i64 << (source_bits - 1)) - 1;
+    let min = -max - 1;
+
+    let mut buf = Vec::with_capacity(num_frames * wavdata.audiodata.len() * sample_size);
+
+    for f in 0..num_frames {
+        for channel in &wavdata.audiodata {
+            let sample: i32 = channel[f].into();
+            if !(min..=max).contains(&(sample as i64)) {
+                return Err(OndaError::SampleOutOfRange {
+                    bits_per_sample: source_bits as u16,
+                });
+            }
+
+            let sample = if bits >= source_bits {
+                sample << (bits - source_bits)
+            } else {
+                sample >> (source_bits - bits)
+            };
+            let value = match layout.signedness {
+                Signedness::Signed => sample as u32,
+                Signedness::Unsigned => sample as u32 ^ 1 << (bits - 1),
+            };
+
+            let bytes = value.to_le_bytes();
+            match layout.endianness {
+                Endianness::Little => buf.extend_from_slice(&bytes[..sample_size]),
+                Endianness::Big => buf.extend(bytes[..sample_size].iter().rev()),
+            }
+        }
+    }
+
+    Ok(buf)
+}
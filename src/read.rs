@@ -1,163 +1,1287 @@
-use anyhow::{bail, Result};
+use crate::error::{OndaError, Result};
+#[cfg(feature = "flac")]
+use crate::flac;
+use crate::metadata::{self, WavMetadata};
+#[cfg(feature = "mp3")]
+use crate::mp3;
+#[cfg(feature = "ogg")]
+use crate::ogg;
+#[cfg(feature = "symphonia")]
+use crate::symphonia_bridge;
+use crate::{adpcm, aiff, au, caf, Sample};
+use crate::{
+    SUBFORMAT_GUID_SUFFIX, WAVE_FORMAT_ALAW, WAVE_FORMAT_EXTENSIBLE, WAVE_FORMAT_IEEE_FLOAT,
+    WAVE_FORMAT_IMA_ADPCM, WAVE_FORMAT_MS_ADPCM, WAVE_FORMAT_MULAW, WAVE_FORMAT_PCM,
+};
+use std::borrow::Cow;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Read};
+use std::ops::{Index, IndexMut};
 use std::path::Path;
+use std::time::Duration;
 
-/// WAV info and audio data. `audiodata` is a vector of channels, and each channel is
-/// a vector of 16-bit samples.
-#[derive(Clone, Debug)]
-pub struct WavData {
+/// WAV info and audio data. `audiodata` is a vector of channels, in the order they are
+/// interleaved in the file, and each channel is a vector of samples. Integer samples keep
+/// the range of `bits_per_sample`, e.g. 24-bit samples read into `i32` lie within
+/// `-8388608..=8388607`. Unsigned 8-bit samples are converted to signed, so they lie within
+/// `-128..=127`.
+///
+/// `valid_bits_per_sample` and `channel_mask` come from extensible fmt chunks. Otherwise
+/// the valid bits equal `bits_per_sample` and the channel mask is 0 (no speaker mapping).
+/// `metadata` holds the tags of RIFF files, and is empty for other containers. `warnings`
+/// lists the inconsistencies tolerated by lenient parsing, and is empty otherwise.
+///
+/// Indexing returns the samples of a channel, and iterating over a reference returns its
+/// [`Frame`](crate::Frame)s. `Debug` and `Display` summarize the format and duration,
+/// leaving the samples out.
+///
+/// Clones copy the samples. [`into_shared`](Self::into_shared) turns the audio data into a
+/// [`SharedWavData`](crate::SharedWavData), whose clones share them instead.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "S: serde::Deserialize<'de> + crate::Base64Sample"))
+)]
+pub struct WavData<S = i16> {
     pub num_channels: u16,
     pub samplerate: u32,
-    pub audiodata: Vec<Vec<i16>>,
+    pub bits_per_sample: u16,
+    pub valid_bits_per_sample: u16,
+    pub channel_mask: u32,
+    /// Deserialized from either numbers or base64 strings, and empty if missing.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "crate::serialize::deserialize_audiodata")
+    )]
+    pub audiodata: Vec<Vec<S>>,
+    pub metadata: WavMetadata,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<S> Index<usize> for WavData<S> {
+    type Output = [S];
+
+    fn index(&self, channel: usize) -> &[S] {
+        &self.audiodata[channel]
+    }
+}
+
+impl<S> IndexMut<usize> for WavData<S> {
+    fn index_mut(&mut self, channel: usize) -> &mut [S] {
+        &mut self.audiodata[channel]
+    }
+}
+
+impl<S> fmt::Debug for WavData<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WavData")
+            .field("num_channels", &self.num_channels)
+            .field("samplerate", &self.samplerate)
+            .field("bits_per_sample", &self.bits_per_sample)
+            .field("valid_bits_per_sample", &self.valid_bits_per_sample)
+            .field("channel_mask", &self.channel_mask)
+            .field("num_frames", &self.num_frames())
+            .field("duration", &self.duration())
+            .field("metadata", &self.metadata)
+            .field("warnings", &self.warnings)
+            .finish_non_exhaustive()
+    }
+}
+
+/// E.g. `2 channels, 48000 Hz, 24-bit int, 1.500 s`.
+impl<S: Sample> fmt::Display for WavData<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sample_type = if S::FLOAT { "float" } else { "int" };
+        write!(
+            f,
+            "{} channel{}, {} Hz, {}-bit {sample_type}, {:.3} s",
+            self.num_channels,
+            if self.num_channels == 1 { "" } else { "s" },
+            self.samplerate,
+            self.bits_per_sample,
+            self.len_seconds()
+        )
+    }
+}
+
+/// Audio data of 32-bit float samples within `-1.0..=1.0`, as returned by [`read_f32`].
+pub type WavDataF32 = WavData<f32>;
+
+impl<S: Sample> WavData<S> {
+    /// Creates empty audio data with `num_channels` channels and the bit depth of `S`, to
+    /// fill with [`push_frame`](Self::push_frame) or
+    /// [`extend_from_frames`](Self::extend_from_frames).
+    pub fn new(samplerate: u32, num_channels: u16) -> Self {
+        Self::with_capacity(samplerate, num_channels, 0)
+    }
+
+    /// Creates empty audio data like [`new`](Self::new), with room for `num_frames` frames.
+    pub fn with_capacity(samplerate: u32, num_channels: u16, num_frames: usize) -> Self {
+        WavData {
+            num_channels,
+            samplerate,
+            bits_per_sample: S::BITS,
+            valid_bits_per_sample: S::BITS,
+            channel_mask: 0,
+            audiodata: (0..num_channels)
+                .map(|_| Vec::with_capacity(num_frames))
+                .collect(),
+            metadata: WavMetadata::default(),
+            warnings: vec![],
+        }
+    }
+}
+
+impl<S> WavData<S> {
+    /// Number of frames, i.e. samples per channel.
+    pub fn num_frames(&self) -> usize {
+        self.audiodata.first().map_or(0, Vec::len)
+    }
+
+    /// Samples of channel `index`, or `None` if there is no such channel.
+    pub fn channel(&self, index: usize) -> Option<&[S]> {
+        self.audiodata.get(index).map(Vec::as_slice)
+    }
+
+    /// Mutable samples of channel `index`, or `None` if there is no such channel.
+    pub fn channel_mut(&mut self, index: usize) -> Option<&mut [S]> {
+        self.audiodata.get_mut(index).map(Vec::as_mut_slice)
+    }
+
+    /// Samples of the first channel, which is the left one of stereo audio data and the
+    /// only one of mono audio data.
+    pub fn left(&self) -> Option<&[S]> {
+        self.channel(0)
+    }
+
+    /// Mutable samples of the first channel, like [`left`](Self::left).
+    pub fn left_mut(&mut self) -> Option<&mut [S]> {
+        self.channel_mut(0)
+    }
+
+    /// Samples of the second channel, which is the right one of stereo audio data, or
+    /// `None` for mono audio data.
+    pub fn right(&self) -> Option<&[S]> {
+        self.channel(1)
+    }
+
+    /// Mutable samples of the second channel, like [`right`](Self::right).
+    pub fn right_mut(&mut self) -> Option<&mut [S]> {
+        self.channel_mut(1)
+    }
+
+    /// Returns an iterator over the samples of each channel in turn.
+    pub fn channels(&self) -> impl ExactSizeIterator<Item = &[S]> + '_ {
+        self.audiodata.iter().map(Vec::as_slice)
+    }
+
+    /// Returns an iterator over the mutable samples of each channel in turn.
+    pub fn channels_mut(&mut self) -> impl ExactSizeIterator<Item = &mut [S]> + '_ {
+        self.audiodata.iter_mut().map(Vec::as_mut_slice)
+    }
+
+    /// Shortens every channel to at most `num_frames` frames.
+    pub fn truncate_frames(&mut self, num_frames: usize) {
+        for channel in &mut self.audiodata {
+            channel.truncate(num_frames);
+        }
+    }
+
+    /// Checks that there is a channel per `num_channels` and that all channels are of equal
+    /// length, which the mutating methods rely on.
+    pub fn validate(&self) -> Result<()> {
+        if self.audiodata.len() != self.num_channels as usize {
+            return Err(OndaError::ChannelMismatch {
+                expected: self.num_channels as usize,
+                found: self.audiodata.len(),
+            });
+        }
+        if self.audiodata.iter().any(|c| c.len() != self.num_frames()) {
+            return Err(OndaError::InvalidArgument(
+                "channels have different lengths",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Duration of the audio data, or zero if the sample rate is 0.
+    pub fn duration(&self) -> Duration {
+        if self.samplerate == 0 {
+            return Duration::ZERO;
+        }
+
+        let num_frames = self.num_frames() as u64;
+        let samplerate = self.samplerate as u64;
+        let nanos = (num_frames % samplerate) * 1_000_000_000 / samplerate;
+
+        Duration::new(num_frames / samplerate, nanos as u32)
+    }
+
+    /// Duration of the audio data in seconds, or 0 if the sample rate is 0.
+    pub fn len_seconds(&self) -> f64 {
+        if self.samplerate == 0 {
+            return 0.0;
+        }
+
+        self.num_frames() as f64 / self.samplerate as f64
+    }
+}
+
+impl<S: Copy> WavData<S> {
+    /// Returns an iterator over the frames of audio data with `N` channels, each holding one
+    /// sample per channel.
+    pub fn frames<const N: usize>(&self) -> Result<impl Iterator<Item = [S; N]> + '_> {
+        let channels: &[Vec<S>; N] = match self.audiodata.as_slice().try_into() {
+            Ok(channels) => channels,
+            Err(_) => {
+                return Err(OndaError::ChannelMismatch {
+                    expected: N,
+                    found: self.audiodata.len(),
+                })
+            }
+        };
+        let num_frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+
+        Ok((0..num_frames).map(move |f| channels.each_ref().map(|channel| channel[f])))
+    }
+
+    /// Returns an iterator over the left and right samples of stereo audio data.
+    pub fn frames_stereo(&self) -> Result<impl Iterator<Item = (S, S)> + '_> {
+        Ok(self.frames::<2>()?.map(|[left, right]| (left, right)))
+    }
+
+    /// Returns the frames from `start` to `end`, which are rounded down to whole frames and
+    /// clamped to the duration of the audio data. The other fields are kept as they are.
+    pub fn slice(&self, start: Duration, end: Duration) -> WavData<S> {
+        let start = self.frame_at(start);
+        let end = self.frame_at(end).max(start);
+
+        self.frame_range(start, end)
+    }
+
+    /// Splits the audio data at frame `frame`, clamped to the number of frames, into the
+    /// frames before it and those from it on. The other fields are kept in both halves.
+    pub fn split_at_frame(&self, frame: usize) -> (WavData<S>, WavData<S>) {
+        let (head, tail) = self
+            .audiodata
+            .iter()
+            .map(|channel| {
+                let (head, tail) = channel.split_at(frame.min(channel.len()));
+                (head.to_vec(), tail.to_vec())
+            })
+            .unzip();
+
+        (self.with_audiodata(head), self.with_audiodata(tail))
+    }
+
+    /// Index of the frame at a time, clamped to the number of frames.
+    pub(crate) fn frame_at(&self, time: Duration) -> usize {
+        frames_in(time, self.samplerate).min(self.num_frames())
+    }
+
+    /// Copies frames `start..end`, clamped to each channel, and the other fields.
+    pub(crate) fn frame_range(&self, start: usize, end: usize) -> WavData<S> {
+        self.with_audiodata(
+            self.audiodata
+                .iter()
+                .map(|channel| channel[start.min(channel.len())..end.min(channel.len())].to_vec())
+                .collect(),
+        )
+    }
+
+    /// Copies the fields other than the audio data.
+    pub(crate) fn with_audiodata(&self, audiodata: Vec<Vec<S>>) -> WavData<S> {
+        WavData {
+            num_channels: self.num_channels,
+            samplerate: self.samplerate,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata,
+            metadata: self.metadata.clone(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// Appends a frame holding one sample per channel. Channels must be of equal length.
+    pub fn push_frame(&mut self, frame: &[S]) -> Result<()> {
+        self.validate()?;
+        if frame.len() != self.audiodata.len() {
+            return Err(OndaError::ChannelMismatch {
+                expected: self.audiodata.len(),
+                found: frame.len(),
+            });
+        }
+
+        for (channel, &sample) in self.audiodata.iter_mut().zip(frame) {
+            channel.push(sample);
+        }
+
+        Ok(())
+    }
+
+    /// Appends frames of `N` channels, like [`push_frame`](Self::push_frame).
+    pub fn extend_from_frames<const N: usize>(
+        &mut self,
+        frames: impl IntoIterator<Item = [S; N]>,
+    ) -> Result<()> {
+        self.validate()?;
+        if N != self.audiodata.len() {
+            return Err(OndaError::ChannelMismatch {
+                expected: self.audiodata.len(),
+                found: N,
+            });
+        }
+
+        for frame in frames {
+            for (channel, sample) in self.audiodata.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Offset added to µ-law magnitudes before companding.
+pub(crate) const MULAW_BIAS: i16 = 0x84;
+
+const W64_RIFF_GUID: [u8; 16] = [
+    0x72, 0x69, 0x66, 0x66, 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+const W64_WAVE_GUID: [u8; 16] = [
+    0x77, 0x61, 0x76, 0x65, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+const W64_FMT_GUID: [u8; 16] = [
+    0x66, 0x6D, 0x74, 0x20, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+const W64_DATA_GUID: [u8; 16] = [
+    0x64, 0x61, 0x74, 0x61, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+
+/// Options for parsing WAV files with the `_with_options` functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject fmt chunks whose byte rate or block align contradict the channel count and bit
+    /// depth. Otherwise both are recomputed from those, and a [`ParseWarning`] is recorded.
+    /// Defaults to true.
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { strict: true }
+    }
+}
+
+/// Inconsistency tolerated by lenient parsing, as recorded in [`WavData::warnings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// The byte rate of the fmt chunk differs from the one its other fields imply.
+    ByteRateMismatch { found: u32, expected: u64 },
+    /// The block align of the fmt chunk differs from the frame size, which was used instead.
+    BlockAlignMismatch { found: u16, expected: u16 },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ByteRateMismatch { found, expected } => {
+                write!(f, "byte rate is {found} instead of {expected}")
+            }
+            Self::BlockAlignMismatch { found, expected } => {
+                write!(f, "block align is {found} instead of {expected}")
+            }
+        }
+    }
+}
+
+/// Byte order of the numbers in a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// How samples are encoded in the data chunk.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SampleFormat {
+    Int,
+    Float,
+    /// G.711 A-law companded 8-bit samples, decoded to 16-bit.
+    ALaw,
+    /// G.711 µ-law companded 8-bit samples, decoded to 16-bit.
+    MuLaw,
+    /// IMA ADPCM 4-bit blocks, decoded to 16-bit.
+    ImaAdpcm,
+    /// Microsoft ADPCM 4-bit blocks, decoded to 16-bit.
+    MsAdpcm,
+}
+
+impl SampleFormat {
+    /// Size of the per-channel header of compressed blocks, or `None` if samples are
+    /// stored as fixed-size values.
+    fn block_header_size(self) -> Option<usize> {
+        match self {
+            SampleFormat::ImaAdpcm => Some(adpcm::IMA_HEADER_SIZE),
+            SampleFormat::MsAdpcm => Some(adpcm::MS_HEADER_SIZE),
+            _ => None,
+        }
+    }
 }
 
 /// Details about the WAV file.
-#[derive(Clone, Copy)]
-struct Spec {
-    num_channels: u16,
-    samplerate: u32,
+#[derive(Clone)]
+pub(crate) struct Spec {
+    pub(crate) format: SampleFormat,
+    pub(crate) num_channels: u16,
+    pub(crate) samplerate: u32,
+    pub(crate) block_align: u16,
+    pub(crate) bits_per_sample: u16,
+    pub(crate) valid_bits_per_sample: u16,
+    pub(crate) channel_mask: u32,
+    /// Predictor coefficient pairs of Microsoft ADPCM files.
+    pub(crate) adpcm_coefficients: Vec<(i16, i16)>,
+    /// Frame count of the fact chunk of compressed files, whose last block may be padded.
+    pub(crate) num_frames: Option<usize>,
+    pub(crate) metadata: WavMetadata,
+    pub(crate) warnings: Vec<ParseWarning>,
+}
+
+impl Spec {
+    /// Bit depth and valid bits of the decoded samples.
+    pub(crate) fn decoded_bits(&self) -> (u16, u16) {
+        // companded samples are expanded to 16 bits
+        match self.format {
+            SampleFormat::ALaw
+            | SampleFormat::MuLaw
+            | SampleFormat::ImaAdpcm
+            | SampleFormat::MsAdpcm => (16, 16),
+            _ => (self.bits_per_sample, self.valid_bits_per_sample),
+        }
+    }
+
+    /// Bit depth and valid bits of samples decoded as `S`, which are those of `S` when
+    /// integer samples are normalized to floats.
+    pub(crate) fn decoded_bits_as<S: Sample>(&self) -> (u16, u16) {
+        match self.format {
+            SampleFormat::Float => self.decoded_bits(),
+            _ if S::FLOAT => (S::BITS, S::BITS),
+            _ => self.decoded_bits(),
+        }
+    }
+
+    /// Number of frames in each compressed block, or `None` if samples are stored as
+    /// fixed-size values.
+    pub(crate) fn frames_per_block(&self) -> Option<usize> {
+        let num_channels = self.num_channels as usize;
+        let header_size = self.format.block_header_size()? * num_channels;
+        let codes = (self.block_align as usize - header_size) * 2 / num_channels;
+
+        // the header holds the first sample of IMA blocks, and the first two of Microsoft
+        // ones
+        match self.format {
+            SampleFormat::ImaAdpcm => Some(1 + codes),
+            _ => Some(2 + codes),
+        }
+    }
 }
 
-/// Parses a WAV file from a byte slice buffer;
+/// Parses an 8 or 16-bit WAV, AIFF, AU or CAF file from a byte slice buffer. A-law, µ-law and ADPCM
+/// files are decoded to 16-bit samples. Use [`parse_bytes_i32`] for files with a higher bit
+/// depth.
 pub fn parse_bytes(buf: impl AsRef<[u8]>) -> Result<WavData> {
-    let buf = buf.as_ref();
-    let mut offset = 0;
+    parse_bytes_with_options(buf, ParseOptions::default())
+}
 
-    parse_riff_chunk(buf, &mut offset)?;
-    let spec = parse_fmt_chunk(buf, &mut offset)?;
-    find_data_offset(buf, &mut offset)?;
-    let data = parse_data_chunk(buf, &mut offset, spec)?;
+/// Parses an 8 or 16-bit file from a byte slice buffer like [`parse_bytes`], with the given
+/// options.
+pub fn parse_bytes_with_options(buf: impl AsRef<[u8]>, options: ParseOptions) -> Result<WavData> {
+    parse_bytes_as_with_options(buf, options)
+}
 
-    Ok(WavData {
-        num_channels: spec.num_channels,
-        samplerate: spec.samplerate,
-        audiodata: data,
-    })
+/// Parses an 8, 16, 24 or 32-bit WAV file from a byte slice buffer into 32-bit samples.
+pub fn parse_bytes_i32(buf: impl AsRef<[u8]>) -> Result<WavData<i32>> {
+    parse_bytes_i32_with_options(buf, ParseOptions::default())
+}
+
+/// Parses an 8, 16, 24 or 32-bit file from a byte slice buffer like [`parse_bytes_i32`],
+/// with the given options.
+pub fn parse_bytes_i32_with_options(
+    buf: impl AsRef<[u8]>,
+    options: ParseOptions,
+) -> Result<WavData<i32>> {
+    parse_bytes_as_with_options(buf, options)
+}
+
+/// Parses a WAV file from a byte slice buffer into 32-bit float samples. Integer samples of
+/// any bit depth are normalized to `-1.0..1.0`, and 64-bit float samples rounded.
+pub fn parse_bytes_f32(buf: impl AsRef<[u8]>) -> Result<WavData<f32>> {
+    parse_bytes_f32_with_options(buf, ParseOptions::default())
+}
+
+/// Parses a file from a byte slice buffer into 32-bit float samples like
+/// [`parse_bytes_f32`], with the given options.
+pub fn parse_bytes_f32_with_options(
+    buf: impl AsRef<[u8]>,
+    options: ParseOptions,
+) -> Result<WavData<f32>> {
+    parse_bytes_as_with_options(buf, options)
+}
+
+/// Parses a WAV file from a byte slice buffer into 64-bit float samples. Integer samples of
+/// any bit depth are normalized to `-1.0..1.0`.
+pub fn parse_bytes_f64(buf: impl AsRef<[u8]>) -> Result<WavData<f64>> {
+    parse_bytes_f64_with_options(buf, ParseOptions::default())
 }
 
-/// Reads a WAV file from the provided path.
+/// Parses a file from a byte slice buffer into 64-bit float samples like
+/// [`parse_bytes_f64`], with the given options.
+pub fn parse_bytes_f64_with_options(
+    buf: impl AsRef<[u8]>,
+    options: ParseOptions,
+) -> Result<WavData<f64>> {
+    parse_bytes_as_with_options(buf, options)
+}
+
+/// Parses a file from a byte slice buffer into samples of type `S`, e.g.
+/// `parse_bytes_as::<f32>(buf)`. The samples must be stored in a format that `S` holds, as
+/// for the function of each sample type.
+pub fn parse_bytes_as<S: Sample>(buf: impl AsRef<[u8]>) -> Result<WavData<S>> {
+    parse_bytes_as_with_options(buf, ParseOptions::default())
+}
+
+/// Parses a file from a byte slice buffer into samples of type `S` like [`parse_bytes_as`],
+/// with the given options.
+pub fn parse_bytes_as_with_options<S: Sample>(
+    buf: impl AsRef<[u8]>,
+    options: ParseOptions,
+) -> Result<WavData<S>> {
+    let (spec, data) = parse_wav(buf.as_ref(), options)?;
+
+    Ok(wavdata(&spec, S::decode_samples(&data, &spec)?))
+}
+
+/// Reads an 8 or 16-bit WAV file from the provided path.
 pub fn read(path: impl AsRef<Path>) -> Result<WavData> {
+    parse_bytes(read_file(path)?)
+}
+
+/// Reads an 8, 16, 24 or 32-bit WAV file from the provided path into 32-bit samples.
+pub fn read_i32(path: impl AsRef<Path>) -> Result<WavData<i32>> {
+    parse_bytes_i32(read_file(path)?)
+}
+
+/// Reads a WAV file from the provided path into 32-bit float samples within `-1.0..=1.0`,
+/// whatever its bit depth, like [`parse_bytes_f32`].
+pub fn read_f32(path: impl AsRef<Path>) -> Result<WavDataF32> {
+    parse_bytes_f32(read_file(path)?)
+}
+
+/// Reads a WAV file from the provided path into 64-bit float samples within `-1.0..=1.0`,
+/// whatever its bit depth, like [`parse_bytes_f64`].
+pub fn read_f64(path: impl AsRef<Path>) -> Result<WavData<f64>> {
+    parse_bytes_f64(read_file(path)?)
+}
+
+/// Reads a WAV file from the provided path into samples of type `S`, like
+/// [`parse_bytes_as`].
+pub fn read_as<S: Sample>(path: impl AsRef<Path>) -> Result<WavData<S>> {
+    parse_bytes_as(read_file(path)?)
+}
+
+pub(crate) fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
     let mut reader = BufReader::new(File::open(path)?);
 
     let mut buf = Vec::new();
     reader.read_to_end(&mut buf)?;
 
-    parse_bytes(&buf)
+    Ok(buf)
 }
 
-fn parse_riff_chunk(buf: &[u8], offset: &mut usize) -> Result<()> {
-    if !compare_str_bytes(buf, offset, "RIFF") {
-        bail!("not a RIFF file");
+/// Parses the WAV header and returns it along with the raw bytes of the data chunk, with
+/// samples in little-endian order. Wave64, AIFF, AU and CAF files are detected and parsed as
+/// well, and so are FLAC, Ogg Vorbis and MP3 files with the `flac`, `ogg` and `mp3` features.
+/// With the `symphonia` feature, WAV files of other codecs are decoded through Symphonia.
+pub(crate) fn parse_wav(buf: &[u8], options: ParseOptions) -> Result<(Spec, Cow<'_, [u8]>)> {
+    if buf.starts_with(&W64_RIFF_GUID) {
+        return parse_w64(buf, options).map(|(spec, data)| (spec, Cow::Borrowed(data)));
+    }
+
+    if buf.starts_with(b"FORM") {
+        return aiff::parse_aiff(buf);
     }
 
+    if buf.starts_with(b".snd") {
+        return au::parse_au(buf);
+    }
+
+    if buf.starts_with(b"caff") {
+        return caf::parse_caf(buf);
+    }
+
+    #[cfg(feature = "flac")]
+    if buf.starts_with(b"fLaC") {
+        return flac::parse_flac(buf);
+    }
+
+    #[cfg(feature = "ogg")]
+    if buf.starts_with(b"OggS") {
+        return ogg::parse_ogg(buf);
+    }
+
+    #[cfg(feature = "mp3")]
+    if mp3::is_mp3(buf) {
+        return mp3::parse_mp3(buf);
+    }
+
+    let mut offset = 0;
+
+    let (endian, data_size) = parse_riff_chunk(buf, &mut offset)?;
+    let mut spec = match parse_fmt_chunk(buf, &mut offset, endian, options) {
+        #[cfg(feature = "symphonia")]
+        Err(OndaError::UnsupportedCodec { .. }) => return symphonia_bridge::parse_riff_stream(buf),
+        result => result?,
+    };
+    find_chunk(buf, &mut offset, endian, b"data")?;
+    let data = parse_data_chunk(buf, &mut offset, endian, data_size)?;
+
+    let data = to_wav_layout(data, &spec, endian, false)?;
+    if spec.format.block_header_size().is_some() {
+        spec.num_frames = parse_fact_chunk(buf, endian, data_size.is_some());
+    }
+    spec.metadata = metadata::parse_metadata(buf, endian, data_size);
+
+    Ok((spec, data))
+}
+
+/// Converts samples to the layout of a WAV data chunk: little-endian, with 8-bit PCM samples
+/// stored as offset binary. `signed_8bit` marks 8-bit PCM input as two's complement, which
+/// other containers use.
+pub(crate) fn to_wav_layout<'a>(
+    data: &'a [u8],
+    spec: &Spec,
+    byte_order: Endianness,
+    signed_8bit: bool,
+) -> Result<Cow<'a, [u8]>> {
+    if spec.format.block_header_size().is_some() && byte_order == Endianness::Big {
+        return Err(OndaError::Unsupported("compressed big-endian"));
+    }
+
+    match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Int, 8) if signed_8bit => {
+            Ok(Cow::Owned(data.iter().map(|byte| byte ^ 0x80).collect()))
+        }
+        (_, 8) => Ok(Cow::Borrowed(data)),
+        _ if byte_order == Endianness::Little => Ok(Cow::Borrowed(data)),
+        (_, bits) => {
+            let mut data = data.to_vec();
+            for sample in data.chunks_exact_mut(bits as usize / 8) {
+                sample.reverse();
+            }
+            Ok(Cow::Owned(data))
+        }
+    }
+}
+
+pub(crate) fn wavdata<S: Sample>(spec: &Spec, audiodata: Vec<Vec<S>>) -> WavData<S> {
+    let (bits_per_sample, valid_bits_per_sample) = spec.decoded_bits_as::<S>();
+
+    WavData {
+        num_channels: spec.num_channels,
+        samplerate: spec.samplerate,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask: spec.channel_mask,
+        audiodata,
+        metadata: spec.metadata.clone(),
+        warnings: spec.warnings.clone(),
+    }
+}
+
+/// Parses a Sony Wave64 file. Its chunks have GUIDs as IDs, and 64-bit sizes which include
+/// the chunk header.
+fn parse_w64(buf: &[u8], options: ParseOptions) -> Result<(Spec, &[u8])> {
+    const HEADER_SIZE: usize = 24;
+
+    // skip riff GUID and file size
+    let mut offset = HEADER_SIZE;
+
+    if parse_slice(buf, &mut offset, 16)? != W64_WAVE_GUID {
+        return Err(OndaError::NotWave);
+    }
+
+    let mut spec = None;
+
+    while offset.saturating_add(HEADER_SIZE) <= buf.len() {
+        let id = parse_slice(buf, &mut offset, 16)?;
+
+        let Some(size) =
+            (parse_u64(buf, &mut offset, Endianness::Little)? as usize).checked_sub(HEADER_SIZE)
+        else {
+            return Err(OndaError::Malformed("Wave64 chunk smaller than its header"));
+        };
+
+        if id == W64_FMT_GUID {
+            let mut fmt_offset = offset;
+            spec = Some(parse_fmt_payload(
+                buf,
+                &mut fmt_offset,
+                size,
+                Endianness::Little,
+                options,
+            )?);
+        } else if id == W64_DATA_GUID {
+            let Some(spec) = spec else {
+                return Err(OndaError::MissingChunk { id: *b"fmt " });
+            };
+
+            return Ok((spec, parse_slice(buf, &mut offset, size)?));
+        }
+
+        // chunks are aligned to 8 bytes
+        let Some(next) = size
+            .checked_next_multiple_of(8)
+            .and_then(|size| offset.checked_add(size))
+        else {
+            break;
+        };
+        offset = next;
+    }
+
+    Err(OndaError::MissingChunk { id: *b"data" })
+}
+
+/// Parses the RIFF header and returns the byte order of the file. RF64 and BW64 files
+/// keep their 64-bit data chunk size in a ds64 chunk, which is returned as well.
+pub(crate) fn parse_riff_chunk(
+    buf: &[u8],
+    offset: &mut usize,
+) -> Result<(Endianness, Option<u64>)> {
+    let (endian, rf64) = match parse_id(buf, offset)? {
+        b"RIFF" => (Endianness::Little, false),
+        b"RIFX" => (Endianness::Big, false),
+        b"RF64" | b"BW64" => (Endianness::Little, true),
+        _ => return Err(OndaError::NotRiff),
+    };
+
     // ignore chunk size
     *offset += 4;
 
-    if !compare_str_bytes(buf, offset, "WAVE") {
-        bail!("not a WAVE file");
+    if parse_id(buf, offset)? != b"WAVE" {
+        return Err(OndaError::NotWave);
     }
 
-    Ok(())
+    if rf64 {
+        return Ok((endian, Some(parse_ds64_chunk(buf, offset)?)));
+    }
+
+    Ok((endian, None))
 }
 
-fn parse_fmt_chunk(buf: &[u8], offset: &mut usize) -> Result<Spec> {
-    if parse_str(&buf, offset, 4) != "fmt " {
-        bail!("fmt chunk not found");
+fn parse_ds64_chunk(buf: &[u8], offset: &mut usize) -> Result<u64> {
+    if parse_id(buf, offset)? != b"ds64" {
+        return Err(OndaError::MissingChunk { id: *b"ds64" });
     }
 
-    if parse_u32(&buf, offset) != 16 {
-        bail!("fmt chunk wrong size");
+    let size = parse_u32(buf, offset, Endianness::Little)? as usize;
+    if size < 28 {
+        return Err(OndaError::BadChunkSize { id: *b"ds64" });
     }
 
-    if parse_u16(buf, offset) != 1 {
-        bail!("not a PCM file");
+    // ignore RIFF size
+    *offset += 8;
+    let data_size = parse_u64(buf, offset, Endianness::Little)?;
+
+    // ignore sample count and the size table of other chunks
+    *offset += size - 16;
+
+    Ok(data_size)
+}
+
+fn parse_fmt_chunk(
+    buf: &[u8],
+    offset: &mut usize,
+    endian: Endianness,
+    options: ParseOptions,
+) -> Result<Spec> {
+    // Broadcast Wave files may put their bext chunk first
+    find_chunk(buf, offset, endian, b"fmt ")?;
+
+    let size = parse_u32(buf, offset, endian)? as usize;
+    parse_fmt_payload(buf, offset, size, endian, options)
+}
+
+/// Parses the contents of a fmt chunk of the given size.
+pub(crate) fn parse_fmt_payload(
+    buf: &[u8],
+    offset: &mut usize,
+    size: usize,
+    endian: Endianness,
+    options: ParseOptions,
+) -> Result<Spec> {
+    if size < 16 {
+        return Err(OndaError::BadChunkSize { id: *b"fmt " });
+    }
+
+    let mut format_tag = parse_u16(buf, offset, endian)?;
+    let num_channels = parse_u16(buf, offset, endian)?;
+    let samplerate = parse_u32(buf, offset, endian)?;
+    let byterate = parse_u32(buf, offset, endian)?;
+    let mut block_align = parse_u16(buf, offset, endian)?;
+    let bits_per_sample = parse_u16(buf, offset, endian)?;
+    let mut consumed = 16;
+
+    let mut valid_bits_per_sample = bits_per_sample;
+    let mut channel_mask = 0;
+    let mut warnings = vec![];
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        if size < 40 {
+            return Err(OndaError::BadChunkSize { id: *b"fmt " });
+        }
+
+        // ignore extension size
+        *offset += 2;
+        valid_bits_per_sample = parse_u16(buf, offset, endian)?;
+        channel_mask = parse_u32(buf, offset, endian)?;
+
+        let subformat = parse_slice(buf, offset, 16)?;
+        if subformat[2..] != SUBFORMAT_GUID_SUFFIX {
+            return Err(OndaError::BadFmtChunk("unknown extensible subformat"));
+        }
+        format_tag = u16::from_le_bytes([subformat[0], subformat[1]]);
+        consumed = 40;
+
+        if valid_bits_per_sample > bits_per_sample {
+            return Err(OndaError::BadFmtChunk(
+                "valid bits per sample exceed container size",
+            ));
+        }
     }
 
-    let num_channels = parse_u16(buf, offset);
-    let samplerate = parse_u32(buf, offset);
-    let byterate = parse_u32(buf, offset);
-    let block_align = parse_u16(buf, offset);
-    let bits_per_sample = parse_u16(buf, offset);
+    let format = match format_tag {
+        WAVE_FORMAT_PCM => SampleFormat::Int,
+        WAVE_FORMAT_IEEE_FLOAT => SampleFormat::Float,
+        WAVE_FORMAT_ALAW => SampleFormat::ALaw,
+        WAVE_FORMAT_MULAW => SampleFormat::MuLaw,
+        WAVE_FORMAT_IMA_ADPCM => SampleFormat::ImaAdpcm,
+        WAVE_FORMAT_MS_ADPCM => SampleFormat::MsAdpcm,
+        _ => {
+            return Err(OndaError::UnsupportedCodec {
+                tag: format_tag as u32,
+            })
+        }
+    };
 
-    if byterate != samplerate * num_channels as u32 * bits_per_sample as u32 / 8 {
-        bail!("byte rate does not match with other parameters");
+    if num_channels == 0 {
+        return Err(OndaError::NoChannels);
     }
 
-    if block_align != num_channels * bits_per_sample / 8 {
-        bail!("block align does not match with other parameters");
+    let supported = match format {
+        SampleFormat::Int => matches!(bits_per_sample, 8 | 16 | 24 | 32),
+        SampleFormat::Float => matches!(bits_per_sample, 32 | 64),
+        SampleFormat::ALaw | SampleFormat::MuLaw => bits_per_sample == 8,
+        SampleFormat::ImaAdpcm | SampleFormat::MsAdpcm => bits_per_sample == 4,
+    };
+    if !supported {
+        return Err(OndaError::UnsupportedBitDepth(bits_per_sample as u32));
     }
 
+    if let Some(header_size) = format.block_header_size() {
+        // compressed blocks start with a header per channel
+        if (block_align as usize) <= header_size * num_channels as usize {
+            return Err(OndaError::BadFmtChunk(
+                "block align too small for compressed blocks",
+            ));
+        }
+    } else {
+        let expected_block_align = frame_size(num_channels, bits_per_sample)?;
+        let expected_byterate = samplerate as u64 * expected_block_align as u64;
+
+        if byterate as u64 != expected_byterate {
+            if options.strict {
+                return Err(OndaError::BadFmtChunk(
+                    "byte rate does not match with other parameters",
+                ));
+            }
+            warnings.push(ParseWarning::ByteRateMismatch {
+                found: byterate,
+                expected: expected_byterate,
+            });
+        }
+
+        if block_align != expected_block_align {
+            if options.strict {
+                return Err(OndaError::BadFmtChunk(
+                    "block align does not match with other parameters",
+                ));
+            }
+            warnings.push(ParseWarning::BlockAlignMismatch {
+                found: block_align,
+                expected: expected_block_align,
+            });
+            block_align = expected_block_align;
+        }
+    }
+
+    let mut adpcm_coefficients = vec![];
+    if format == SampleFormat::MsAdpcm && size >= 22 {
+        // ignore extension size and samples per block
+        *offset += 4;
+        let num_coefficients = parse_u16(buf, offset, endian)? as usize;
+        consumed = 22 + 4 * num_coefficients;
+
+        if size < consumed {
+            return Err(OndaError::BadFmtChunk(
+                "fmt chunk too small for ADPCM coefficients",
+            ));
+        }
+
+        for _ in 0..num_coefficients {
+            let coefficient1 = parse_u16(buf, offset, endian)? as i16;
+            let coefficient2 = parse_u16(buf, offset, endian)? as i16;
+            adpcm_coefficients.push((coefficient1, coefficient2));
+        }
+    } else if format == SampleFormat::MsAdpcm {
+        adpcm_coefficients = adpcm::MS_STANDARD_COEFFICIENTS.to_vec();
+    }
+
+    // skip any remaining extension bytes
+    *offset += size - consumed;
+
     Ok(Spec {
+        format,
         num_channels,
         samplerate,
+        block_align,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask,
+        adpcm_coefficients,
+        num_frames: None,
+        metadata: WavMetadata::default(),
+        warnings,
+    })
+}
+
+/// Returns the frame count of the fact chunk, if the file has one. RF64 files may mark it
+/// as kept in the ds64 chunk, which leaves it unknown.
+fn parse_fact_chunk(buf: &[u8], endian: Endianness, rf64: bool) -> Option<usize> {
+    // skip the RIFF header
+    let mut offset = 12;
+    find_chunk(buf, &mut offset, endian, b"fact").ok()?;
+
+    let size = parse_u32(buf, &mut offset, endian).ok()?;
+    if size < 4 {
+        return None;
+    }
+
+    match parse_u32(buf, &mut offset, endian).ok()? {
+        u32::MAX if rf64 => None,
+        num_frames => Some(num_frames as usize),
+    }
+}
+
+pub(crate) fn parse_data_chunk<'a>(
+    buf: &'a [u8],
+    offset: &mut usize,
+    endian: Endianness,
+    ds64_size: Option<u64>,
+) -> Result<&'a [u8]> {
+    let size = match (parse_u32(buf, offset, endian)?, ds64_size) {
+        // RF64 files mark sizes kept in the ds64 chunk with the maximum value
+        (u32::MAX, Some(size)) => size as usize,
+        (size, _) => size as usize,
+    };
+
+    parse_slice(buf, offset, size)
+}
+
+/// Splits interleaved sample bytes into one vector per channel.
+pub(crate) fn deinterleave<S>(
+    data: &[u8],
+    spec: &Spec,
+    decode: impl Fn(&[u8]) -> S,
+) -> Vec<Vec<S>> {
+    let sample_size = spec.bits_per_sample as usize / 8;
+    let frame_size = sample_size * spec.num_channels as usize;
+
+    let mut channels: Vec<Vec<S>> = (0..spec.num_channels)
+        .map(|_| Vec::with_capacity(data.len() / frame_size))
+        .collect();
+
+    for frame in data.chunks_exact(frame_size) {
+        for (channel, sample) in channels.iter_mut().zip(frame.chunks_exact(sample_size)) {
+            channel.push(decode(sample));
+        }
+    }
+
+    channels
+}
+
+/// Decodes the samples of a data chunk in WAV layout into 16-bit channels.
+pub(crate) fn decode_samples_i16(data: &[u8], spec: &Spec) -> Result<Vec<Vec<i16>>> {
+    Ok(match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Int, 8) => deinterleave(data, spec, |b| decode_u8(b) as i16),
+        (SampleFormat::Int, 16) => deinterleave(data, spec, decode_i16),
+        (SampleFormat::Int, bits) => return Err(sample_type_mismatch(false, bits)),
+        (SampleFormat::ALaw, _) => deinterleave(data, spec, |b| decode_alaw(b[0])),
+        (SampleFormat::MuLaw, _) => deinterleave(data, spec, |b| decode_mulaw(b[0])),
+        (SampleFormat::ImaAdpcm | SampleFormat::MsAdpcm, _) => decode_adpcm(data, spec)?,
+        (SampleFormat::Float, bits) => return Err(sample_type_mismatch(true, bits)),
+    })
+}
+
+/// Decodes the samples of a data chunk in WAV layout into 32-bit channels.
+pub(crate) fn decode_samples_i32(data: &[u8], spec: &Spec) -> Result<Vec<Vec<i32>>> {
+    Ok(match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Int, 8) => deinterleave(data, spec, |b| decode_u8(b) as i32),
+        (SampleFormat::Int, 16) => deinterleave(data, spec, |b| decode_i16(b) as i32),
+        (SampleFormat::Int, 24) => deinterleave(data, spec, decode_i24),
+        (SampleFormat::Int, 32) => deinterleave(data, spec, decode_i32),
+        (SampleFormat::ALaw, _) => deinterleave(data, spec, |b| decode_alaw(b[0]) as i32),
+        (SampleFormat::MuLaw, _) => deinterleave(data, spec, |b| decode_mulaw(b[0]) as i32),
+        (SampleFormat::ImaAdpcm | SampleFormat::MsAdpcm, _) => widen(decode_adpcm(data, spec)?),
+        (SampleFormat::Float, bits) => return Err(sample_type_mismatch(true, bits)),
+        (_, bits) => return Err(OndaError::UnsupportedBitDepth(bits as u32)),
     })
 }
 
-fn parse_data_chunk(buf: &[u8], offset: &mut usize, spec: Spec) -> Result<Vec<Vec<i16>>> {
-    let size = parse_u32(&buf, offset) as usize;
+/// Decodes the samples of a data chunk in WAV layout into 32-bit float channels. Integer
+/// samples are normalized by their bit depth.
+pub(crate) fn decode_samples_f32(data: &[u8], spec: &Spec) -> Result<Vec<Vec<f32>>> {
+    Ok(match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => deinterleave(data, spec, decode_f32),
+        (SampleFormat::Float, 64) => deinterleave(data, spec, |b| decode_f64(b) as f32),
+        (SampleFormat::Float, bits) => return Err(OndaError::UnsupportedBitDepth(bits as u32)),
+        _ => normalize(decode_samples_i32(data, spec)?, spec),
+    })
+}
 
-    let mut channels = vec![vec![]; spec.num_channels as usize];
+/// Decodes the samples of a data chunk in WAV layout into 64-bit float channels. Integer
+/// samples are normalized by their bit depth.
+pub(crate) fn decode_samples_f64(data: &[u8], spec: &Spec) -> Result<Vec<Vec<f64>>> {
+    Ok(match (spec.format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => deinterleave(data, spec, |b| decode_f32(b) as f64),
+        (SampleFormat::Float, 64) => deinterleave(data, spec, decode_f64),
+        (SampleFormat::Float, bits) => return Err(OndaError::UnsupportedBitDepth(bits as u32)),
+        _ => normalize(decode_samples_i32(data, spec)?, spec),
+    })
+}
+
+/// Converts decoded integer samples to floats within `-1.0..1.0`.
+fn normalize<S: Sample>(channels: Vec<Vec<i32>>, spec: &Spec) -> Vec<Vec<S>> {
+    let (bits_per_sample, _) = spec.decoded_bits();
+
+    channels
+        .into_iter()
+        .map(|channel| {
+            channel
+                .into_iter()
+                .map(|sample| S::from_f64(sample.to_f64(bits_per_sample), S::BITS))
+                .collect()
+        })
+        .collect()
+}
+
+/// Decodes IMA or Microsoft ADPCM blocks, keeping the number of frames given by the fact
+/// chunk.
+pub(crate) fn decode_adpcm(data: &[u8], spec: &Spec) -> Result<Vec<Vec<i16>>> {
+    let mut channels = match spec.format {
+        SampleFormat::ImaAdpcm => adpcm::decode_ima(data, spec.num_channels, spec.block_align),
+        _ => adpcm::decode_ms(
+            data,
+            spec.num_channels,
+            spec.block_align,
+            &spec.adpcm_coefficients,
+        )?,
+    };
+
+    if let Some(num_frames) = spec.num_frames {
+        if channels[0].len() < num_frames {
+            return Err(OndaError::Malformed(
+                "data chunk holds fewer frames than the fact chunk",
+            ));
+        }
 
-    while *offset < size {
         for channel in &mut channels {
-            channel.push(parse_i16(buf, offset));
+            channel.truncate(num_frames);
         }
     }
 
     Ok(channels)
 }
 
-fn find_data_offset(buf: &[u8], offset: &mut usize) -> Result<()> {
+/// Error for samples that can't be decoded into the requested sample type.
+pub(crate) fn sample_type_mismatch(float: bool, bits_per_sample: u16) -> OndaError {
+    OndaError::SampleTypeMismatch {
+        float,
+        bits_per_sample,
+    }
+}
+
+fn widen(channels: Vec<Vec<i16>>) -> Vec<Vec<i32>> {
+    channels
+        .into_iter()
+        .map(|channel| channel.into_iter().map(i32::from).collect())
+        .collect()
+}
+
+/// Skips chunks until the one with the given ID, and moves `offset` past that ID.
+pub(crate) fn find_chunk(
+    buf: &[u8],
+    offset: &mut usize,
+    endian: Endianness,
+    id: &[u8; 4],
+) -> Result<()> {
     loop {
-        let subchunk_id = parse_str(&buf, offset, 4);
+        if offset.saturating_add(8) > buf.len() {
+            return Err(OndaError::MissingChunk { id: *id });
+        }
 
-        if subchunk_id == "data" {
+        if parse_id(buf, offset)? == id {
             return Ok(());
-        } else if *offset >= buf.len() {
-            bail!("data chunk not found");
         }
 
-        let size = parse_u32(&buf, offset) as usize;
-        *offset += size;
+        // chunks are padded to an even size
+        let size = parse_u32(buf, offset, endian)? as usize;
+        *offset = offset.saturating_add(size + size % 2);
     }
 }
 
-fn parse_u32(buf: &[u8], offset: &mut usize) -> u32 {
-    let num = u32::from_le_bytes([
-        buf[*offset],
-        buf[*offset + 1],
-        buf[*offset + 2],
-        buf[*offset + 3],
-    ]);
+pub(crate) fn decode_u8(bytes: &[u8]) -> i8 {
+    // 8-bit samples are stored as offset binary
+    (bytes[0] ^ 0x80) as i8
+}
 
-    *offset += 4;
-    num
+/// Expands a G.711 A-law byte to a 16-bit sample.
+pub(crate) fn decode_alaw(byte: u8) -> i16 {
+    // even bits are inverted on the wire
+    let byte = byte ^ 0x55;
+
+    let mantissa = ((byte & 0x0F) as i16) << 4;
+    let segment = (byte >> 4) & 0x07;
+
+    let magnitude = match segment {
+        0 => mantissa + 8,
+        _ => (mantissa + 0x108) << (segment - 1),
+    };
+
+    // a set sign bit means positive
+    if byte & 0x80 != 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
 }
 
-fn parse_u16(buf: &[u8], offset: &mut usize) -> u16 {
-    let num = u16::from_le_bytes([buf[*offset], buf[*offset + 1]]);
+/// Expands a G.711 µ-law byte to a 16-bit sample.
+pub(crate) fn decode_mulaw(byte: u8) -> i16 {
+    // all bits are inverted on the wire
+    let byte = !byte;
+
+    let mantissa = ((byte & 0x0F) as i16) << 3;
+    let segment = (byte >> 4) & 0x07;
 
-    *offset += 2;
-    num
+    let magnitude = ((mantissa + MULAW_BIAS) << segment) - MULAW_BIAS;
+
+    if byte & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
 }
 
-fn parse_i16(buf: &[u8], offset: &mut usize) -> i16 {
-    let num = i16::from_le_bytes([buf[*offset], buf[*offset + 1]]);
+pub(crate) fn decode_i16(bytes: &[u8]) -> i16 {
+    i16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn decode_i24(bytes: &[u8]) -> i32 {
+    // place the sample in the upper bytes, then shift back down to sign-extend it
+    i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8
+}
+
+fn decode_i32(bytes: &[u8]) -> i32 {
+    i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+pub(crate) fn decode_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
 
-    *offset += 2;
-    num
+fn decode_f64(bytes: &[u8]) -> f64 {
+    let mut le_bytes = [0; 8];
+    le_bytes.copy_from_slice(&bytes[..8]);
+    f64::from_le_bytes(le_bytes)
 }
 
-fn parse_str<'a>(buf: &'a [u8], offset: &mut usize, len: usize) -> &'a str {
-    let str = std::str::from_utf8(&buf[*offset..(*offset + len)]).unwrap();
+/// Size of a frame of whole-byte samples, or an error if it doesn't fit in the 16 bits of a
+/// WAV block align.
+pub(crate) fn frame_size(num_channels: u16, bits_per_sample: u16) -> Result<u16> {
+    u16::try_from(num_channels as u32 * bits_per_sample as u32 / 8)
+        .map_err(|_| OndaError::UnsupportedChannels(num_channels as u32))
+}
+
+/// Number of frames in a duration at a sample rate, rounded down.
+pub(crate) fn frames_in(duration: Duration, samplerate: u32) -> usize {
+    let frames = duration.as_nanos() * samplerate as u128 / 1_000_000_000;
+    frames.min(usize::MAX as u128) as usize
+}
+
+/// Returns the `len` bytes at `offset`, or a truncation error if the buffer ends first.
+pub(crate) fn parse_slice<'a>(buf: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let Some(bytes) = offset
+        .checked_add(len)
+        .and_then(|end| buf.get(*offset..end))
+    else {
+        return Err(OndaError::Truncated {
+            offset: *offset as u64,
+            needed: len as u64,
+        });
+    };
+
     *offset += len;
+    Ok(bytes)
+}
+
+fn parse_array<const N: usize>(buf: &[u8], offset: &mut usize) -> Result<[u8; N]> {
+    Ok(parse_slice(buf, offset, N)?.try_into().unwrap())
+}
+
+pub(crate) fn parse_u64(buf: &[u8], offset: &mut usize, endian: Endianness) -> Result<u64> {
+    let bytes = parse_array(buf, offset)?;
 
-    str
+    Ok(match endian {
+        Endianness::Little => u64::from_le_bytes(bytes),
+        Endianness::Big => u64::from_be_bytes(bytes),
+    })
+}
+
+pub(crate) fn parse_u32(buf: &[u8], offset: &mut usize, endian: Endianness) -> Result<u32> {
+    let bytes = parse_array(buf, offset)?;
+
+    Ok(match endian {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    })
+}
+
+pub(crate) fn parse_u16(buf: &[u8], offset: &mut usize, endian: Endianness) -> Result<u16> {
+    let bytes = parse_array(buf, offset)?;
+
+    Ok(match endian {
+        Endianness::Little => u16::from_le_bytes(bytes),
+        Endianness::Big => u16::from_be_bytes(bytes),
+    })
 }
 
-fn compare_str_bytes(buf: &[u8], offset: &mut usize, string: &str) -> bool {
-    let res = &buf[*offset..(*offset + string.len())] == string.as_bytes();
-    *offset += string.len();
-    res
+/// Returns the four-byte ID at `offset`, such as a chunk ID or a form type.
+pub(crate) fn parse_id<'a>(buf: &'a [u8], offset: &mut usize) -> Result<&'a [u8; 4]> {
+    Ok(parse_slice(buf, offset, 4)?.try_into().unwrap())
 }
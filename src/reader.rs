@@ -0,0 +1,566 @@
+//! Streaming reader of WAV files, which decodes the data chunk as it is read.
+
+use crate::error::{OndaError, Result};
+use crate::metadata::{self, WavMetadata};
+use crate::read::{
+    parse_fmt_payload, parse_riff_chunk, parse_u32, to_wav_layout, wavdata, Endianness,
+    ParseOptions, ParseWarning, Spec,
+};
+use crate::{parse_bytes, parse_bytes_f32, parse_bytes_f64, parse_bytes_i32, Sample, WavData};
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames decoded at a time by the `parse_reader` functions.
+const READ_FRAMES: usize = 65536;
+
+/// Reader of RIFF WAV files from any stream, such as stdin, that decodes a number of frames
+/// at a time instead of the whole file. Only the metadata chunks before the data chunk are
+/// read.
+pub struct WavReader<R> {
+    reader: R,
+    spec: Spec,
+    endian: Endianness,
+    /// Size of the data chunk, and bytes of it that are left to read and of its padding.
+    data_size: u64,
+    data_left: u64,
+    data_padding: u64,
+    /// The chunks before the data chunk, for parsing the metadata of the whole file.
+    header: Vec<u8>,
+    /// Frames in total and left according to the fact chunk of compressed files, whose
+    /// last block may be padded.
+    num_frames: Option<u64>,
+    frames_left: Option<u64>,
+    /// Frames to drop from the start of the next block read, after seeking into the middle
+    /// of a compressed block.
+    skip_frames: usize,
+}
+
+impl WavReader<BufReader<File>> {
+    /// Opens a WAV file from the provided path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> WavReader<R> {
+    /// Reads the header of a WAV file, up to the start of its data chunk.
+    pub fn new(reader: R) -> Result<Self> {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    /// Reads the header of a WAV file like [`new`](Self::new), with the given options.
+    pub fn with_options(mut reader: R, options: ParseOptions) -> Result<Self> {
+        let mut header = vec![0; 12];
+        reader.read_exact(&mut header)?;
+
+        // RF64 files keep their sizes in the ds64 chunk that follows
+        if header.starts_with(b"RF64") || header.starts_with(b"BW64") {
+            let mut ds64_header = [0; 8];
+            reader.read_exact(&mut ds64_header)?;
+            let size = u32::from_le_bytes(ds64_header[4..].try_into().unwrap());
+
+            header.extend_from_slice(&ds64_header);
+            (&mut reader).take(size as u64).read_to_end(&mut header)?;
+        }
+
+        let mut offset = 0;
+        let (endian, ds64_size) = parse_riff_chunk(&header, &mut offset)?;
+
+        let mut spec = None;
+        let mut num_frames = None;
+        // stream position of the next chunk, for errors
+        let mut position = header.len() as u64;
+
+        let data_size = loop {
+            let mut chunk_header = [0; 8];
+            if let Err(err) = reader.read_exact(&mut chunk_header) {
+                match err.kind() {
+                    ErrorKind::UnexpectedEof => {
+                        return Err(OndaError::MissingChunk { id: *b"data" })
+                    }
+                    _ => return Err(err.into()),
+                }
+            }
+
+            let id = &chunk_header[..4];
+            let size = parse_u32(&chunk_header, &mut 4, endian)?;
+
+            if id == b"data" {
+                break match (size, ds64_size) {
+                    // RF64 files mark sizes kept in the ds64 chunk with the maximum value
+                    (u32::MAX, Some(size)) => size,
+                    (size, _) => size as u64,
+                };
+            }
+
+            // chunks are padded to an even size
+            let padded_size = size as u64 + size as u64 % 2;
+            let chunk_offset = position;
+            position += 8 + padded_size;
+
+            let is_format = matches!(id, b"fmt " | b"fact");
+            if metadata::is_layout_chunk(id) && !is_format {
+                io::copy(&mut (&mut reader).take(padded_size), &mut io::sink())?;
+                continue;
+            }
+
+            let mut payload = vec![];
+            (&mut reader).take(padded_size).read_to_end(&mut payload)?;
+            if (payload.len() as u64) < padded_size {
+                return Err(OndaError::Truncated {
+                    offset: chunk_offset + 8,
+                    needed: padded_size,
+                });
+            }
+
+            match id {
+                b"fmt " => {
+                    spec = Some(parse_fmt_payload(
+                        &payload,
+                        &mut 0,
+                        size as usize,
+                        endian,
+                        options,
+                    )?);
+                }
+                b"fact" if size >= 4 => num_frames = Some(parse_u32(&payload, &mut 0, endian)?),
+                _ => {}
+            }
+
+            // keep the metadata chunks, to parse them as a file without audio
+            header.extend_from_slice(&chunk_header);
+            header.extend_from_slice(&payload);
+        };
+
+        let Some(mut spec) = spec else {
+            return Err(OndaError::MissingChunk { id: *b"fmt " });
+        };
+        spec.metadata = metadata::parse_metadata(&header, endian, None);
+
+        let num_frames = match (spec.frames_per_block(), num_frames) {
+            (None, _) | (_, None) => None,
+            (Some(_), Some(u32::MAX)) if ds64_size.is_some() => None,
+            (Some(_), Some(num_frames)) => Some(num_frames as u64),
+        };
+
+        Ok(Self {
+            reader,
+            spec,
+            endian,
+            data_size,
+            data_left: data_size,
+            data_padding: data_size % 2,
+            header,
+            num_frames,
+            frames_left: num_frames,
+            skip_frames: 0,
+        })
+    }
+
+    pub fn num_channels(&self) -> u16 {
+        self.spec.num_channels
+    }
+
+    pub fn samplerate(&self) -> u32 {
+        self.spec.samplerate
+    }
+
+    /// Bit depth of the decoded samples, which is 16 for companded and compressed files.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.spec.decoded_bits().0
+    }
+
+    pub fn valid_bits_per_sample(&self) -> u16 {
+        self.spec.decoded_bits().1
+    }
+
+    pub fn channel_mask(&self) -> u32 {
+        self.spec.channel_mask
+    }
+
+    /// Metadata of the chunks before the data chunk.
+    pub fn metadata(&self) -> &WavMetadata {
+        &self.spec.metadata
+    }
+
+    /// Inconsistencies of the fmt chunk tolerated by lenient parsing.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.spec.warnings
+    }
+
+    /// Reads up to `num_frames` frames of an 8 or 16-bit file, like [`read`](crate::read).
+    /// Fewer frames are returned at the end of the data, and then empty channels.
+    /// Compressed files are read in whole blocks, so more frames may be returned.
+    pub fn read_frames(&mut self, num_frames: usize) -> Result<Vec<Vec<i16>>> {
+        self.read_frames_as(num_frames)
+    }
+
+    /// Reads up to `num_frames` frames of an 8, 16, 24 or 32-bit file into 32-bit samples,
+    /// like [`read_frames`](Self::read_frames).
+    pub fn read_frames_i32(&mut self, num_frames: usize) -> Result<Vec<Vec<i32>>> {
+        self.read_frames_as(num_frames)
+    }
+
+    /// Reads up to `num_frames` frames into 32-bit float samples, normalizing integer
+    /// samples like [`read_f32`](crate::read_f32), and otherwise like
+    /// [`read_frames`](Self::read_frames).
+    pub fn read_frames_f32(&mut self, num_frames: usize) -> Result<Vec<Vec<f32>>> {
+        self.read_frames_as(num_frames)
+    }
+
+    /// Reads up to `num_frames` frames into 64-bit float samples, normalizing integer
+    /// samples like [`read_f64`](crate::read_f64), and otherwise like
+    /// [`read_frames`](Self::read_frames).
+    pub fn read_frames_f64(&mut self, num_frames: usize) -> Result<Vec<Vec<f64>>> {
+        self.read_frames_as(num_frames)
+    }
+
+    /// Reads up to `num_frames` frames into samples of type `S`, like
+    /// [`parse_bytes_as`](crate::parse_bytes_as) and [`read_frames`](Self::read_frames).
+    pub fn read_frames_as<S: Sample>(&mut self, num_frames: usize) -> Result<Vec<Vec<S>>> {
+        self.read_samples(num_frames, S::decode_samples)
+    }
+
+    /// Number of frames in the data chunk.
+    pub fn num_frames(&self) -> u64 {
+        let num_blocks = self.data_size / self.spec.block_align as u64;
+
+        match (self.spec.frames_per_block(), self.num_frames) {
+            (_, Some(num_frames)) => num_frames,
+            (Some(frames_per_block), None) => num_blocks * frames_per_block as u64,
+            (None, None) => num_blocks,
+        }
+    }
+
+    /// Returns the underlying reader, positioned within or after the data chunk.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Moves the parsed header to another reader, positioned at the same place in the
+    /// data chunk.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn with_reader<T>(self, reader: T) -> WavReader<T> {
+        WavReader {
+            reader,
+            spec: self.spec,
+            endian: self.endian,
+            data_size: self.data_size,
+            data_left: self.data_left,
+            data_padding: self.data_padding,
+            header: self.header,
+            num_frames: self.num_frames,
+            frames_left: self.frames_left,
+            skip_frames: self.skip_frames,
+        }
+    }
+
+    /// Reads the metadata chunks after the data chunk, skipping what is left of the data.
+    fn read_trailing_metadata(&mut self) -> Result<()> {
+        let size = self.data_left.saturating_add(self.data_padding);
+        io::copy(&mut (&mut self.reader).take(size), &mut io::sink())?;
+        self.data_left = 0;
+
+        // parse the whole file without its audio
+        let mut buf = std::mem::take(&mut self.header);
+        buf.extend_from_slice(b"data");
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        self.reader.read_to_end(&mut buf)?;
+
+        self.spec.metadata = metadata::parse_metadata(&buf, self.endian, None);
+
+        Ok(())
+    }
+
+    fn read_samples<S>(
+        &mut self,
+        num_frames: usize,
+        decode: impl Fn(&[u8], &Spec) -> Result<Vec<Vec<S>>>,
+    ) -> Result<Vec<Vec<S>>> {
+        let size = self.read_size(num_frames);
+
+        let mut data = vec![];
+        (&mut self.reader).take(size).read_to_end(&mut data)?;
+
+        self.decode_read(&data, size, decode)
+    }
+
+    /// Bytes of the data chunk to read for `num_frames` frames.
+    pub(crate) fn read_size(&self, num_frames: usize) -> u64 {
+        let num_units = match self.spec.frames_per_block() {
+            // frames skipped after seeking into a block are read too
+            Some(frames_per_block) => (num_frames + self.skip_frames).div_ceil(frames_per_block),
+            None => num_frames,
+        };
+
+        (num_units as u64 * self.spec.block_align as u64).min(self.data_left)
+    }
+
+    /// Decodes the bytes read for a `read_size` of `size`.
+    pub(crate) fn decode_read<S>(
+        &mut self,
+        data: &[u8],
+        size: u64,
+        decode: impl Fn(&[u8], &Spec) -> Result<Vec<Vec<S>>>,
+    ) -> Result<Vec<Vec<S>>> {
+        // a stream that ends early ends the data
+        self.data_left = match data.len() as u64 {
+            len if len < size => 0,
+            len => self.data_left - len,
+        };
+
+        let data = to_wav_layout(data, &self.spec, self.endian, false)?;
+        let mut channels = decode(&data, &self.spec)?;
+
+        if let Some(frames_left) = &mut self.frames_left {
+            let len = (channels[0].len() as u64).min(*frames_left);
+            for channel in &mut channels {
+                channel.truncate(len as usize);
+            }
+            *frames_left -= len;
+        }
+
+        if self.skip_frames > 0 {
+            let len = self.skip_frames.min(channels[0].len());
+            for channel in &mut channels {
+                channel.drain(..len);
+            }
+            self.skip_frames -= len;
+        }
+
+        Ok(channels)
+    }
+}
+
+impl<R: Read + Seek> WavReader<R> {
+    /// Moves to the given frame, so the next read starts there. Frames past the end move
+    /// to the end of the data.
+    pub fn seek_to_frame(&mut self, frame: u64) -> Result<()> {
+        let frame = frame.min(self.num_frames());
+        let block_align = self.spec.block_align as u64;
+
+        // compressed files are read from the start of the block holding the frame
+        let (target, block_start) = match self.spec.frames_per_block() {
+            Some(frames_per_block) => {
+                let block = frame / frames_per_block as u64;
+                (block * block_align, block * frames_per_block as u64)
+            }
+            None => (frame * block_align, frame),
+        };
+        let target = target.min(self.data_size);
+
+        let position = self.data_size - self.data_left;
+        self.reader
+            .seek(SeekFrom::Current(target as i64 - position as i64))?;
+
+        self.data_left = self.data_size - target;
+        self.frames_left = self
+            .num_frames
+            .map(|num_frames| num_frames.saturating_sub(block_start));
+        self.skip_frames = (frame - block_start) as usize;
+
+        Ok(())
+    }
+
+    /// Moves to the frame at the given time from the start, like
+    /// [`seek_to_frame`](Self::seek_to_frame).
+    pub fn seek_to_time(&mut self, time: Duration) -> Result<()> {
+        let frame = time.as_nanos() * self.spec.samplerate as u128 / 1_000_000_000;
+        self.seek_to_frame(frame.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// Parses an 8 or 16-bit file from a reader, like [`parse_bytes`]. WAV files are read chunk
+/// by chunk, and other containers are read into memory first.
+pub fn parse_reader(reader: impl Read + Seek) -> Result<WavData> {
+    parse_reader_as(reader, parse_bytes, WavReader::read_frames)
+}
+
+/// Parses an 8, 16, 24 or 32-bit file from a reader into 32-bit samples, like
+/// [`parse_bytes_i32`].
+pub fn parse_reader_i32(reader: impl Read + Seek) -> Result<WavData<i32>> {
+    parse_reader_as(reader, parse_bytes_i32, WavReader::read_frames_i32)
+}
+
+/// Parses a file from a reader into 32-bit float samples, like [`parse_bytes_f32`].
+pub fn parse_reader_f32(reader: impl Read + Seek) -> Result<WavData<f32>> {
+    parse_reader_as(reader, parse_bytes_f32, WavReader::read_frames_f32)
+}
+
+/// Parses a file from a reader into 64-bit float samples, like [`parse_bytes_f64`].
+pub fn parse_reader_f64(reader: impl Read + Seek) -> Result<WavData<f64>> {
+    parse_reader_as(reader, parse_bytes_f64, WavReader::read_frames_f64)
+}
+
+/// Reads `len_frames` frames of an 8 or 16-bit WAV file from the provided path, starting at
+/// `start_frame`, without decoding the rest of the data. Fewer frames are returned at the end
+/// of the data, and only the metadata chunks before the data chunk are read.
+pub fn read_range(path: impl AsRef<Path>, start_frame: u64, len_frames: usize) -> Result<WavData> {
+    read_range_with_options(path, start_frame, len_frames, ParseOptions::default())
+}
+
+/// Reads a range of frames of an 8 or 16-bit WAV file like [`read_range`], with the given
+/// options.
+pub fn read_range_with_options(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+    options: ParseOptions,
+) -> Result<WavData> {
+    read_range_as(
+        path,
+        start_frame,
+        len_frames,
+        options,
+        WavReader::read_frames,
+    )
+}
+
+/// Reads a range of frames of an 8, 16, 24 or 32-bit WAV file into 32-bit samples, like
+/// [`read_range`].
+pub fn read_range_i32(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+) -> Result<WavData<i32>> {
+    read_range_i32_with_options(path, start_frame, len_frames, ParseOptions::default())
+}
+
+/// Reads a range of frames of an 8, 16, 24 or 32-bit WAV file like [`read_range_i32`], with
+/// the given options.
+pub fn read_range_i32_with_options(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+    options: ParseOptions,
+) -> Result<WavData<i32>> {
+    read_range_as(
+        path,
+        start_frame,
+        len_frames,
+        options,
+        WavReader::read_frames_i32,
+    )
+}
+
+/// Reads a range of frames of a WAV file into 32-bit float samples, like
+/// [`read_f32`](crate::read_f32) and [`read_range`].
+pub fn read_range_f32(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+) -> Result<WavData<f32>> {
+    read_range_f32_with_options(path, start_frame, len_frames, ParseOptions::default())
+}
+
+/// Reads a range of frames of a 32-bit IEEE float WAV file like [`read_range_f32`], with the
+/// given options.
+pub fn read_range_f32_with_options(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+    options: ParseOptions,
+) -> Result<WavData<f32>> {
+    read_range_as(
+        path,
+        start_frame,
+        len_frames,
+        options,
+        WavReader::read_frames_f32,
+    )
+}
+
+/// Reads a range of frames of a WAV file into 64-bit float samples, like
+/// [`read_f64`](crate::read_f64) and [`read_range`].
+pub fn read_range_f64(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+) -> Result<WavData<f64>> {
+    read_range_f64_with_options(path, start_frame, len_frames, ParseOptions::default())
+}
+
+/// Reads a range of frames of a 32 or 64-bit IEEE float WAV file like [`read_range_f64`],
+/// with the given options.
+pub fn read_range_f64_with_options(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+    options: ParseOptions,
+) -> Result<WavData<f64>> {
+    read_range_as(
+        path,
+        start_frame,
+        len_frames,
+        options,
+        WavReader::read_frames_f64,
+    )
+}
+
+fn read_range_as<S: Sample>(
+    path: impl AsRef<Path>,
+    start_frame: u64,
+    len_frames: usize,
+    options: ParseOptions,
+    read_frames: impl Fn(&mut WavReader<BufReader<File>>, usize) -> Result<Vec<Vec<S>>>,
+) -> Result<WavData<S>> {
+    let mut wav_reader = WavReader::with_options(BufReader::new(File::open(path)?), options)?;
+    wav_reader.seek_to_frame(start_frame)?;
+
+    let mut audiodata: Vec<Vec<S>> = (0..wav_reader.num_channels()).map(|_| vec![]).collect();
+
+    // compressed files are read in whole blocks, so reads may return more frames
+    while audiodata[0].len() < len_frames {
+        let frames = read_frames(&mut wav_reader, len_frames - audiodata[0].len())?;
+        if frames[0].is_empty() {
+            break;
+        }
+
+        for (channel, samples) in audiodata.iter_mut().zip(frames) {
+            channel.extend(samples);
+        }
+    }
+    for channel in &mut audiodata {
+        channel.truncate(len_frames);
+    }
+
+    Ok(wavdata(&wav_reader.spec, audiodata))
+}
+
+fn parse_reader_as<R: Read + Seek, S: Sample>(
+    mut reader: R,
+    parse: impl Fn(Vec<u8>) -> Result<WavData<S>>,
+    read_frames: impl Fn(&mut WavReader<R>, usize) -> Result<Vec<Vec<S>>>,
+) -> Result<WavData<S>> {
+    let start = reader.stream_position()?;
+    let mut magic = [0; 4];
+    let is_riff = reader.read_exact(&mut magic).is_ok()
+        && matches!(&magic, b"RIFF" | b"RIFX" | b"RF64" | b"BW64");
+    reader.seek(SeekFrom::Start(start))?;
+
+    if !is_riff {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        return parse(buf);
+    }
+
+    let mut wav_reader = WavReader::new(reader)?;
+    let mut audiodata: Vec<Vec<S>> = (0..wav_reader.num_channels()).map(|_| vec![]).collect();
+
+    loop {
+        let frames = read_frames(&mut wav_reader, READ_FRAMES)?;
+        if frames[0].is_empty() {
+            break;
+        }
+
+        for (channel, samples) in audiodata.iter_mut().zip(frames) {
+            channel.extend(samples);
+        }
+    }
+
+    wav_reader.read_trailing_metadata()?;
+
+    Ok(wavdata(&wav_reader.spec, audiodata))
+}
@@ -0,0 +1,104 @@
+//! Sample rate conversion of audio data.
+
+use crate::error::{OndaError, Result};
+use crate::write::validate_channels;
+use crate::{Sample, WavData};
+
+impl<S: Sample> WavData<S> {
+    /// Converts the audio data to `target_rate` Hz by linear interpolation between
+    /// neighbouring samples, e.g. from 44100 to 48000 Hz. It is fast, but aliases when
+    /// downsampling and dulls high frequencies. The positions of markers, sampler loops and
+    /// peaks, and the time reference of the bext chunk, are scaled to the new rate. With the
+    /// `hq-resample` feature, `resample_hq` converts with a band-limited filter instead.
+    pub fn resample(&self, target_rate: u32) -> Result<WavData<S>> {
+        check_rates(self.samplerate, target_rate)?;
+        let num_frames = validate_channels(&self.audiodata)?;
+
+        let source_rate = self.samplerate as u64;
+        let target_rate_u64 = target_rate as u64;
+        let num_frames = resampled_len(num_frames, self.samplerate, target_rate);
+
+        let audiodata = self
+            .audiodata
+            .iter()
+            .map(|channel| {
+                let Some(&last) = channel.last() else {
+                    return vec![];
+                };
+
+                (0..num_frames as u64)
+                    .map(|f| {
+                        // position in the source, as a whole frame and a fraction of one
+                        let index = (f * source_rate / target_rate_u64) as usize;
+                        let fraction =
+                            (f * source_rate % target_rate_u64) as f64 / target_rate_u64 as f64;
+
+                        let a = channel.get(index).copied().unwrap_or(last);
+                        let b = channel.get(index + 1).copied().unwrap_or(last);
+                        let a = a.to_f64(self.bits_per_sample);
+                        let b = b.to_f64(self.bits_per_sample);
+
+                        S::from_f64(a + (b - a) * fraction, self.bits_per_sample)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(self.with_samplerate(target_rate, audiodata))
+    }
+
+    /// Copies the other fields for resampled audio data, scaling the frame positions of the
+    /// metadata.
+    pub(crate) fn with_samplerate(&self, target_rate: u32, audiodata: Vec<Vec<S>>) -> WavData<S> {
+        let scale_u64 = |position: u64| {
+            let position = position as u128 * target_rate as u128 / self.samplerate as u128;
+            position.min(u64::MAX as u128) as u64
+        };
+        let scale = |position: u32| scale_u64(position as u64).min(u32::MAX as u64) as u32;
+
+        let mut metadata = self.metadata.clone();
+        for cue_point in &mut metadata.cue_points {
+            cue_point.sample_offset = scale(cue_point.sample_offset);
+            cue_point.length = scale(cue_point.length);
+        }
+        if let Some(sampler) = &mut metadata.sampler {
+            for sample_loop in &mut sampler.loops {
+                sample_loop.start = scale(sample_loop.start);
+                sample_loop.end = scale(sample_loop.end);
+            }
+        }
+        if let Some(bext) = &mut metadata.bext {
+            bext.time_reference = scale_u64(bext.time_reference);
+        }
+        if let Some(peak) = &mut metadata.peak {
+            for peak in &mut peak.peaks {
+                peak.position = scale(peak.position);
+            }
+        }
+
+        WavData {
+            num_channels: self.num_channels,
+            samplerate: target_rate,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata,
+            metadata,
+            warnings: self.warnings.clone(),
+        }
+    }
+}
+
+/// Checks that both sample rates are positive.
+pub(crate) fn check_rates(source_rate: u32, target_rate: u32) -> Result<()> {
+    if source_rate == 0 || target_rate == 0 {
+        return Err(OndaError::InvalidArgument("sample rate is 0"));
+    }
+
+    Ok(())
+}
+
+/// Number of frames covering the same duration at the target rate, rounded up.
+pub(crate) fn resampled_len(num_frames: usize, source_rate: u32, target_rate: u32) -> usize {
+    (num_frames as u64 * target_rate as u64).div_ceil(source_rate as u64) as usize
+}
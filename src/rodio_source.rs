@@ -0,0 +1,114 @@
+//! Playback of audio data through rodio.
+
+use crate::WavData;
+use rodio::source::SeekError;
+use rodio::{ChannelCount, SampleRate, Source};
+use std::time::Duration;
+
+/// Audio data as a rodio source, e.g. `sink.append(WavSource::from(onda::read("x.wav")?))`.
+/// Integer samples are scaled to `-1.0..1.0` by their bit depth.
+pub struct WavSource<S = i16> {
+    data: WavData<S>,
+    scale: f64,
+    /// Position of the next sample, as a frame and a channel within it.
+    frame: usize,
+    channel: usize,
+}
+
+impl<S> WavSource<S> {
+    fn new(data: WavData<S>, scale: f64) -> Self {
+        Self {
+            data,
+            scale,
+            frame: 0,
+            channel: 0,
+        }
+    }
+
+    /// Returns the audio data.
+    pub fn into_inner(self) -> WavData<S> {
+        self.data
+    }
+}
+
+/// Scale of integer samples with the given bit depth.
+fn int_scale(bits_per_sample: u16) -> f64 {
+    1.0 / (1u64 << (bits_per_sample.clamp(1, 64) - 1)) as f64
+}
+
+impl From<WavData<i16>> for WavSource<i16> {
+    fn from(data: WavData<i16>) -> Self {
+        let scale = int_scale(data.bits_per_sample);
+        Self::new(data, scale)
+    }
+}
+
+impl From<WavData<i32>> for WavSource<i32> {
+    fn from(data: WavData<i32>) -> Self {
+        let scale = int_scale(data.bits_per_sample);
+        Self::new(data, scale)
+    }
+}
+
+impl From<WavData<f32>> for WavSource<f32> {
+    fn from(data: WavData<f32>) -> Self {
+        Self::new(data, 1.0)
+    }
+}
+
+impl From<WavData<f64>> for WavSource<f64> {
+    fn from(data: WavData<f64>) -> Self {
+        Self::new(data, 1.0)
+    }
+}
+
+impl<S: Copy + Into<f64>> Iterator for WavSource<S> {
+    type Item = rodio::Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = *self.data.audiodata.get(self.channel)?.get(self.frame)?;
+
+        self.channel += 1;
+        if self.channel == self.data.audiodata.len() {
+            self.channel = 0;
+            self.frame += 1;
+        }
+
+        Some((sample.into() * self.scale) as f32)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let num_channels = self.data.audiodata.len();
+        let frames_left = self.data.num_frames().saturating_sub(self.frame);
+        let len = (frames_left * num_channels).saturating_sub(self.channel);
+
+        (len, Some(len))
+    }
+}
+
+impl<S: Copy + Into<f64>> Source for WavSource<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.data.num_channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.data.samplerate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.data.duration())
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let frame = pos.as_nanos() * self.data.samplerate as u128 / 1_000_000_000;
+
+        self.frame = frame.min(self.data.num_frames() as u128) as usize;
+        self.channel = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! The sample types of [`WavData`], and conversions between them.
+
+use crate::error::Result;
+use crate::read::{
+    decode_samples_f32, decode_samples_f64, decode_samples_i16, decode_samples_i32, Spec,
+};
+use crate::write::{encode_data_float, encode_data_int, encode_float, encode_int};
+use crate::{WavData, WriterBuilder};
+use std::fmt;
+use std::path::Path;
+
+/// Sample types of audio data: `i16`, `i32`, `f32` and `f64`. Integer samples keep the
+/// range of the bit depth of their audio data, e.g. `-8388608..=8388607` for 24 bits, and
+/// float samples lie within `-1.0..=1.0`.
+pub trait Sample:
+    Copy + PartialOrd + Default + fmt::Debug + Send + Sync + 'static + private::Sealed
+{
+    /// Whether the samples are IEEE floats.
+    const FLOAT: bool;
+    /// Size of a sample in bits, which is the widest bit depth it holds.
+    const BITS: u16;
+
+    /// Converts the sample to a float within `-1.0..=1.0`. Integers are divided by the
+    /// magnitude of the lowest value of `bits_per_sample`, and floats kept as they are.
+    fn to_f64(self, bits_per_sample: u16) -> f64;
+
+    /// Converts a float within `-1.0..=1.0` to a sample. Integers are rounded and clamped to
+    /// the range of `bits_per_sample`.
+    fn from_f64(value: f64, bits_per_sample: u16) -> Self;
+}
+
+/// Magnitude of the lowest integer sample of a bit depth.
+fn int_scale(bits_per_sample: u16) -> f64 {
+    (1u64 << (bits_per_sample.clamp(1, 64) - 1)) as f64
+}
+
+macro_rules! impl_int_sample {
+    ($($sample:ty => $decode:ident, $create_bytes:ident);*) => {
+        $(
+            impl Sample for $sample {
+                const FLOAT: bool = false;
+                const BITS: u16 = <$sample>::BITS as u16;
+
+                fn to_f64(self, bits_per_sample: u16) -> f64 {
+                    self as f64 / int_scale(bits_per_sample)
+                }
+
+                fn from_f64(value: f64, bits_per_sample: u16) -> Self {
+                    let scale = int_scale(bits_per_sample.min(<Self as Sample>::BITS));
+                    (value * scale).round().clamp(-scale, scale - 1.0) as $sample
+                }
+            }
+
+            impl Sealed for $sample {
+                fn decode_samples(data: &[u8], spec: &Spec) -> Result<Vec<Vec<Self>>> {
+                    $decode(data, spec)
+                }
+
+                fn create_bytes(
+                    audiodata: &[Vec<Self>],
+                    builder: &WriterBuilder,
+                ) -> Result<Vec<u8>> {
+                    builder.$create_bytes(audiodata)
+                }
+
+                fn encode_data(audiodata: &[Vec<Self>], bits_per_sample: u16) -> Result<Vec<u8>> {
+                    encode_data_int(audiodata, bits_per_sample)
+                }
+
+                fn encode(self, buf: &mut Vec<u8>, bits_per_sample: u16) -> Result<()> {
+                    encode_int(buf, self as i32, bits_per_sample)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float_sample {
+    ($($sample:ty => $decode:ident, $create_bytes:ident);*) => {
+        $(
+            impl Sample for $sample {
+                const FLOAT: bool = true;
+                const BITS: u16 = std::mem::size_of::<$sample>() as u16 * 8;
+
+                fn to_f64(self, _bits_per_sample: u16) -> f64 {
+                    self as f64
+                }
+
+                fn from_f64(value: f64, _bits_per_sample: u16) -> Self {
+                    value as $sample
+                }
+            }
+
+            impl Sealed for $sample {
+                fn decode_samples(data: &[u8], spec: &Spec) -> Result<Vec<Vec<Self>>> {
+                    $decode(data, spec)
+                }
+
+                fn create_bytes(
+                    audiodata: &[Vec<Self>],
+                    builder: &WriterBuilder,
+                ) -> Result<Vec<u8>> {
+                    builder.$create_bytes(audiodata)
+                }
+
+                fn encode_data(audiodata: &[Vec<Self>], bits_per_sample: u16) -> Result<Vec<u8>> {
+                    encode_data_float(audiodata, bits_per_sample)
+                }
+
+                fn encode(self, buf: &mut Vec<u8>, bits_per_sample: u16) -> Result<()> {
+                    encode_float(buf, self as f64, bits_per_sample);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+// the sealed trait can't be named outside the crate, so neither can the `Spec` it takes
+#[allow(private_interfaces)]
+pub(crate) mod private {
+    use super::*;
+
+    /// Decoding and encoding of each sample type, kept out of the public trait.
+    pub trait Sealed: Sized {
+        fn decode_samples(data: &[u8], spec: &Spec) -> Result<Vec<Vec<Self>>>;
+
+        fn create_bytes(audiodata: &[Vec<Self>], builder: &WriterBuilder) -> Result<Vec<u8>>;
+
+        /// Encodes the samples as the bytes of an integer or float PCM data chunk.
+        fn encode_data(audiodata: &[Vec<Self>], bits_per_sample: u16) -> Result<Vec<u8>>;
+
+        /// Appends the sample to a data chunk of the same sample type and the given bit
+        /// depth.
+        fn encode(self, buf: &mut Vec<u8>, bits_per_sample: u16) -> Result<()>;
+    }
+
+    impl_int_sample!(
+        i16 => decode_samples_i16, create_bytes_i16;
+        i32 => decode_samples_i32, create_bytes_i32
+    );
+    impl_float_sample!(
+        f32 => decode_samples_f32, create_bytes_f32;
+        f64 => decode_samples_f64, create_bytes_f64
+    );
+}
+
+/// Triangular (TPDF) dither of one least significant bit, from a small xorshift generator
+/// so results are the same on every run.
+pub(crate) struct Dither {
+    state: u64,
+}
+
+impl Dither {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Returns a uniform value within `0.0..1.0`.
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        (self.state >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns dither within `-1.0..1.0` least significant bits of `bits_per_sample`,
+    /// scaled to the range of float samples.
+    pub(crate) fn next(&mut self, bits_per_sample: u16) -> f64 {
+        (self.uniform() - self.uniform()) / int_scale(bits_per_sample)
+    }
+}
+
+impl<S: Sample> WavData<S> {
+    /// Converts the samples to another sample type. Integer samples keep their bit depth
+    /// if it fits the new type, which makes widening lossless, and are otherwise rounded
+    /// to the full width of the new type. Float samples become 32 or 64-bit floats.
+    pub fn convert<T: Sample>(&self) -> WavData<T> {
+        self.convert_with(|value, _| value)
+    }
+
+    /// Converts the samples to another sample type like [`convert`](Self::convert), but
+    /// adds TPDF dither before rounding when the new bit depth is lower, or when float
+    /// samples become integers, so the rounding error doesn't correlate with the signal.
+    pub fn convert_dithered<T: Sample>(&self) -> WavData<T> {
+        let lossy = S::FLOAT || self.bits_per_sample > T::BITS;
+        let mut dither = Dither::new();
+
+        self.convert_with(|value, bits_per_sample| match lossy && !T::FLOAT {
+            true => value + dither.next(bits_per_sample),
+            false => value,
+        })
+    }
+
+    fn convert_with<T: Sample>(&self, mut process: impl FnMut(f64, u16) -> f64) -> WavData<T> {
+        let (bits_per_sample, valid_bits_per_sample) =
+            if !T::FLOAT && !S::FLOAT && self.bits_per_sample <= T::BITS {
+                (self.bits_per_sample, self.valid_bits_per_sample)
+            } else {
+                (T::BITS, T::BITS)
+            };
+
+        let audiodata = self
+            .audiodata
+            .iter()
+            .map(|channel| {
+                channel
+                    .iter()
+                    .map(|sample| {
+                        let value = process(sample.to_f64(self.bits_per_sample), bits_per_sample);
+                        T::from_f64(value, bits_per_sample)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        WavData {
+            num_channels: self.num_channels,
+            samplerate: self.samplerate,
+            bits_per_sample,
+            valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata,
+            metadata: self.metadata.clone(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// Creates a vector of WAV bytes from the audio data and its metadata, with the bit
+    /// depth of `bits_per_sample`. Integer samples must fit in its range.
+    pub fn create_bytes(&self) -> Result<Vec<u8>> {
+        let builder = WriterBuilder::new()
+            .channels(self.num_channels)
+            .samplerate(self.samplerate)
+            .bit_depth(self.bits_per_sample)
+            .float(S::FLOAT)
+            .metadata(self.metadata.clone());
+
+        S::create_bytes(&self.audiodata, &builder)
+    }
+
+    /// Writes the audio data and its metadata into a WAV file, like
+    /// [`create_bytes`](Self::create_bytes).
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        crate::write::write_file(&self.create_bytes()?, path)
+    }
+}
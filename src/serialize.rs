@@ -0,0 +1,155 @@
+//! Serde support, including how the samples of [`WavData`] are serialized.
+
+use crate::WavData;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// How [`WavData::with_payload`] serializes the samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplePayload {
+    /// One array of numbers per channel, as the derived `Serialize` does.
+    #[default]
+    Numbers,
+    /// One base64 string per channel, of the little-endian bytes of its samples.
+    Base64,
+    /// No samples, leaving only the spec and metadata.
+    Skip,
+}
+
+/// Sample types that can be stored as base64 strings.
+pub trait Base64Sample: Copy {
+    /// Size of a sample in bytes.
+    const SIZE: usize;
+
+    fn to_le_bytes(self, buf: &mut Vec<u8>);
+
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_base64_sample {
+    ($($sample:ty),*) => {
+        $(
+            impl Base64Sample for $sample {
+                const SIZE: usize = std::mem::size_of::<$sample>();
+
+                fn to_le_bytes(self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&<$sample>::to_le_bytes(self));
+                }
+
+                fn from_le_bytes(bytes: &[u8]) -> Self {
+                    <$sample>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_base64_sample!(i16, i32, f32, f64);
+
+/// [`WavData`] serialized with its samples in the given form.
+pub struct WithPayload<'a, S> {
+    data: &'a WavData<S>,
+    payload: SamplePayload,
+}
+
+impl<S> WavData<S> {
+    /// Returns a view that serializes the samples in the given form, e.g. to keep large
+    /// payloads out of a JSON dump. The derived `Deserialize` reads any of them back.
+    pub fn with_payload(&self, payload: SamplePayload) -> WithPayload<'_, S> {
+        WithPayload {
+            data: self,
+            payload,
+        }
+    }
+}
+
+impl<S: Serialize + Base64Sample> Serialize for WithPayload<'_, S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        let data = self.data;
+
+        let mut state = serializer.serialize_struct("WavData", 8)?;
+        state.serialize_field("num_channels", &data.num_channels)?;
+        state.serialize_field("samplerate", &data.samplerate)?;
+        state.serialize_field("bits_per_sample", &data.bits_per_sample)?;
+        state.serialize_field("valid_bits_per_sample", &data.valid_bits_per_sample)?;
+        state.serialize_field("channel_mask", &data.channel_mask)?;
+        match self.payload {
+            SamplePayload::Numbers => state.serialize_field("audiodata", &data.audiodata)?,
+            SamplePayload::Base64 => {
+                let channels: Vec<String> = data
+                    .audiodata
+                    .iter()
+                    .map(|channel| {
+                        let mut bytes = Vec::with_capacity(channel.len() * S::SIZE);
+                        for &sample in channel {
+                            sample.to_le_bytes(&mut bytes);
+                        }
+                        STANDARD.encode(bytes)
+                    })
+                    .collect();
+                state.serialize_field("audiodata", &channels)?;
+            }
+            SamplePayload::Skip => state.skip_field("audiodata")?,
+        }
+        state.serialize_field("metadata", &data.metadata)?;
+        state.serialize_field("warnings", &data.warnings)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AudioData<S> {
+    Numbers(Vec<Vec<S>>),
+    Base64(Vec<String>),
+}
+
+pub(crate) fn deserialize_audiodata<'de, D, S>(deserializer: D) -> Result<Vec<Vec<S>>, D::Error>
+where
+    D: Deserializer<'de>,
+    S: Deserialize<'de> + Base64Sample,
+{
+    let channels = match AudioData::deserialize(deserializer)? {
+        AudioData::Numbers(channels) => return Ok(channels),
+        AudioData::Base64(channels) => channels,
+    };
+
+    channels
+        .iter()
+        .map(|channel| {
+            let bytes = STANDARD.decode(channel).map_err(D::Error::custom)?;
+            if bytes.len() % S::SIZE != 0 {
+                return Err(D::Error::custom("base64 samples end with a partial sample"));
+            }
+            Ok(bytes.chunks_exact(S::SIZE).map(S::from_le_bytes).collect())
+        })
+        .collect()
+}
+
+/// Serde functions for the 64-byte UMID of the bext chunk, which is too long for serde's
+/// array support.
+pub(crate) mod umid {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        umid: &[u8; 64],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(umid)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; 64], D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        let len = bytes.len();
+
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"64 bytes"))
+    }
+}
@@ -0,0 +1,167 @@
+//! Audio data whose channels are shared between clones, for non-destructive editing.
+
+use crate::metadata::WavMetadata;
+use crate::read::frames_in;
+use crate::{ParseWarning, WavData};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// WAV info and audio data like [`WavData`], whose channels are reference counted, so
+/// cloning doesn't copy samples. A channel is copied when it is first mutated while shared
+/// with a clone, and the other channels stay shared. The gain, fade, mixing and silence
+/// operations of [`WavData`] work on it as well, and
+/// [`into_wavdata`](SharedWavData::into_wavdata) converts it for the others.
+#[derive(Clone)]
+pub struct SharedWavData<S = i16> {
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub bits_per_sample: u16,
+    pub valid_bits_per_sample: u16,
+    pub channel_mask: u32,
+    pub audiodata: Vec<Arc<Vec<S>>>,
+    pub metadata: WavMetadata,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<S> SharedWavData<S> {
+    /// Number of frames, i.e. samples per channel.
+    pub fn num_frames(&self) -> usize {
+        self.audiodata.first().map_or(0, |channel| channel.len())
+    }
+
+    /// Samples of channel `index`, or `None` if there is no such channel.
+    pub fn channel(&self, index: usize) -> Option<&[S]> {
+        self.audiodata.get(index).map(|channel| channel.as_slice())
+    }
+
+    /// Returns an iterator over the samples of each channel in turn.
+    pub fn channels(&self) -> impl ExactSizeIterator<Item = &[S]> + '_ {
+        self.audiodata.iter().map(|channel| channel.as_slice())
+    }
+
+    /// Index of the frame at a time, clamped to the number of frames.
+    pub(crate) fn frame_at(&self, time: Duration) -> usize {
+        frames_in(time, self.samplerate).min(self.num_frames())
+    }
+
+    /// Copies the fields other than the audio data.
+    pub(crate) fn with_audiodata(&self, audiodata: Vec<Arc<Vec<S>>>) -> SharedWavData<S> {
+        SharedWavData {
+            num_channels: self.num_channels,
+            samplerate: self.samplerate,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata,
+            metadata: self.metadata.clone(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// Whether channel `index` is shared with a clone, so mutating it would copy it.
+    pub fn is_shared(&self, index: usize) -> bool {
+        self.audiodata
+            .get(index)
+            .is_some_and(|channel| Arc::strong_count(channel) > 1)
+    }
+}
+
+impl<S: Clone> SharedWavData<S> {
+    /// Copies frames `range`, clamped to each channel, and the other fields. Channels
+    /// holding the whole range are shared rather than copied.
+    pub(crate) fn frame_range(&self, range: Range<usize>) -> SharedWavData<S> {
+        self.with_audiodata(
+            self.audiodata
+                .iter()
+                .map(|channel| {
+                    let start = range.start.min(channel.len());
+                    let end = range.end.min(channel.len());
+                    if (start, end) == (0, channel.len()) {
+                        Arc::clone(channel)
+                    } else {
+                        Arc::new(channel[start..end].to_vec())
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Mutable samples of channel `index`, or `None` if there is no such channel. The
+    /// channel is copied first if it is shared with a clone.
+    pub fn channel_mut(&mut self, index: usize) -> Option<&mut [S]> {
+        self.audiodata
+            .get_mut(index)
+            .map(|channel| Arc::make_mut(channel).as_mut_slice())
+    }
+
+    /// Returns an iterator over the mutable samples of each channel in turn. Channels are
+    /// copied as they are reached if they are shared with a clone.
+    pub fn channels_mut(&mut self) -> impl ExactSizeIterator<Item = &mut [S]> + '_ {
+        self.audiodata
+            .iter_mut()
+            .map(|channel| Arc::make_mut(channel).as_mut_slice())
+    }
+
+    /// Returns the audio data with channels of its own, copying those that are shared.
+    pub fn into_wavdata(self) -> WavData<S> {
+        WavData {
+            num_channels: self.num_channels,
+            samplerate: self.samplerate,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata: self
+                .audiodata
+                .into_iter()
+                .map(Arc::unwrap_or_clone)
+                .collect(),
+            metadata: self.metadata,
+            warnings: self.warnings,
+        }
+    }
+}
+
+impl<S> fmt::Debug for SharedWavData<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedWavData")
+            .field("num_channels", &self.num_channels)
+            .field("samplerate", &self.samplerate)
+            .field("bits_per_sample", &self.bits_per_sample)
+            .field("valid_bits_per_sample", &self.valid_bits_per_sample)
+            .field("channel_mask", &self.channel_mask)
+            .field("num_frames", &self.num_frames())
+            .field("metadata", &self.metadata)
+            .field("warnings", &self.warnings)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> WavData<S> {
+    /// Moves the channels into reference counted storage, without copying them.
+    pub fn into_shared(self) -> SharedWavData<S> {
+        SharedWavData {
+            num_channels: self.num_channels,
+            samplerate: self.samplerate,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            audiodata: self.audiodata.into_iter().map(Arc::new).collect(),
+            metadata: self.metadata,
+            warnings: self.warnings,
+        }
+    }
+}
+
+impl<S> From<WavData<S>> for SharedWavData<S> {
+    fn from(wavdata: WavData<S>) -> Self {
+        wavdata.into_shared()
+    }
+}
+
+impl<S: Clone> From<SharedWavData<S>> for WavData<S> {
+    fn from(shared: SharedWavData<S>) -> Self {
+        shared.into_wavdata()
+    }
+}
@@ -0,0 +1,178 @@
+//! Detection and removal of silence in audio data.
+
+use crate::error::Result;
+use crate::read::frames_in;
+use crate::write::validate_channels;
+use crate::{Sample, SharedWavData, WavData};
+use std::ops::Range;
+use std::time::Duration;
+
+impl<S: Sample> WavData<S> {
+    /// Returns the audio data without its leading and trailing silence, i.e. the frames
+    /// whose samples in all channels are quieter than `threshold_dbfs`, e.g. -50.0. Silence
+    /// shorter than `min_duration` is kept, so quiet fade ins and outs survive. The other
+    /// fields are kept as they are. Channels must be of equal length.
+    pub fn trim_silence(&self, threshold_dbfs: f32, min_duration: Duration) -> Result<WavData<S>> {
+        let min_frames = frames_in(min_duration, self.samplerate);
+        let range = trim_range(
+            &self.audiodata,
+            self.bits_per_sample,
+            threshold_dbfs,
+            min_frames,
+        )?;
+
+        Ok(self.frame_range(range.start, range.end))
+    }
+
+    /// Splits the audio data into its sounds, e.g. the utterances of a recording, which are
+    /// separated by silence of at least `min_gap`, silence being frames whose samples in
+    /// all channels are quieter than `threshold_dbfs`. The silence between the sounds, and
+    /// before and after them, is left out. The other fields are kept in each part.
+    /// Channels must be of equal length.
+    pub fn split_on_silence(
+        &self,
+        threshold_dbfs: f32,
+        min_gap: Duration,
+    ) -> Result<Vec<WavData<S>>> {
+        Ok(self
+            .sound_ranges(threshold_dbfs, min_gap)?
+            .into_iter()
+            .map(|range| self.frame_range(range.start, range.end))
+            .collect())
+    }
+
+    /// Frame ranges of the sounds that [`split_on_silence`](Self::split_on_silence) would
+    /// split the audio data into, for cutting it up without copying it.
+    pub fn sound_ranges(
+        &self,
+        threshold_dbfs: f32,
+        min_gap: Duration,
+    ) -> Result<Vec<Range<usize>>> {
+        let min_frames = frames_in(min_gap, self.samplerate);
+        sound_ranges(
+            &self.audiodata,
+            self.bits_per_sample,
+            threshold_dbfs,
+            min_frames,
+        )
+    }
+}
+
+impl<S: Sample> SharedWavData<S> {
+    /// Returns the audio data without its leading and trailing silence, like
+    /// [`WavData::trim_silence`].
+    pub fn trim_silence(
+        &self,
+        threshold_dbfs: f32,
+        min_duration: Duration,
+    ) -> Result<SharedWavData<S>> {
+        let channels: Vec<&[S]> = self.channels().collect();
+        let min_frames = frames_in(min_duration, self.samplerate);
+        let range = trim_range(&channels, self.bits_per_sample, threshold_dbfs, min_frames)?;
+
+        Ok(self.frame_range(range))
+    }
+
+    /// Splits the audio data into its sounds, like [`WavData::split_on_silence`].
+    pub fn split_on_silence(
+        &self,
+        threshold_dbfs: f32,
+        min_gap: Duration,
+    ) -> Result<Vec<SharedWavData<S>>> {
+        Ok(self
+            .sound_ranges(threshold_dbfs, min_gap)?
+            .into_iter()
+            .map(|range| self.frame_range(range))
+            .collect())
+    }
+
+    /// Frame ranges of the sounds, like [`WavData::sound_ranges`].
+    pub fn sound_ranges(
+        &self,
+        threshold_dbfs: f32,
+        min_gap: Duration,
+    ) -> Result<Vec<Range<usize>>> {
+        let channels: Vec<&[S]> = self.channels().collect();
+        let min_frames = frames_in(min_gap, self.samplerate);
+        sound_ranges(&channels, self.bits_per_sample, threshold_dbfs, min_frames)
+    }
+}
+
+/// Frames left after trimming silence of at least `min_frames` from both ends.
+fn trim_range<S: Sample, C: AsRef<[S]>>(
+    audiodata: &[C],
+    bits: u16,
+    threshold_dbfs: f32,
+    min_frames: usize,
+) -> Result<Range<usize>> {
+    let num_frames = validate_channels(audiodata)?;
+    let min_frames = min_frames.max(1);
+    let first = sound_frames(audiodata, bits, threshold_dbfs).position(|sound| sound);
+    let last = sound_frames(audiodata, bits, threshold_dbfs).rposition(|sound| sound);
+
+    let range = match (first, last) {
+        (Some(first), Some(last)) => {
+            let start = if first >= min_frames { first } else { 0 };
+            let end = if num_frames - (last + 1) >= min_frames {
+                last + 1
+            } else {
+                num_frames
+            };
+            start..end
+        }
+        _ if num_frames >= min_frames => 0..0,
+        _ => 0..num_frames,
+    };
+
+    Ok(range)
+}
+
+/// Frame ranges of the sounds separated by silence of at least `min_frames`.
+fn sound_ranges<S: Sample, C: AsRef<[S]>>(
+    audiodata: &[C],
+    bits: u16,
+    threshold_dbfs: f32,
+    min_frames: usize,
+) -> Result<Vec<Range<usize>>> {
+    validate_channels(audiodata)?;
+    let min_frames = min_frames.max(1);
+
+    let mut ranges = vec![];
+    let mut current: Option<Range<usize>> = None;
+    for (f, sound) in sound_frames(audiodata, bits, threshold_dbfs).enumerate() {
+        if !sound {
+            continue;
+        }
+
+        current = match current {
+            Some(range) if f - range.end < min_frames => Some(range.start..f + 1),
+            Some(range) => {
+                ranges.push(range);
+                Some(f..f + 1)
+            }
+            None => Some(f..f + 1),
+        };
+    }
+    ranges.extend(current);
+
+    Ok(ranges)
+}
+
+/// Returns an iterator telling for each frame of channels of equal length whether any of
+/// its samples is at least as loud as `threshold_dbfs`.
+fn sound_frames<S: Sample, C: AsRef<[S]>>(
+    audiodata: &[C],
+    bits: u16,
+    threshold_dbfs: f32,
+) -> impl DoubleEndedIterator<Item = bool> + ExactSizeIterator + '_ {
+    let threshold = 10f64.powf(threshold_dbfs as f64 / 20.0);
+    let num_frames = audiodata
+        .first()
+        .map_or(0, |channel| channel.as_ref().len());
+
+    (0..num_frames).map(move |f| {
+        audiodata
+            .iter()
+            .any(|channel| channel.as_ref()[f].to_f64(bits).abs() >= threshold)
+    })
+}
@@ -0,0 +1,146 @@
+//! Level statistics of audio data, for quality checks.
+
+use crate::{Sample, WavData};
+
+/// Levels of a channel, as measured by [`stats`](WavData::stats). Levels are relative to
+/// full scale, i.e. 1.0, like samples converted to float. A channel without samples
+/// measures 0 throughout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelStats<S> {
+    /// Lowest sample.
+    pub min: S,
+    /// Highest sample.
+    pub max: S,
+    /// Highest magnitude of a sample.
+    pub peak: f64,
+    /// Root mean square, the level of the signal's power.
+    pub rms: f64,
+    /// Mean of the samples, which is 0.0 unless the signal is biased.
+    pub dc_offset: f64,
+}
+
+/// A run of full scale samples in a channel, as found by
+/// [`detect_clipping`](WavData::detect_clipping).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClipRegion {
+    /// Index of the clipped channel.
+    pub channel: usize,
+    /// Index of the first clipped sample.
+    pub start: usize,
+    /// Index after the last clipped sample.
+    pub end: usize,
+}
+
+impl ClipRegion {
+    /// Number of clipped samples.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the region has no samples.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl<S> ChannelStats<S> {
+    /// Peak level in dBFS, or negative infinity for silence.
+    pub fn peak_dbfs(&self) -> f64 {
+        20.0 * self.peak.log10()
+    }
+
+    /// RMS level in dBFS, or negative infinity for silence. A full scale sine measures
+    /// about -3 dBFS.
+    pub fn rms_dbfs(&self) -> f64 {
+        20.0 * self.rms.log10()
+    }
+}
+
+impl<S: Sample> WavData<S> {
+    /// Measures the levels of each channel in one pass over it.
+    pub fn stats(&self) -> Vec<ChannelStats<S>> {
+        let bits = self.bits_per_sample;
+
+        self.audiodata
+            .iter()
+            .map(|channel| {
+                let Some(&first) = channel.first() else {
+                    let zero = S::from_f64(0.0, bits);
+                    return ChannelStats {
+                        min: zero,
+                        max: zero,
+                        peak: 0.0,
+                        rms: 0.0,
+                        dc_offset: 0.0,
+                    };
+                };
+
+                let (mut min, mut max) = (first, first);
+                let (mut sum, mut sum_squares) = (0.0, 0.0);
+                for &sample in channel {
+                    let value = sample.to_f64(bits);
+                    if value < min.to_f64(bits) {
+                        min = sample;
+                    }
+                    if value > max.to_f64(bits) {
+                        max = sample;
+                    }
+                    sum += value;
+                    sum_squares += value * value;
+                }
+
+                let len = channel.len() as f64;
+                ChannelStats {
+                    min,
+                    max,
+                    peak: min.to_f64(bits).abs().max(max.to_f64(bits).abs()),
+                    rms: (sum_squares / len).sqrt(),
+                    dc_offset: sum / len,
+                }
+            })
+            .collect()
+    }
+
+    /// Finds the runs of at least `consecutive_full_scale` samples at full scale in each
+    /// channel, which mark a recording that clipped, since music rarely reaches full scale
+    /// for more than a sample or two in a row. Float samples count from ±1.0 on. Regions are
+    /// ordered by channel, then by position.
+    pub fn detect_clipping(&self, consecutive_full_scale: usize) -> Vec<ClipRegion> {
+        let bits = self.bits_per_sample;
+        // the highest positive integer sample is one step below 1.0
+        let max = S::from_f64(1.0, bits).to_f64(bits);
+        let min_len = consecutive_full_scale.max(1);
+        let mut regions = vec![];
+
+        for (channel, samples) in self.audiodata.iter().enumerate() {
+            let mut start = None;
+            for (i, sample) in samples.iter().enumerate() {
+                let value = sample.to_f64(bits);
+                let clipped = value >= max || value <= -1.0;
+                match (clipped, start) {
+                    (true, None) => start = Some(i),
+                    (false, Some(first)) => {
+                        if i - first >= min_len {
+                            regions.push(ClipRegion {
+                                channel,
+                                start: first,
+                                end: i,
+                            });
+                        }
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(first) = start.filter(|&first| samples.len() - first >= min_len) {
+                regions.push(ClipRegion {
+                    channel,
+                    start: first,
+                    end: samples.len(),
+                });
+            }
+        }
+
+        regions
+    }
+}
@@ -0,0 +1,127 @@
+//! Decoding of WAV files with codecs onda lacks, e.g. MP3-in-WAV, through Symphonia.
+//! Decoded samples are packed into the layout of a 16-bit WAV data chunk so they share the
+//! WAV decoders.
+
+use crate::encoded::parse_encoded;
+use crate::error::{OndaError, Result};
+use crate::metadata;
+use crate::read::{parse_riff_chunk, SampleFormat, Spec};
+use std::borrow::Cow;
+use std::io::Cursor;
+use symphonia::core::codecs::audio::AudioDecoderOptions;
+use symphonia::core::errors::Error;
+use symphonia::core::formats::probe::Hint;
+use symphonia::core::formats::{FormatOptions, TrackType};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+
+const WAVE_FORMAT_MPEG: u16 = 0x50;
+const WAVE_FORMAT_MPEGLAYER3: u16 = 0x55;
+const WAVE_FORMAT_MPEG_ADTS_AAC: u16 = 0x1600;
+const WAVE_FORMAT_FLAC: u16 = 0xF1AC;
+
+/// Decodes the data chunk of a WAV file whose codec onda doesn't support, and returns a
+/// 16-bit spec along with the samples, in the layout of a WAV data chunk. Streams Symphonia
+/// can't decode either keep the `UnsupportedCodec` error.
+pub(crate) fn parse_riff_stream(buf: &[u8]) -> Result<(Spec, Cow<'_, [u8]>)> {
+    let encoded = parse_encoded(buf)?;
+    let unsupported = OndaError::UnsupportedCodec {
+        tag: encoded.format_tag as u32,
+    };
+
+    let mut hint = Hint::new();
+    match encoded.format_tag {
+        WAVE_FORMAT_MPEG => hint.with_extension("mp2"),
+        WAVE_FORMAT_MPEGLAYER3 => hint.with_extension("mp3"),
+        WAVE_FORMAT_MPEG_ADTS_AAC => hint.with_extension("aac"),
+        WAVE_FORMAT_FLAC => hint.with_extension("flac"),
+        _ => &mut hint,
+    };
+
+    let source = MediaSourceStream::new(Box::new(Cursor::new(encoded.data)), Default::default());
+    let Ok(mut reader) = symphonia::default::get_probe().probe(
+        &hint,
+        source,
+        FormatOptions::default(),
+        MetadataOptions::default(),
+    ) else {
+        return Err(unsupported);
+    };
+
+    let Some(track) = reader.default_track(TrackType::Audio) else {
+        return Err(unsupported);
+    };
+    let track_id = track.id;
+    let Some(params) = track
+        .codec_params
+        .as_ref()
+        .and_then(|params| params.audio())
+    else {
+        return Err(unsupported);
+    };
+    let Ok(mut decoder) = symphonia::default::get_codecs()
+        .make_audio_decoder(params, &AudioDecoderOptions::default())
+    else {
+        return Err(unsupported);
+    };
+
+    let mut format = None;
+    let mut samples: Vec<i16> = vec![];
+    let mut data = Vec::new();
+
+    while let Some(packet) = reader.next_packet()? {
+        if packet.track_id != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // corrupt packets are skipped, as players do
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = decoded.spec();
+        let packet_format = (spec.channels().count(), spec.rate());
+        match format {
+            None => format = Some(packet_format),
+            Some(f) if f != packet_format => {
+                return Err(OndaError::Unsupported("stream format change"))
+            }
+            Some(_) => {}
+        }
+
+        samples.resize(decoded.samples_interleaved(), 0);
+        decoded.copy_to_slice_interleaved(&mut samples);
+        for sample in &samples {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    let Some((num_channels, samplerate)) = format else {
+        return Err(OndaError::Malformed("no audio packets found"));
+    };
+    if num_channels == 0 || num_channels > u16::MAX as usize {
+        return Err(OndaError::UnsupportedChannels(num_channels as u32));
+    }
+    let num_channels = num_channels as u16;
+
+    let mut offset = 0;
+    let (endian, data_size) = parse_riff_chunk(buf, &mut offset)?;
+
+    let spec = Spec {
+        format: SampleFormat::Int,
+        num_channels,
+        samplerate,
+        block_align: num_channels * 2,
+        bits_per_sample: 16,
+        valid_bits_per_sample: 16,
+        channel_mask: 0,
+        adpcm_coefficients: vec![],
+        num_frames: None,
+        metadata: metadata::parse_metadata(buf, endian, data_size),
+        warnings: vec![],
+    };
+
+    Ok((spec, Cow::Owned(data)))
+}
@@ -0,0 +1,336 @@
+//! Conformance checks of RIFF WAV files, for QC before delivery.
+
+use crate::read::{
+    parse_fmt_payload, parse_u16, parse_u32, parse_u64, Endianness, ParseOptions, ParseWarning,
+    Spec,
+};
+use crate::{WAVE_FORMAT_EXTENSIBLE, WAVE_FORMAT_PCM};
+use std::fmt;
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The file deviates from the spec, but decoders commonly accept it.
+    Warning,
+    /// The file is corrupt or contradicts the spec.
+    Error,
+}
+
+/// A problem found by [`validate`], at a byte offset from the start of the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub offset: u64,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity} at offset {}: {}", self.offset, self.message)
+    }
+}
+
+/// Issues found by [`validate`], in the order of their offsets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no errors were found. Warnings do not count.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(Severity::Warning)
+    }
+
+    fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(move |i| i.severity == severity)
+    }
+
+    fn warn(&mut self, offset: usize, message: impl Into<String>) {
+        self.push(Severity::Warning, offset, message.into());
+    }
+
+    fn error(&mut self, offset: usize, message: impl Into<String>) {
+        self.push(Severity::Error, offset, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, offset: usize, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            offset: offset as u64,
+            message,
+        });
+    }
+}
+
+/// Checks the RIFF, RIFX or RF64 WAV file in a byte slice buffer for chunk alignment, size
+/// fields, padding bytes, fact chunk consistency and fmt chunk conformance. Unlike the
+/// parse functions, it reports every problem instead of stopping at the first one.
+pub fn validate(buf: impl AsRef<[u8]>) -> ValidationReport {
+    let buf = buf.as_ref();
+    let mut report = ValidationReport::default();
+
+    if buf.len() < 12 {
+        report.error(0, "file too small for a RIFF header");
+        return report;
+    }
+
+    let (endian, rf64) = match &buf[..4] {
+        b"RIFF" => (Endianness::Little, false),
+        b"RIFX" => (Endianness::Big, false),
+        b"RF64" | b"BW64" => (Endianness::Little, true),
+        _ => {
+            report.error(0, "not a RIFF file");
+            return report;
+        }
+    };
+    if &buf[8..12] != b"WAVE" {
+        report.error(8, "RIFF form type is not WAVE");
+        return report;
+    }
+
+    let mut riff_size = read_u32(buf, 4, endian) as u64;
+    let mut ds64_data_size = None;
+    let mut ds64_frames = None;
+
+    if rf64 {
+        if riff_size != u32::MAX as u64 {
+            report.warn(4, "RF64 size field is not 0xFFFFFFFF");
+        }
+
+        match buf.get(12..16) {
+            Some(b"ds64") if read_u32(buf, 16, endian) >= 28 && buf.len() >= 48 => {
+                riff_size = read_u64(buf, 20);
+                ds64_data_size = Some(read_u64(buf, 28));
+                ds64_frames = Some(read_u64(buf, 36));
+            }
+            Some(b"ds64") => report.error(12, "ds64 chunk too small"),
+            _ => report.error(12, "ds64 chunk missing after RF64 header"),
+        }
+    }
+
+    let riff_end = match riff_size.checked_add(8) {
+        Some(end) if end <= buf.len() as u64 => end as usize,
+        _ => {
+            report.error(4, "RIFF size exceeds file size");
+            buf.len()
+        }
+    };
+    if riff_end < buf.len() {
+        report.warn(riff_end, "trailing bytes after RIFF chunk");
+    }
+
+    let mut found_fmt = false;
+    let mut fmt: Option<(usize, Spec, u16)> = None;
+    let mut data: Option<(usize, u64)> = None;
+    let mut fact: Option<(usize, u64)> = None;
+
+    let mut offset = 12;
+    while offset < riff_end {
+        if offset + 8 > riff_end {
+            report.error(offset, "truncated chunk header");
+            break;
+        }
+
+        let id: [u8; 4] = buf[offset..offset + 4].try_into().unwrap();
+        let name = String::from_utf8_lossy(&id).into_owned();
+        let mut size = read_u32(buf, offset + 4, endian) as u64;
+        if id == *b"data" && rf64 && size == u32::MAX as u64 {
+            size = ds64_data_size.unwrap_or(size);
+        }
+
+        if !id.iter().all(|&b| (0x20..0x7F).contains(&b)) {
+            report.error(offset, format!("chunk ID {id:02X?} is not printable ASCII"));
+        }
+
+        let payload_offset = offset + 8;
+        let Some(end) = usize::try_from(size)
+            .ok()
+            .and_then(|size| payload_offset.checked_add(size))
+            .filter(|&end| end <= riff_end)
+        else {
+            report.error(offset, format!("{name} chunk size exceeds RIFF size"));
+            break;
+        };
+        let payload = &buf[payload_offset..end];
+
+        match &id {
+            b"fmt " if found_fmt => report.error(offset, "duplicate fmt chunk"),
+            b"fmt " => {
+                found_fmt = true;
+                if data.is_some() {
+                    report.warn(offset, "fmt chunk after data chunk");
+                }
+                fmt = check_fmt_chunk(&mut report, payload, payload_offset, endian)
+                    .map(|(spec, format_tag)| (offset, spec, format_tag));
+            }
+            b"data" if data.is_some() => report.error(offset, "duplicate data chunk"),
+            b"data" => data = Some((offset, size)),
+            b"fact" if payload.len() < 4 => report.error(offset, "fact chunk too small"),
+            b"fact" => {
+                let frames = match read_u32(payload, 0, endian) {
+                    u32::MAX if rf64 => ds64_frames.unwrap_or(u32::MAX as u64),
+                    frames => frames as u64,
+                };
+                fact = Some((offset, frames));
+            }
+            _ => {}
+        }
+
+        // chunks are padded to an even size
+        offset = end;
+        if size % 2 == 1 {
+            match buf.get(end) {
+                Some(_) if end >= riff_end => {
+                    report.warn(
+                        end,
+                        format!("padding byte of {name} chunk outside RIFF chunk"),
+                    );
+                }
+                Some(0) => {}
+                Some(_) => report.warn(end, format!("padding byte of {name} chunk is not zero")),
+                None => report.warn(end, format!("padding byte of {name} chunk missing")),
+            }
+            offset += 1;
+        }
+    }
+
+    if data.is_none() {
+        report.error(12, "data chunk missing");
+    }
+
+    match (fmt, data) {
+        (Some((fmt_offset, spec, format_tag)), Some((data_offset, data_size))) => {
+            check_frames(&mut report, &spec, data_offset, data_size, fact);
+
+            // formats other than PCM need a fact chunk
+            if fact.is_none() && format_tag != WAVE_FORMAT_PCM {
+                report.warn(fmt_offset, "fact chunk missing for non-PCM format");
+            }
+        }
+        // an unparsable fmt chunk was reported already
+        (None, _) if !found_fmt => report.error(12, "fmt chunk missing"),
+        _ => {}
+    }
+
+    report.issues.sort_by_key(|issue| issue.offset);
+    report
+}
+
+/// Checks the contents of a fmt chunk, and returns its spec and format tag if it can be
+/// decoded.
+fn check_fmt_chunk(
+    report: &mut ValidationReport,
+    payload: &[u8],
+    payload_offset: usize,
+    endian: Endianness,
+) -> Option<(Spec, u16)> {
+    let options = ParseOptions { strict: false };
+    let spec = match parse_fmt_payload(payload, &mut 0, payload.len(), endian, options) {
+        Ok(spec) => spec,
+        Err(err) => {
+            report.error(payload_offset, format!("{err}"));
+            return None;
+        }
+    };
+
+    for warning in &spec.warnings {
+        let field_offset = match warning {
+            ParseWarning::ByteRateMismatch { .. } => 8,
+            _ => 12,
+        };
+        report.warn(payload_offset + field_offset, format!("{warning}"));
+    }
+
+    let mut format_tag = parse_u16(payload, &mut 0, endian).ok()?;
+    let extensible = format_tag == WAVE_FORMAT_EXTENSIBLE;
+    if extensible {
+        format_tag = parse_u16(payload, &mut 24, endian).ok()?;
+
+        let mask_channels = spec.channel_mask.count_ones();
+        if spec.channel_mask != 0 && mask_channels != spec.num_channels as u32 {
+            report.warn(
+                payload_offset + 20,
+                format!(
+                    "channel mask has {mask_channels} channels instead of {}",
+                    spec.num_channels
+                ),
+            );
+        }
+    } else if format_tag == WAVE_FORMAT_PCM && (spec.num_channels > 2 || spec.bits_per_sample > 16)
+    {
+        report.warn(
+            payload_offset,
+            "PCM with more than 2 channels or 16 bits should use WAVE_FORMAT_EXTENSIBLE",
+        );
+    }
+
+    if format_tag == WAVE_FORMAT_PCM && !extensible && !matches!(payload.len(), 16 | 18) {
+        report.warn(
+            payload_offset - 4,
+            format!("fmt chunk of PCM is {} bytes instead of 16", payload.len()),
+        );
+    }
+
+    Some((spec, format_tag))
+}
+
+/// Checks that the data chunk holds whole frames or blocks, and that they agree with the
+/// frame count of the fact chunk.
+fn check_frames(
+    report: &mut ValidationReport,
+    spec: &Spec,
+    data_offset: usize,
+    data_size: u64,
+    fact: Option<(usize, u64)>,
+) {
+    let block_align = spec.block_align as u64;
+    if !data_size.is_multiple_of(block_align) {
+        report.warn(data_offset, "data chunk ends with a partial block");
+    }
+    let num_blocks = data_size / block_align;
+
+    let Some((fact_offset, fact_frames)) = fact else {
+        return;
+    };
+
+    // the last compressed block may be padded
+    let (min_frames, max_frames) = match spec.frames_per_block() {
+        Some(frames_per_block) => {
+            let frames_per_block = frames_per_block as u64;
+            let max_frames = num_blocks * frames_per_block;
+            (max_frames.saturating_sub(frames_per_block - 1), max_frames)
+        }
+        None => (num_blocks, num_blocks),
+    };
+
+    if !(min_frames..=max_frames).contains(&fact_frames) {
+        report.error(
+            fact_offset,
+            format!("fact chunk has {fact_frames} frames, but data chunk holds {max_frames}"),
+        );
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize, endian: Endianness) -> u32 {
+    parse_u32(buf, &mut { offset }, endian).unwrap_or(0)
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    parse_u64(buf, &mut { offset }, Endianness::Little).unwrap_or(0)
+}
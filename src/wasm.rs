@@ -0,0 +1,62 @@
+//! JavaScript bindings through wasm-bindgen, for decoding and encoding in the browser.
+
+use crate::{create_bytes, parse_bytes, WavData};
+use js_sys::{Array, Int16Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+/// WAV info and audio data decoded by `parseBytes`, exported to JavaScript as `WavData`.
+#[wasm_bindgen(js_name = WavData)]
+pub struct JsWavData(WavData);
+
+#[wasm_bindgen(js_class = WavData)]
+impl JsWavData {
+    #[wasm_bindgen(getter, js_name = numChannels)]
+    pub fn num_channels(&self) -> u16 {
+        self.0.num_channels
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn samplerate(&self) -> u32 {
+        self.0.samplerate
+    }
+
+    #[wasm_bindgen(getter, js_name = bitsPerSample)]
+    pub fn bits_per_sample(&self) -> u16 {
+        self.0.bits_per_sample
+    }
+
+    #[wasm_bindgen(getter, js_name = numFrames)]
+    pub fn num_frames(&self) -> usize {
+        self.0.num_frames()
+    }
+
+    /// Copy of the samples of a channel, or `undefined` if there is no such channel.
+    pub fn channel(&self, index: usize) -> Option<Int16Array> {
+        let channel = self.0.audiodata.get(index)?;
+        Some(Int16Array::from(&channel[..]))
+    }
+}
+
+impl From<JsWavData> for WavData {
+    fn from(data: JsWavData) -> Self {
+        data.0
+    }
+}
+
+/// Parses an 8 or 16-bit file from a `Uint8Array`, like [`parse_bytes`].
+#[wasm_bindgen(js_name = parseBytes)]
+pub fn parse_bytes_js(buf: &Uint8Array) -> Result<JsWavData, JsError> {
+    Ok(JsWavData(parse_bytes(buf.to_vec())?))
+}
+
+/// Creates 16-bit WAV bytes from an array of channels, each an `Int16Array` or an array of
+/// numbers, like [`create_bytes`].
+#[wasm_bindgen(js_name = createBytes)]
+pub fn create_bytes_js(channels: &Array, samplerate: u32) -> Result<Uint8Array, JsError> {
+    let audiodata: Vec<Vec<i16>> = channels
+        .iter()
+        .map(|channel| Int16Array::new(&channel).to_vec())
+        .collect();
+
+    Ok(Uint8Array::from(&create_bytes(audiodata, samplerate)?[..]))
+}
@@ -1,82 +1,1146 @@
-use anyhow::Result;
+use crate::error::{OndaError, Result};
+use crate::metadata::{self, RawChunk, WavMetadata};
+use crate::read::{frame_size, sample_type_mismatch, MULAW_BIAS};
+use crate::sample::Dither;
+use crate::{
+    Sample, SUBFORMAT_GUID_SUFFIX, WAVE_FORMAT_EXTENSIBLE, WAVE_FORMAT_IEEE_FLOAT,
+    WAVE_FORMAT_MULAW, WAVE_FORMAT_PCM,
+};
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::ops::RangeInclusive;
 use std::path::Path;
 
-const BITS_PER_SAMPLE: u16 = 16;
+/// Bit depth of integer PCM samples written by [`write_with_depth`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitDepth {
+    B8,
+    B16,
+    B24,
+    B32,
+}
 
-/// Creates a vector of WAV bytes from audio data.
-pub fn create_bytes(audiodata: impl AsRef<[Vec<i16>]>, samplerate: u32) -> Result<Vec<u8>> {
-    let audiodata = audiodata.as_ref();
+impl BitDepth {
+    pub(crate) fn bits(self) -> u16 {
+        match self {
+            BitDepth::B8 => 8,
+            BitDepth::B16 => 16,
+            BitDepth::B24 => 24,
+            BitDepth::B32 => 32,
+        }
+    }
+}
 
-    let num_channels = audiodata.len() as u16;
+/// Format of the WAV files created by a [`WriterBuilder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WavSpec {
+    pub num_channels: u16,
+    pub samplerate: u32,
+    pub bits_per_sample: u16,
+    /// Whether samples are IEEE floats rather than integers.
+    pub float: bool,
+}
 
-    let audiodata_size =
-        audiodata[0].len() as u32 * num_channels as u32 * BITS_PER_SAMPLE as u32 / 8;
+/// Builder of WAV files with a given format and metadata, e.g.
+/// `WriterBuilder::new().channels(2).samplerate(48000).bit_depth(24)`. It defaults to
+/// 16-bit stereo at 44100 Hz.
+#[derive(Clone, Debug)]
+pub struct WriterBuilder {
+    spec: WavSpec,
+    metadata: WavMetadata,
+    dither: bool,
+}
 
-    let mut buf = vec![];
-    write_riff_chunk(&mut buf, audiodata_size)?;
-    write_fmt_chunk(&mut buf, num_channels, samplerate)?;
-    write_data_chunk(&mut buf, audiodata, audiodata_size)?;
+impl Default for WriterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    Ok(buf)
+impl WriterBuilder {
+    pub fn new() -> Self {
+        Self::from_spec(WavSpec {
+            num_channels: 2,
+            samplerate: 44100,
+            bits_per_sample: 16,
+            float: false,
+        })
+    }
+
+    /// Starts from the format of an existing spec.
+    pub fn from_spec(spec: WavSpec) -> Self {
+        Self {
+            spec,
+            metadata: WavMetadata::default(),
+            dither: false,
+        }
+    }
+
+    pub fn channels(mut self, num_channels: u16) -> Self {
+        self.spec.num_channels = num_channels;
+        self
+    }
+
+    pub fn samplerate(mut self, samplerate: u32) -> Self {
+        self.spec.samplerate = samplerate;
+        self
+    }
+
+    /// Bits per sample, 8, 16, 24 or 32 for integers and 32 or 64 for floats.
+    pub fn bit_depth(mut self, bits_per_sample: u16) -> Self {
+        self.spec.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    pub fn float(mut self, float: bool) -> Self {
+        self.spec.float = float;
+        self
+    }
+
+    /// Metadata chunks written along with the audio data.
+    pub fn metadata(mut self, metadata: WavMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Whether float samples written in an integer format get TPDF dither before they are
+    /// rounded, so the rounding error doesn't correlate with the signal, e.g. when
+    /// exporting synthesized audio to 16 bits. Off by default.
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Returns the configured spec, after checking that it can be written.
+    pub fn build(&self) -> Result<WavSpec> {
+        let spec = self.spec;
+        if spec.num_channels == 0 {
+            return Err(OndaError::NoChannels);
+        }
+
+        let supported = match spec.float {
+            false => matches!(spec.bits_per_sample, 8 | 16 | 24 | 32),
+            true => matches!(spec.bits_per_sample, 32 | 64),
+        };
+        if !supported {
+            return Err(OndaError::UnsupportedBitDepth(spec.bits_per_sample as u32));
+        }
+
+        Ok(spec)
+    }
+
+    /// Creates a vector of WAV bytes from 16-bit audio data. The spec must be an integer
+    /// format, and samples must fit in the range of its bit depth.
+    pub fn create_bytes_i16<C: AsRef<[i16]>>(&self, audiodata: impl AsRef<[C]>) -> Result<Vec<u8>> {
+        let audiodata = audiodata.as_ref();
+        let spec = self.checked_spec(audiodata.len(), false)?;
+
+        if spec.bits_per_sample == 16 {
+            return build_bytes(
+                audiodata,
+                spec.samplerate,
+                WAVE_FORMAT_PCM,
+                16,
+                &self.metadata,
+                |buf, sample| buf.extend_from_slice(&sample.to_le_bytes()),
+            );
+        }
+
+        let audiodata: Vec<Vec<i32>> = audiodata
+            .iter()
+            .map(|channel| {
+                channel
+                    .as_ref()
+                    .iter()
+                    .map(|&sample| sample as i32)
+                    .collect()
+            })
+            .collect();
+        build_bytes_int(
+            &audiodata,
+            spec.samplerate,
+            spec.bits_per_sample,
+            &self.metadata,
+        )
+    }
+
+    /// Creates a vector of WAV bytes from 32-bit audio data. The spec must be an integer
+    /// format, and samples must fit in the range of its bit depth.
+    pub fn create_bytes_i32<C: AsRef<[i32]>>(&self, audiodata: impl AsRef<[C]>) -> Result<Vec<u8>> {
+        let audiodata = audiodata.as_ref();
+        let spec = self.checked_spec(audiodata.len(), false)?;
+
+        build_bytes_int(
+            audiodata,
+            spec.samplerate,
+            spec.bits_per_sample,
+            &self.metadata,
+        )
+    }
+
+    /// Creates a vector of WAV bytes from 32-bit float audio data. Integer formats round the
+    /// samples to their bit depth, with [`dither`](Self::dither) if enabled, and clip them.
+    pub fn create_bytes_f32<C: AsRef<[f32]>>(&self, audiodata: impl AsRef<[C]>) -> Result<Vec<u8>> {
+        let audiodata = audiodata.as_ref();
+        if !self.spec.float {
+            return self.create_bytes_i32(self.quantize(audiodata));
+        }
+        let spec = self.checked_spec(audiodata.len(), true)?;
+
+        build_bytes_float(audiodata, spec, &self.metadata, |sample| sample as f64)
+    }
+
+    /// Creates a vector of WAV bytes from 64-bit float audio data. Float formats of 32 bits
+    /// round the samples, and integer formats are like
+    /// [`create_bytes_f32`](Self::create_bytes_f32).
+    pub fn create_bytes_f64<C: AsRef<[f64]>>(&self, audiodata: impl AsRef<[C]>) -> Result<Vec<u8>> {
+        let audiodata = audiodata.as_ref();
+        if !self.spec.float {
+            return self.create_bytes_i32(self.quantize(audiodata));
+        }
+        let spec = self.checked_spec(audiodata.len(), true)?;
+
+        build_bytes_float(audiodata, spec, &self.metadata, |sample| sample)
+    }
+
+    /// Writes 16-bit audio data into a WAV file, like
+    /// [`create_bytes_i16`](Self::create_bytes_i16).
+    pub fn write_i16<C: AsRef<[i16]>>(
+        &self,
+        audiodata: impl AsRef<[C]>,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        write_file(&self.create_bytes_i16(audiodata)?, path)
+    }
+
+    /// Writes 32-bit audio data into a WAV file, like
+    /// [`create_bytes_i32`](Self::create_bytes_i32).
+    pub fn write_i32<C: AsRef<[i32]>>(
+        &self,
+        audiodata: impl AsRef<[C]>,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        write_file(&self.create_bytes_i32(audiodata)?, path)
+    }
+
+    /// Writes 32-bit float audio data into a WAV file, like
+    /// [`create_bytes_f32`](Self::create_bytes_f32).
+    pub fn write_f32<C: AsRef<[f32]>>(
+        &self,
+        audiodata: impl AsRef<[C]>,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        write_file(&self.create_bytes_f32(audiodata)?, path)
+    }
+
+    /// Writes 64-bit float audio data into a WAV file, like
+    /// [`create_bytes_f64`](Self::create_bytes_f64).
+    pub fn write_f64<C: AsRef<[f64]>>(
+        &self,
+        audiodata: impl AsRef<[C]>,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        write_file(&self.create_bytes_f64(audiodata)?, path)
+    }
+
+    /// Converts float samples to integers of the configured bit depth.
+    fn quantize<S: Sample, C: AsRef<[S]>>(&self, audiodata: &[C]) -> Vec<Vec<i32>> {
+        let bits_per_sample = self.spec.bits_per_sample;
+        let mut dither = Dither::new();
+
+        audiodata
+            .iter()
+            .map(|channel| {
+                channel
+                    .as_ref()
+                    .iter()
+                    .map(|sample| {
+                        let mut value = sample.to_f64(bits_per_sample);
+                        if self.dither {
+                            value += dither.next(bits_per_sample);
+                        }
+                        i32::from_f64(value, bits_per_sample)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the spec after checking it against the audio data to write.
+    fn checked_spec(&self, num_channels: usize, float: bool) -> Result<WavSpec> {
+        let spec = self.build()?;
+        if spec.float != float {
+            return Err(sample_type_mismatch(spec.float, spec.bits_per_sample));
+        }
+        if num_channels != spec.num_channels as usize {
+            return Err(OndaError::ChannelMismatch {
+                expected: spec.num_channels as usize,
+                found: num_channels,
+            });
+        }
+
+        Ok(spec)
+    }
+}
+
+/// Creates a vector of 16-bit WAV bytes from audio data. Like the other writing functions,
+/// it takes the channels as any slices of samples, such as `Vec<Vec<i16>>` or borrowed
+/// `&[&[i16]]` buffers, which are not copied.
+pub fn create_bytes<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    create_bytes_with_metadata(audiodata, samplerate, &WavMetadata::default())
+}
+
+/// Appends the bytes of a 16-bit WAV file to `buf`, like [`create_bytes`], so batch
+/// exports can reuse one buffer by clearing it between files.
+pub fn create_bytes_into<C: AsRef<[i16]>>(
+    buf: &mut Vec<u8>,
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<()> {
+    write_to(buf, audiodata, samplerate)
 }
 
-/// Writes audio data into a WAV file.
-pub fn write(
-    audiodata: impl AsRef<[Vec<i16>]>,
+/// Writes a 16-bit WAV file into any writer, like [`create_bytes`], without building it in
+/// memory first.
+pub fn write_to<C: AsRef<[i16]>>(
+    mut writer: impl Write,
+    audiodata: impl AsRef<[C]>,
     samplerate: u32,
+) -> Result<()> {
+    write_wav(
+        &mut writer,
+        audiodata.as_ref(),
+        samplerate,
+        WAVE_FORMAT_PCM,
+        16,
+        &WavMetadata::default(),
+        |buf, sample| buf.extend_from_slice(&sample.to_le_bytes()),
+    )
+}
+
+/// Creates a vector of 16-bit WAV bytes from audio data, with the tags, markers and sampler
+/// settings of `metadata` in chunks after the data chunk.
+pub fn create_bytes_with_metadata<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    metadata: &WavMetadata,
+) -> Result<Vec<u8>> {
+    build_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        WAVE_FORMAT_PCM,
+        16,
+        metadata,
+        |buf, sample| buf.extend_from_slice(&sample.to_le_bytes()),
+    )
+}
+
+/// Creates a vector of WAV bytes from 32-bit audio data, stored with the given bit depth.
+/// Samples must fit in the range of that bit depth.
+pub fn create_bytes_with_depth<C: AsRef<[i32]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    bit_depth: BitDepth,
+) -> Result<Vec<u8>> {
+    build_bytes_int(
+        audiodata.as_ref(),
+        samplerate,
+        bit_depth.bits(),
+        &WavMetadata::default(),
+    )
+}
+
+/// Creates a vector of 32-bit IEEE float WAV bytes from audio data.
+pub fn create_bytes_f32<C: AsRef<[f32]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    build_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        WAVE_FORMAT_IEEE_FLOAT,
+        32,
+        &WavMetadata::default(),
+        |buf, sample| buf.extend_from_slice(&sample.to_le_bytes()),
+    )
+}
+
+/// Creates a vector of 64-bit IEEE float WAV bytes from audio data.
+pub fn create_bytes_f64<C: AsRef<[f64]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    build_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        WAVE_FORMAT_IEEE_FLOAT,
+        64,
+        &WavMetadata::default(),
+        |buf, sample| buf.extend_from_slice(&sample.to_le_bytes()),
+    )
+}
+
+/// Creates a vector of G.711 µ-law WAV bytes from 16-bit audio data.
+pub fn create_bytes_mulaw<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+) -> Result<Vec<u8>> {
+    build_bytes(
+        audiodata.as_ref(),
+        samplerate,
+        WAVE_FORMAT_MULAW,
+        8,
+        &WavMetadata::default(),
+        |buf, &sample| buf.push(encode_mulaw(sample)),
+    )
+}
+
+/// Writes audio data into a 16-bit WAV file.
+pub fn write<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_bytes(audiodata, samplerate)?, path)
+}
+
+/// Writes audio data into a 16-bit WAV file, with the metadata chunks of `metadata`.
+pub fn write_with_metadata<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    metadata: &WavMetadata,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(
+        &create_bytes_with_metadata(audiodata, samplerate, metadata)?,
+        path,
+    )
+}
+
+/// Writes 32-bit audio data into a WAV file with the given bit depth.
+pub fn write_with_depth<C: AsRef<[i32]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    bit_depth: BitDepth,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(
+        &create_bytes_with_depth(audiodata, samplerate, bit_depth)?,
+        path,
+    )
+}
+
+/// Writes audio data into a 32-bit IEEE float WAV file.
+pub fn write_f32<C: AsRef<[f32]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_bytes_f32(audiodata, samplerate)?, path)
+}
+
+/// Writes audio data into a 64-bit IEEE float WAV file.
+pub fn write_f64<C: AsRef<[f64]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_bytes_f64(audiodata, samplerate)?, path)
+}
+
+/// Writes 16-bit audio data into a G.711 µ-law WAV file.
+pub fn write_mulaw<C: AsRef<[i16]>>(
+    audiodata: impl AsRef<[C]>,
+    samplerate: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    write_file(&create_bytes_mulaw(audiodata, samplerate)?, path)
+}
+
+/// Writes frames into a WAV file of the given format as an iterator yields them, so that
+/// generated audio is never held in memory as a whole. Each frame holds one sample per
+/// channel, like a slice or an array, and samples are encoded like
+/// [`WavWriter::write_samples`].
+pub fn write_from_frames<S: Sample, F: AsRef<[S]>>(
+    frames: impl IntoIterator<Item = F>,
+    spec: WavSpec,
     path: impl AsRef<Path>,
 ) -> Result<()> {
-    let bytes = create_bytes(audiodata, samplerate)?;
+    let mut writer = WavWriter::with_spec(
+        BufWriter::new(File::create(path)?),
+        spec,
+        WavMetadata::default(),
+    )?;
+
+    for frame in frames {
+        let frame = frame.as_ref();
+        if frame.len() != spec.num_channels as usize {
+            return Err(OndaError::ChannelMismatch {
+                expected: spec.num_channels as usize,
+                found: frame.len(),
+            });
+        }
+        writer.write_samples(frame)?;
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
+/// Writer of WAV files that appends frames as they arrive, e.g. while recording. Files are
+/// 16-bit unless created with [`with_spec`](Self::with_spec). Sizes are patched by
+/// [`finalize`](Self::finalize), so a file is incomplete until then.
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    /// Position of the RIFF header in the writer.
+    start: u64,
+    spec: WavSpec,
+    metadata: WavMetadata,
+    num_frames: u64,
+    /// Position of the frame count of the fact chunk of float files.
+    fact_offset: Option<u64>,
+    /// Encoded samples, reused across writes.
+    buf: Vec<u8>,
+}
+
+impl WavWriter<BufWriter<File>> {
+    /// Creates a WAV file at the provided path.
+    pub fn create(path: impl AsRef<Path>, num_channels: u16, samplerate: u32) -> Result<Self> {
+        Self::new(
+            BufWriter::new(File::create(path)?),
+            num_channels,
+            samplerate,
+        )
+    }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// Writes the header of a WAV file, up to the start of its data chunk.
+    pub fn new(writer: W, num_channels: u16, samplerate: u32) -> Result<Self> {
+        Self::with_metadata(writer, num_channels, samplerate, WavMetadata::default())
+    }
+
+    /// Writes the header of a 16-bit WAV file with the given metadata, like
+    /// [`with_spec`](Self::with_spec).
+    pub fn with_metadata(
+        writer: W,
+        num_channels: u16,
+        samplerate: u32,
+        metadata: WavMetadata,
+    ) -> Result<Self> {
+        let spec = WavSpec {
+            num_channels,
+            samplerate,
+            bits_per_sample: 16,
+            float: false,
+        };
+
+        Self::with_spec(writer, spec, metadata)
+    }
+
+    /// Writes the header of a WAV file of the given format with the given metadata. Its
+    /// bext chunk is written in the header, and the other metadata chunks after the data by
+    /// `finalize`.
+    pub fn with_spec(mut writer: W, spec: WavSpec, metadata: WavMetadata) -> Result<Self> {
+        let spec = WriterBuilder::from_spec(spec).build()?;
+        let format_tag = match spec.float {
+            true => WAVE_FORMAT_IEEE_FLOAT,
+            false => WAVE_FORMAT_PCM,
+        };
+
+        let start = writer.stream_position()?;
+
+        let mut buf = vec![];
+        write_riff_chunk(&mut buf, false)?;
+        // room for a ds64 chunk, in case the file outgrows RIFF sizes
+        write!(buf, "JUNK")?;
+        buf.extend_from_slice(&DS64_SIZE.to_le_bytes());
+        buf.resize(buf.len() + DS64_SIZE as usize, 0);
+        buf.extend_from_slice(&metadata::build_leading_chunks(&metadata)?);
+        write_fmt_chunk(
+            &mut buf,
+            format_tag,
+            spec.num_channels,
+            spec.samplerate,
+            spec.bits_per_sample,
+        )?;
+        // the frame count and data size are patched by finalize
+        let fact_offset = match spec.float {
+            true => {
+                write_fact_chunk(&mut buf, 0)?;
+                Some(start + buf.len() as u64 - 4)
+            }
+            false => None,
+        };
+        write!(buf, "data")?;
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        writer.write_all(&buf)?;
+
+        Ok(Self {
+            writer,
+            start,
+            spec,
+            metadata,
+            num_frames: 0,
+            fact_offset,
+            buf: vec![],
+        })
+    }
+
+    /// Adds a chunk with the given ID, written after the metadata chunks by `finalize` in the
+    /// order it was added. Odd-sized payloads are padded. The chunks making up the file
+    /// layout, such as fmt and data, can't be added.
+    pub fn add_chunk(&mut self, id: [u8; 4], payload: &[u8]) -> Result<()> {
+        if matches!(
+            &id,
+            b"RIFF" | b"RF64" | b"ds64" | b"fmt " | b"fact" | b"data"
+        ) {
+            return Err(OndaError::ReservedChunk { id });
+        }
+        if payload.len() > u32::MAX as usize {
+            return Err(OndaError::TooLarge("chunk payload"));
+        }
 
+        self.metadata.unknown_chunks.push(RawChunk {
+            id,
+            data: payload.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    /// Appends 16-bit frames to the data chunk. Each frame holds one sample per channel.
+    pub fn write_frames<const N: usize>(&mut self, frames: &[[i16; N]]) -> Result<()> {
+        if N != self.spec.num_channels as usize {
+            return Err(OndaError::ChannelMismatch {
+                expected: self.spec.num_channels as usize,
+                found: N,
+            });
+        }
+
+        self.write_samples(frames.as_flattened())
+    }
+
+    /// Appends interleaved 16-bit samples to the data chunk, like
+    /// [`write_samples`](Self::write_samples).
+    pub fn write_interleaved(&mut self, samples: &[i16]) -> Result<()> {
+        self.write_samples(samples)
+    }
+
+    /// Appends interleaved samples to the data chunk, for channel counts only known at run
+    /// time. The number of samples must be a multiple of the number of channels. Integer
+    /// samples must fit in the bit depth of an integer file, and float samples need a float
+    /// file.
+    pub fn write_samples<S: Sample>(&mut self, samples: &[S]) -> Result<()> {
+        let num_channels = self.spec.num_channels as usize;
+        if !samples.len().is_multiple_of(num_channels) {
+            return Err(OndaError::InvalidArgument(
+                "samples are not a whole number of frames",
+            ));
+        }
+        if S::FLOAT != self.spec.float {
+            return Err(sample_type_mismatch(
+                self.spec.float,
+                self.spec.bits_per_sample,
+            ));
+        }
+
+        self.buf.clear();
+        for &sample in samples {
+            sample.encode(&mut self.buf, self.spec.bits_per_sample)?;
+        }
+        self.writer.write_all(&self.buf)?;
+        self.num_frames += (samples.len() / num_channels) as u64;
+
+        Ok(())
+    }
+
+    /// Number of channels of the file.
+    pub fn num_channels(&self) -> u16 {
+        self.spec.num_channels
+    }
+
+    /// Number of frames written so far.
+    pub fn num_frames(&self) -> u64 {
+        self.num_frames
+    }
+
+    /// Writes the metadata chunks after the data and patches the sizes of the header,
+    /// then returns the underlying writer. Files too large for RIFF sizes become RF64.
+    pub fn finalize(mut self) -> Result<W> {
+        let frame_size = self.spec.num_channels as u64 * self.spec.bits_per_sample as u64 / 8;
+        let audiodata_size = self.num_frames * frame_size;
+        // chunks are padded to an even size
+        let padding = audiodata_size % 2;
+        if padding == 1 {
+            self.writer.write_all(&[0])?;
+        }
+
+        let metadata_chunks =
+            metadata::build_metadata_chunks(&self.metadata, self.spec.samplerate)?;
+        self.writer.write_all(&metadata_chunks)?;
+
+        let end = self.writer.stream_position()?;
+        let riff_size = end - self.start - 8;
+        let rf64 = riff_size > u32::MAX as u64;
+
+        // the data size field comes right before the audio data
+        let data_size_offset = end - audiodata_size - padding - metadata_chunks.len() as u64 - 4;
+
+        let mut header = vec![];
+        write_riff_chunk(&mut header, rf64)?;
+        if rf64 {
+            patch_ds64_chunk(&mut header, riff_size, audiodata_size, self.num_frames);
+        } else {
+            header[4..8].copy_from_slice(&(riff_size as u32).to_le_bytes());
+        }
+
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        self.writer.write_all(&header)?;
+        self.writer.seek(SeekFrom::Start(data_size_offset))?;
+        self.writer
+            .write_all(&size_field(audiodata_size, rf64).to_le_bytes())?;
+        if let Some(fact_offset) = self.fact_offset {
+            self.writer.seek(SeekFrom::Start(fact_offset))?;
+            self.writer
+                .write_all(&size_field(self.num_frames, rf64).to_le_bytes())?;
+        }
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+pub(crate) fn write_file(bytes: &[u8], path: impl AsRef<Path>) -> Result<()> {
     let mut writer = BufWriter::new(File::create(path)?);
-    writer.write_all(&bytes)?;
+    writer.write_all(bytes)?;
 
     Ok(())
 }
 
-fn write_riff_chunk(buf: &mut Vec<u8>, audiodata_size: u32) -> Result<()> {
-    write!(buf, "RIFF")?;
+/// Appends an integer sample in the layout of a data chunk, checking that it is in the
+/// range of the bit depth.
+pub(crate) fn encode_int(buf: &mut Vec<u8>, sample: i32, bits_per_sample: u16) -> Result<()> {
+    if !int_range(bits_per_sample).contains(&(sample as i64)) {
+        return Err(OndaError::SampleOutOfRange { bits_per_sample });
+    }
 
-    let chunksize = 36 + audiodata_size;
-    buf.extend_from_slice(&chunksize.to_le_bytes());
+    int_encoder(bits_per_sample)(buf, &sample);
+    Ok(())
+}
+
+/// Appends a float sample in the layout of a data chunk.
+pub(crate) fn encode_float(buf: &mut Vec<u8>, sample: f64, bits_per_sample: u16) {
+    float_encoder(bits_per_sample, |sample| sample)(buf, &sample);
+}
+
+/// Compresses a 16-bit sample to a G.711 µ-law byte.
+pub(crate) fn encode_mulaw(sample: i16) -> u8 {
+    // largest biased magnitude that still fits in the last segment
+    const CLIP: u16 = 0x1FFF;
+
+    // G.711 works on 14-bit samples, and the bias scales down with them
+    let sample = sample >> 2;
+    let bias = (MULAW_BIAS >> 2) as u16;
+
+    let sign = if sample < 0 { 0x80 } else { 0 };
+    let magnitude = (sample.unsigned_abs() + bias).min(CLIP);
+
+    // the segment is the position of the highest set bit above the 5 bits of the bias
+    let segment = (15 - magnitude.leading_zeros()) as u8 - 5;
+    let mantissa = ((magnitude >> (segment + 1)) & 0x0F) as u8;
+
+    !(sign | (segment << 4) | mantissa)
+}
+
+/// Checks that there are between 1 and 65535 channels of equal length, and returns their
+/// number of frames.
+pub(crate) fn validate_channels<S, C: AsRef<[S]>>(audiodata: &[C]) -> Result<usize> {
+    let Some(first) = audiodata.first() else {
+        return Err(OndaError::NoChannels);
+    };
+    let len = first.as_ref().len();
+    if audiodata
+        .iter()
+        .any(|channel| channel.as_ref().len() != len)
+    {
+        return Err(OndaError::InvalidArgument(
+            "channels have different lengths",
+        ));
+    }
+    if audiodata.len() > u16::MAX as usize {
+        return Err(OndaError::UnsupportedChannels(audiodata.len() as u32));
+    }
+
+    Ok(len)
+}
+
+/// Builds integer PCM bytes with the given bit depth, checking that samples are in range.
+fn build_bytes_int<C: AsRef<[i32]>>(
+    audiodata: &[C],
+    samplerate: u32,
+    bits: u16,
+    metadata: &WavMetadata,
+) -> Result<Vec<u8>> {
+    check_int_range(audiodata, bits)?;
+
+    build_bytes(
+        audiodata,
+        samplerate,
+        WAVE_FORMAT_PCM,
+        bits,
+        metadata,
+        int_encoder(bits),
+    )
+}
+
+/// Builds IEEE float bytes with the bit depth of the spec.
+fn build_bytes_float<S: Copy, C: AsRef<[S]>>(
+    audiodata: &[C],
+    spec: WavSpec,
+    metadata: &WavMetadata,
+    widen: impl Fn(S) -> f64,
+) -> Result<Vec<u8>> {
+    build_bytes(
+        audiodata,
+        spec.samplerate,
+        WAVE_FORMAT_IEEE_FLOAT,
+        spec.bits_per_sample,
+        metadata,
+        float_encoder(spec.bits_per_sample, widen),
+    )
+}
+
+/// Encodes integer samples as the bytes of a data chunk with the given bit depth, without
+/// the chunk header, checking that they are in range.
+pub(crate) fn encode_data_int<S: Copy + Into<i32>>(
+    audiodata: &[Vec<S>],
+    bits: u16,
+) -> Result<Vec<u8>> {
+    validate_channels(audiodata)?;
+    check_int_range(audiodata, bits)?;
+
+    let encode = int_encoder(bits);
+    let mut buf = vec![];
+    write_samples(&mut buf, audiodata, |buf, &sample| {
+        encode(buf, &sample.into())
+    })?;
+
+    Ok(buf)
+}
+
+/// Encodes float samples as the bytes of a data chunk with the given bit depth, like
+/// [`encode_data_int`].
+pub(crate) fn encode_data_float<S: Copy + Into<f64>>(
+    audiodata: &[Vec<S>],
+    bits: u16,
+) -> Result<Vec<u8>> {
+    validate_channels(audiodata)?;
+
+    let mut buf = vec![];
+    write_samples(&mut buf, audiodata, float_encoder(bits, S::into))?;
+
+    Ok(buf)
+}
+
+/// Range of integer samples of a bit depth.
+fn int_range(bits: u16) -> RangeInclusive<i64> {
+    let max = (1i64 << (bits - 1)) - 1;
+    -max - 1..=max
+}
+
+fn check_int_range<S: Copy + Into<i32>, C: AsRef<[S]>>(audiodata: &[C], bits: u16) -> Result<()> {
+    let range = int_range(bits);
+    if audiodata
+        .iter()
+        .flat_map(AsRef::as_ref)
+        .any(|&s| !range.contains(&(s.into() as i64)))
+    {
+        return Err(OndaError::SampleOutOfRange {
+            bits_per_sample: bits,
+        });
+    }
+
+    Ok(())
+}
+
+/// Encoder of integer samples that are in the range of the bit depth.
+fn int_encoder(bits: u16) -> impl Fn(&mut Vec<u8>, &i32) {
+    // samples are in range, so the low bytes hold the whole two's complement value, and
+    // 8-bit samples are stored as offset binary
+    let sample_size = bits as usize / 8;
+
+    move |buf, &sample| match bits {
+        8 => buf.push((sample + 128) as u8),
+        _ => buf.extend_from_slice(&sample.to_le_bytes()[..sample_size]),
+    }
+}
+
+/// Encoder of float samples as 32 or 64-bit IEEE floats.
+fn float_encoder<S: Copy>(bits: u16, widen: impl Fn(S) -> f64) -> impl Fn(&mut Vec<u8>, &S) {
+    move |buf, &sample| match bits {
+        32 => buf.extend_from_slice(&(widen(sample) as f32).to_le_bytes()),
+        _ => buf.extend_from_slice(&widen(sample).to_le_bytes()),
+    }
+}
+
+fn build_bytes<S, C: AsRef<[S]>>(
+    audiodata: &[C],
+    samplerate: u32,
+    format_tag: u16,
+    bits_per_sample: u16,
+    metadata: &WavMetadata,
+    encode: impl Fn(&mut Vec<u8>, &S),
+) -> Result<Vec<u8>> {
+    let num_samples: usize = audiodata.iter().map(|c| c.as_ref().len()).sum();
+    let mut buf =
+        Vec::with_capacity(num_samples * bits_per_sample as usize / 8 + RF64_HEADROOM as usize);
+    write_wav(
+        &mut buf,
+        audiodata,
+        samplerate,
+        format_tag,
+        bits_per_sample,
+        metadata,
+        encode,
+    )?;
+
+    Ok(buf)
+}
+
+/// Writes a whole WAV file into `writer`. Sizes are known up front, so the file is written
+/// in order without seeking.
+fn write_wav<S, C: AsRef<[S]>>(
+    writer: &mut impl Write,
+    audiodata: &[C],
+    samplerate: u32,
+    format_tag: u16,
+    bits_per_sample: u16,
+    metadata: &WavMetadata,
+    encode: impl Fn(&mut Vec<u8>, &S),
+) -> Result<()> {
+    let num_frames = validate_channels(audiodata)? as u64;
+    let num_channels = audiodata.len() as u16;
+
+    let audiodata_size = num_frames * num_channels as u64 * bits_per_sample as u64 / 8;
+    let leading_chunks = metadata::build_leading_chunks(metadata)?;
+    let metadata_chunks = metadata::build_metadata_chunks(metadata, samplerate)?;
+    let metadata_size = (leading_chunks.len() + metadata_chunks.len()) as u64;
+
+    // promote to RF64 when the sizes would overflow, leaving room for the other chunks
+    let rf64 = audiodata_size + metadata_size > (u32::MAX - RF64_HEADROOM) as u64;
+
+    let mut header = vec![];
+    write_riff_chunk(&mut header, rf64)?;
+    header.extend_from_slice(&leading_chunks);
+    write_fmt_chunk(
+        &mut header,
+        format_tag,
+        num_channels,
+        samplerate,
+        bits_per_sample,
+    )?;
+    if format_tag != WAVE_FORMAT_PCM {
+        write_fact_chunk(&mut header, size_field(num_frames, rf64))?;
+    }
+    write!(header, "data")?;
+    header.extend_from_slice(&size_field(audiodata_size, rf64).to_le_bytes());
+
+    // chunks are padded to an even size
+    let padding = audiodata_size % 2;
+    let riff_size =
+        header.len() as u64 - 8 + audiodata_size + padding + metadata_chunks.len() as u64;
+    if rf64 {
+        patch_ds64_chunk(&mut header, riff_size, audiodata_size, num_frames);
+    } else {
+        header[4..8].copy_from_slice(&(riff_size as u32).to_le_bytes());
+    }
+
+    writer.write_all(&header)?;
+    write_samples(writer, audiodata, encode)?;
+    if padding == 1 {
+        writer.write_all(&[0])?;
+    }
+    writer.write_all(&metadata_chunks)?;
+
+    Ok(())
+}
+
+/// Bytes reserved for chunks other than data before a file is promoted to RF64.
+const RF64_HEADROOM: u32 = 1024;
+
+/// Size of the ds64 chunk payload, without a table of other chunk sizes.
+const DS64_SIZE: u32 = 28;
+
+/// Offset of the ds64 chunk payload in RF64 files.
+const DS64_OFFSET: usize = 20;
+
+/// Value of a 32-bit size field. RF64 files mark sizes stored in the ds64 chunk with the
+/// maximum value.
+fn size_field(size: u64, rf64: bool) -> u32 {
+    if rf64 {
+        u32::MAX
+    } else {
+        size as u32
+    }
+}
+
+fn write_riff_chunk(buf: &mut Vec<u8>, rf64: bool) -> Result<()> {
+    if rf64 {
+        write!(buf, "RF64")?;
+        buf.extend_from_slice(&u32::MAX.to_le_bytes());
+    } else {
+        write!(buf, "RIFF")?;
+
+        // the chunk size is patched once all chunks are written
+        buf.extend_from_slice(&0u32.to_le_bytes());
+    }
 
     write!(buf, "WAVE")?;
+
+    if rf64 {
+        write_ds64_chunk(buf)?;
+    }
+
+    Ok(())
+}
+
+fn write_ds64_chunk(buf: &mut Vec<u8>) -> Result<()> {
+    write!(buf, "ds64")?;
+    buf.extend_from_slice(&DS64_SIZE.to_le_bytes());
+
+    // RIFF size, data size and sample count are patched once all chunks are written,
+    // and there is no table of other chunk sizes
+    buf.extend_from_slice(&[0; 28]);
+
     Ok(())
 }
 
-fn write_fmt_chunk(buf: &mut Vec<u8>, num_channels: u16, samplerate: u32) -> Result<()> {
-    const CHUNKSIZE: u32 = 16;
-    const AUDIOFORMAT: u16 = 1;
+fn patch_ds64_chunk(buf: &mut [u8], riff_size: u64, audiodata_size: u64, num_frames: u64) {
+    let ds64 = &mut buf[DS64_OFFSET..DS64_OFFSET + 24];
+    ds64[0..8].copy_from_slice(&riff_size.to_le_bytes());
+    ds64[8..16].copy_from_slice(&audiodata_size.to_le_bytes());
+    ds64[16..24].copy_from_slice(&num_frames.to_le_bytes());
+}
+
+fn write_fmt_chunk(
+    buf: &mut Vec<u8>,
+    format_tag: u16,
+    num_channels: u16,
+    samplerate: u32,
+    bits_per_sample: u16,
+) -> Result<()> {
+    // the spec requires the extensible format for more than 2 channels or more than 16 bits
+    let extensible = num_channels > 2 || (format_tag == WAVE_FORMAT_PCM && bits_per_sample > 16);
 
-    let byterate = samplerate * num_channels as u32 * BITS_PER_SAMPLE as u32 / 8;
-    let block_align = num_channels * BITS_PER_SAMPLE / 8;
+    // non-PCM formats carry an extension size field, which is empty unless extensible
+    let chunksize: u32 = match (extensible, format_tag) {
+        (true, _) => 40,
+        (false, WAVE_FORMAT_PCM) => 16,
+        (false, _) => 18,
+    };
+
+    let block_align = frame_size(num_channels, bits_per_sample)?;
+    let byterate = samplerate
+        .checked_mul(block_align as u32)
+        .ok_or(OndaError::TooLarge("byte rate"))?;
 
     write!(buf, "fmt ")?;
-    buf.extend_from_slice(&CHUNKSIZE.to_le_bytes());
-    buf.extend_from_slice(&AUDIOFORMAT.to_le_bytes());
+    buf.extend_from_slice(&chunksize.to_le_bytes());
+    if extensible {
+        buf.extend_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+    } else {
+        buf.extend_from_slice(&format_tag.to_le_bytes());
+    }
     buf.extend_from_slice(&num_channels.to_le_bytes());
     buf.extend_from_slice(&samplerate.to_le_bytes());
     buf.extend_from_slice(&byterate.to_le_bytes());
     buf.extend_from_slice(&block_align.to_le_bytes());
-    buf.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+    buf.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+    if extensible {
+        const EXTENSION_SIZE: u16 = 22;
+
+        buf.extend_from_slice(&EXTENSION_SIZE.to_le_bytes());
+        buf.extend_from_slice(&bits_per_sample.to_le_bytes());
+        buf.extend_from_slice(&default_channel_mask(num_channels).to_le_bytes());
+        buf.extend_from_slice(&format_tag.to_le_bytes());
+        buf.extend_from_slice(&SUBFORMAT_GUID_SUFFIX);
+    } else if format_tag != WAVE_FORMAT_PCM {
+        buf.extend_from_slice(&0u16.to_le_bytes());
+    }
 
     Ok(())
 }
 
-fn write_data_chunk(buf: &mut Vec<u8>, audiodata: &[Vec<i16>], audiodata_size: u32) -> Result<()> {
-    write!(buf, "data")?;
-    buf.extend_from_slice(&audiodata_size.to_le_bytes());
+/// Speaker positions for common layouts, following the channel order of the spec. Other
+/// channel counts are left unassigned.
+pub(crate) fn default_channel_mask(num_channels: u16) -> u32 {
+    const FRONT_LEFT: u32 = 0x1;
+    const FRONT_RIGHT: u32 = 0x2;
+    const FRONT_CENTER: u32 = 0x4;
+    const LOW_FREQUENCY: u32 = 0x8;
+    const BACK_LEFT: u32 = 0x10;
+    const BACK_RIGHT: u32 = 0x20;
+    const BACK_CENTER: u32 = 0x100;
+    const SIDE_LEFT: u32 = 0x200;
+    const SIDE_RIGHT: u32 = 0x400;
 
-    let num_frames = audiodata[0].len();
-    let num_channels = audiodata.len();
+    const STEREO: u32 = FRONT_LEFT | FRONT_RIGHT;
+
+    match num_channels {
+        1 => FRONT_CENTER,
+        2 => STEREO,
+        3 => STEREO | FRONT_CENTER,
+        4 => STEREO | BACK_LEFT | BACK_RIGHT,
+        5 => STEREO | FRONT_CENTER | BACK_LEFT | BACK_RIGHT,
+        6 => STEREO | FRONT_CENTER | LOW_FREQUENCY | BACK_LEFT | BACK_RIGHT,
+        7 => STEREO | FRONT_CENTER | LOW_FREQUENCY | BACK_CENTER | SIDE_LEFT | SIDE_RIGHT,
+        8 => {
+            STEREO | FRONT_CENTER | LOW_FREQUENCY | BACK_LEFT | BACK_RIGHT | SIDE_LEFT | SIDE_RIGHT
+        }
+        _ => 0,
+    }
+}
+
+fn write_fact_chunk(buf: &mut Vec<u8>, num_frames: u32) -> Result<()> {
+    const CHUNKSIZE: u32 = 4;
+
+    write!(buf, "fact")?;
+    buf.extend_from_slice(&CHUNKSIZE.to_le_bytes());
+    buf.extend_from_slice(&num_frames.to_le_bytes());
+
+    Ok(())
+}
+
+/// Bytes of samples encoded before they are written, when writing whole files.
+const WRITE_BUFFER_SIZE: usize = 1 << 16;
+
+/// Writes the samples of a data chunk, interleaved frame by frame.
+fn write_samples<S, C: AsRef<[S]>>(
+    writer: &mut impl Write,
+    audiodata: &[C],
+    encode: impl Fn(&mut Vec<u8>, &S),
+) -> Result<()> {
+    let num_frames = audiodata[0].as_ref().len();
+    let mut buf = Vec::with_capacity(WRITE_BUFFER_SIZE);
 
     for f in 0..num_frames {
-        for c in 0..num_channels {
-            buf.extend_from_slice(&audiodata[c][f].to_le_bytes());
+        for channel in audiodata {
+            encode(&mut buf, &channel.as_ref()[f]);
+        }
+        if buf.len() >= WRITE_BUFFER_SIZE {
+            writer.write_all(&buf)?;
+            buf.clear();
         }
     }
+    writer.write_all(&buf)?;
 
     Ok(())
 }
@@ -0,0 +1,59 @@
+#![cfg(feature = "tokio")]
+
+use onda::*;
+
+/// Two channels of 100 frames of ramps.
+fn stereo_i16() -> Vec<Vec<i16>> {
+    vec![
+        (0..100).map(|i| i * 300).collect(),
+        (0..100).map(|i| -i * 300).collect(),
+    ]
+}
+
+#[tokio::test]
+async fn read_and_write_async() {
+    let path = std::env::temp_dir().join(format!("onda-{}-async.wav", std::process::id()));
+    write_async(stereo_i16(), 44100, &path).await.unwrap();
+
+    let wav = read_async(&path).await.unwrap();
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.audiodata, stereo_i16());
+
+    tokio::fs::remove_file(&path).await.unwrap();
+}
+
+#[tokio::test]
+async fn async_wav_reader() {
+    let audiodata = stereo_i16();
+    let buf = create_bytes(&audiodata, 44100).unwrap();
+
+    let mut reader = AsyncWavReader::new(&buf[..]).await.unwrap();
+    assert_eq!(reader.num_channels(), 2);
+    assert_eq!(reader.samplerate(), 44100);
+
+    let mut channels = vec![vec![]; 2];
+    loop {
+        let frames = reader.read_frames(30).await.unwrap();
+        if frames[0].is_empty() {
+            break;
+        }
+        for (channel, read) in channels.iter_mut().zip(frames) {
+            channel.extend(read);
+        }
+    }
+    assert_eq!(channels, audiodata);
+
+    // lenient parsing tolerates a wrong block align
+    let mut buf = buf;
+    buf[32..34].copy_from_slice(&3u16.to_le_bytes());
+    assert!(AsyncWavReader::new(&buf[..]).await.is_err());
+    let lenient = ParseOptions { strict: false };
+    let mut reader = AsyncWavReader::with_options(&buf[..], lenient)
+        .await
+        .unwrap();
+    assert_eq!(reader.warnings().len(), 1);
+    assert_eq!(
+        reader.read_frames_i32(2).await.unwrap(),
+        [vec![0, 300], vec![0, -300]]
+    );
+}
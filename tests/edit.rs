@@ -0,0 +1,352 @@
+use onda::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Path of a temporary file for a test.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("onda-{}-{name}.wav", std::process::id()))
+}
+
+/// Two channels of 100 frames.
+fn audiodata() -> Vec<Vec<i16>> {
+    vec![(0..100).collect(), (0..100).map(|s| -s).collect()]
+}
+
+/// Metadata with tags, a loop and a chunk onda doesn't read.
+fn metadata() -> WavMetadata {
+    WavMetadata {
+        title: Some("Take 1".to_string()),
+        other_tags: vec![(*b"IPRD", "Album".to_string())],
+        sampler: Some(SamplerInfo {
+            midi_unity_note: 60,
+            midi_pitch_fraction: 0,
+            loops: vec![SampleLoop {
+                start: 10,
+                end: 90,
+                ..Default::default()
+            }],
+        }),
+        unknown_chunks: vec![RawChunk {
+            id: *b"abcd",
+            data: vec![1, 2, 3],
+        }],
+        ..Default::default()
+    }
+}
+
+/// A 16-bit WAV file with `metadata()` after the data chunk, and a chunk onda doesn't
+/// read between the fmt and data chunks.
+fn create_file(path: &PathBuf) -> Vec<u8> {
+    let mut buf = create_bytes_with_metadata(audiodata(), 44100, &metadata()).unwrap();
+    buf.splice(36..36, *b"zzzz\x02\x00\x00\x00\xAB\xCD");
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    fs::write(path, &buf).unwrap();
+    buf
+}
+
+/// Whether `bytes` occurs in `buf`.
+fn contains(buf: &[u8], bytes: &[u8]) -> bool {
+    buf.windows(bytes.len()).any(|w| w == bytes)
+}
+
+#[test]
+fn edit_keeps_other_chunks() {
+    let path = temp_path("edit-keeps-other-chunks");
+    let original = parse_bytes(create_file(&path)).unwrap();
+
+    edit_metadata(&path, |metadata| {
+        metadata.title = Some("Take 2".to_string());
+    })
+    .unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(contains(&buf, b"zzzz\x02\x00\x00\x00\xAB\xCD"));
+    assert!(contains(&buf, b"abcd\x03\x00\x00\x00\x01\x02\x03"));
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, audiodata());
+    assert_eq!(
+        wav.metadata,
+        WavMetadata {
+            title: Some("Take 2".to_string()),
+            ..original.metadata
+        }
+    );
+}
+
+#[test]
+fn edit_without_changes() {
+    let path = temp_path("edit-without-changes");
+    let buf = create_file(&path);
+
+    edit_metadata(&path, |metadata| {
+        metadata.title = Some("Take 1".to_string());
+    })
+    .unwrap();
+
+    let edited = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(edited, buf);
+}
+
+#[test]
+fn edit_removes_chunks() {
+    let path = temp_path("edit-removes-chunks");
+    create_file(&path);
+
+    edit_metadata(&path, |metadata| {
+        metadata.sampler = None;
+        metadata.unknown_chunks.clear();
+    })
+    .unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(!contains(&buf, b"smpl"));
+    assert!(!contains(&buf, b"abcd"));
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, audiodata());
+    assert_eq!(wav.metadata.other_tags, metadata().other_tags);
+}
+
+#[test]
+fn edit_replaces_unparsed_chunk() {
+    let path = temp_path("edit-replaces-unparsed-chunk");
+    let mut buf = create_file(&path);
+    // a bext chunk too short to parse, kept as an unknown chunk
+    buf.extend_from_slice(b"bext\x02\x00\x00\x00\x01\x02");
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    fs::write(&path, &buf).unwrap();
+
+    edit_metadata(&path, |metadata| {
+        metadata.unknown_chunks.retain(|chunk| &chunk.id != b"bext");
+        metadata.bext = Some(BextChunk {
+            description: "Take 2".to_string(),
+            ..Default::default()
+        });
+    })
+    .unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(!contains(&buf, b"bext\x02"));
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata.bext.unwrap().description, "Take 2");
+    let ids: Vec<_> = wav.metadata.unknown_chunks.iter().map(|c| c.id).collect();
+    assert_eq!(ids, [*b"zzzz", *b"abcd"]);
+}
+
+/// The audio data of the file after appending `more` to it.
+fn appended(more: &WavData<i16>) -> Vec<Vec<i16>> {
+    audiodata()
+        .into_iter()
+        .zip(&more.audiodata)
+        .map(|(channel, appended)| [channel, appended.clone()].concat())
+        .collect()
+}
+
+/// Audio data of two channels to append.
+fn stereo(left: Vec<i16>, right: Vec<i16>) -> WavData<i16> {
+    let mut data = WavData::new(44100, 2);
+    data.audiodata = vec![left, right];
+    data
+}
+
+/// Three frames louder than `audiodata()`.
+fn louder() -> WavData<i16> {
+    stereo(vec![1000, 1001, 1002], vec![-1000, -1001, -1002])
+}
+
+#[test]
+fn append_keeps_other_chunks() {
+    let path = temp_path("append-keeps-other-chunks");
+    let original = parse_bytes(create_file(&path)).unwrap();
+
+    let more = louder();
+    append(&path, &more).unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(validate(&buf).is_valid());
+    assert!(contains(&buf, b"zzzz\x02\x00\x00\x00\xAB\xCD"));
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, appended(&more));
+    assert_eq!(wav.metadata, original.metadata);
+}
+
+#[test]
+fn append_mismatched_data() {
+    let path = temp_path("append-mismatched-data");
+    let buf = create_file(&path);
+
+    let mono = WavData::<i16>::new(44100, 1);
+    assert!(matches!(
+        append(&path, &mono),
+        Err(OndaError::ChannelMismatch {
+            expected: 2,
+            found: 1
+        })
+    ));
+
+    let other_rate = WavData::<i16>::new(48000, 2);
+    assert!(matches!(
+        append(&path, &other_rate),
+        Err(OndaError::InvalidArgument(_))
+    ));
+
+    let float = WavData::<f32>::new(44100, 2);
+    assert!(matches!(
+        append(&path, &float),
+        Err(OndaError::SampleTypeMismatch { .. })
+    ));
+
+    let unchanged = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(unchanged, buf);
+}
+
+#[test]
+fn append_updates_peak() {
+    let path = temp_path("append-updates-peak");
+    let metadata = WavMetadata {
+        peak: Some(compute_peak(audiodata())),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(audiodata(), 44100, &metadata).unwrap();
+    fs::write(&path, buf).unwrap();
+
+    let more = louder();
+    append(&path, &more).unwrap();
+    // quieter audio keeps the peaks
+    append(&path, &stereo(vec![1], vec![-1])).unwrap();
+
+    let wav = read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let expected = compute_peak(appended(&more));
+    assert_eq!(wav.metadata.peak.unwrap().peaks, expected.peaks);
+}
+
+/// A PEAK chunk with a peak for each of `positions`.
+fn peak_chunk(positions: &[u32]) -> Vec<u8> {
+    let mut chunk = b"PEAK".to_vec();
+    chunk.extend_from_slice(&(8 + 8 * positions.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(&1u32.to_le_bytes());
+    chunk.extend_from_slice(&0u32.to_le_bytes());
+    for position in positions {
+        chunk.extend_from_slice(&0.5f32.to_le_bytes());
+        chunk.extend_from_slice(&position.to_le_bytes());
+    }
+    chunk
+}
+
+/// Writes a 16-bit WAV file of `audiodata()` with `chunk` before the data chunk.
+fn create_file_with_leading(path: &PathBuf, chunk: &[u8]) {
+    let mut buf = create_bytes(audiodata(), 44100).unwrap();
+    buf.splice(36..36, chunk.iter().copied());
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    fs::write(path, buf).unwrap();
+}
+
+#[test]
+fn append_updates_leading_peak() {
+    let path = temp_path("append-updates-leading-peak");
+    create_file_with_leading(&path, &peak_chunk(&[5, 6]));
+
+    append(&path, &louder()).unwrap();
+
+    let wav = read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let peaks = wav.metadata.peak.unwrap().peaks;
+    assert_eq!(
+        peaks[0],
+        Peak {
+            value: 0.5,
+            position: 5
+        }
+    );
+    assert_eq!(
+        peaks[1],
+        Peak {
+            value: 0.5,
+            position: 6
+        }
+    );
+
+    let path = temp_path("append-updates-leading-peak-louder");
+    create_file_with_leading(&path, &peak_chunk(&[5, 6]));
+
+    let loud = stereo(vec![i16::MAX], vec![0]);
+    append(&path, &loud).unwrap();
+
+    let wav = read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let peaks = wav.metadata.peak.unwrap().peaks;
+    assert_eq!(peaks[0].position, 100);
+    assert_eq!(
+        peaks[1],
+        Peak {
+            value: 0.5,
+            position: 6
+        }
+    );
+}
+
+#[test]
+fn append_removes_mismatched_peak() {
+    let path = temp_path("append-removes-mismatched-peak");
+    create_file_with_leading(&path, &peak_chunk(&[5]));
+
+    let more = louder();
+    append(&path, &more).unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(!contains(&buf, b"PEAK"));
+    assert!(validate(&buf).is_valid());
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, appended(&more));
+    assert_eq!(wav.metadata.peak, None);
+}
+
+#[test]
+fn append_to_rf64() {
+    let path = temp_path("append-to-rf64");
+    let mut buf = create_bytes(audiodata(), 44100).unwrap();
+    let data_size = buf.len() as u64 - 44;
+    let mut ds64 = b"ds64\x1C\x00\x00\x00".to_vec();
+    ds64.extend_from_slice(&(buf.len() as u64 + 28).to_le_bytes());
+    ds64.extend_from_slice(&data_size.to_le_bytes());
+    ds64.extend_from_slice(&100u64.to_le_bytes());
+    ds64.extend_from_slice(&0u32.to_le_bytes());
+    buf.splice(12..12, ds64);
+    buf[..8].copy_from_slice(b"RF64\xFF\xFF\xFF\xFF");
+    buf[76..80].copy_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(&path, &buf).unwrap();
+
+    let more = louder();
+    append(&path, &more).unwrap();
+
+    let buf = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(&buf[..8], b"RF64\xFF\xFF\xFF\xFF");
+    assert_eq!(buf[20..28], (buf.len() as u64 - 8).to_le_bytes());
+    assert_eq!(buf[28..36], (data_size + 12).to_le_bytes());
+    assert_eq!(buf[36..44], 103u64.to_le_bytes());
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, appended(&more));
+}
@@ -0,0 +1,381 @@
+use onda::*;
+
+/// Builds an AIFF file at 44.1 kHz, or an AIFF-C file when `compression` is given, with
+/// `data` as the SSND samples.
+fn aiff_bytes(
+    compression: Option<&[u8; 4]>,
+    num_channels: u16,
+    sample_size: u16,
+    data: &[u8],
+) -> Vec<u8> {
+    let frame_size = num_channels as usize * sample_size.div_ceil(8) as usize;
+    let num_frames = (data.len() / frame_size) as u32;
+
+    let mut comm = num_channels.to_be_bytes().to_vec();
+    comm.extend_from_slice(&num_frames.to_be_bytes());
+    comm.extend_from_slice(&sample_size.to_be_bytes());
+    comm.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+    if let Some(compression) = compression {
+        // compression type followed by an empty pascal string name
+        comm.extend_from_slice(compression);
+        comm.extend_from_slice(&[0, 0]);
+    }
+
+    let form = if compression.is_some() {
+        b"AIFC"
+    } else {
+        b"AIFF"
+    };
+    let mut buf = b"FORM\0\0\0\0".to_vec();
+    buf.extend_from_slice(form);
+    buf.extend_from_slice(b"COMM");
+    buf.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&comm);
+    buf.extend_from_slice(b"SSND");
+    buf.extend_from_slice(&(8 + data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&[0; 8]); // data offset and block size
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+
+    let form_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&form_size.to_be_bytes());
+    buf
+}
+
+#[test]
+fn read_aiff() {
+    let data = [0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF];
+    let wav = parse_bytes(aiff_bytes(None, 2, 16, &data)).unwrap();
+
+    assert_eq!(wav.num_channels, 2);
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.audiodata, [vec![32767, 1], vec![-32768, -1]]);
+
+    // 24-bit samples and a sample size that isn't a whole number of bytes
+    let data = [0x80, 0x00, 0x00, 0x00, 0x01, 0x00];
+    let wav = parse_bytes_i32(aiff_bytes(None, 1, 24, &data)).unwrap();
+    assert_eq!(wav.audiodata, [vec![-8388608, 256]]);
+
+    let wav = parse_bytes(aiff_bytes(None, 1, 12, &[0x12, 0x30])).unwrap();
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(wav.valid_bits_per_sample, 12);
+    assert_eq!(wav.audiodata, [vec![0x1230]]);
+}
+
+#[test]
+fn read_aifc() {
+    let data = [0x01, 0x00, 0xFF, 0x7F];
+    let wav = parse_bytes(aiff_bytes(Some(b"sowt"), 1, 16, &data)).unwrap();
+    assert_eq!(wav.audiodata, [vec![1, 32767]]);
+
+    let data = [0x00, 0x01, 0x7F, 0xFF];
+    let wav = parse_bytes(aiff_bytes(Some(b"twos"), 1, 16, &data)).unwrap();
+    assert_eq!(wav.audiodata, [vec![1, 32767]]);
+
+    let data: Vec<u8> = [0.5f32, -1.0]
+        .iter()
+        .flat_map(|s| s.to_be_bytes())
+        .collect();
+    let wav = parse_bytes_f32(aiff_bytes(Some(b"fl32"), 1, 32, &data)).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5, -1.0]]);
+
+    let wav = parse_bytes(aiff_bytes(Some(b"ulaw"), 1, 8, &[0xFF, 0x80])).unwrap();
+    assert_eq!(wav.audiodata, [vec![0, 32124]]);
+
+    assert!(parse_bytes(aiff_bytes(Some(b"ima4"), 1, 16, &[0, 0])).is_err());
+}
+
+#[test]
+fn write_aiff() {
+    let audiodata = vec![vec![32767, 1, 0], vec![-32768, -1, 256]];
+    let buf = create_aiff_bytes(&audiodata, 44100).unwrap();
+
+    let data = [
+        0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF, 0x00, 0x00, 0x01, 0x00,
+    ];
+    assert_eq!(buf, aiff_bytes(None, 2, 16, &data));
+
+    let wav = parse_bytes(create_aiff_bytes(&audiodata, 22050).unwrap()).unwrap();
+    assert_eq!(wav.samplerate, 22050);
+    assert_eq!(wav.audiodata, audiodata);
+}
+
+/// Builds an AU file with `data` as big-endian sample bytes.
+fn au_bytes(encoding: u32, samplerate: u32, num_channels: u32, data: &[u8]) -> Vec<u8> {
+    let mut buf = b".snd".to_vec();
+    for field in [24, data.len() as u32, encoding, samplerate, num_channels] {
+        buf.extend_from_slice(&field.to_be_bytes());
+    }
+    buf.extend_from_slice(data);
+    buf
+}
+
+#[test]
+fn read_au() {
+    let data = [0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF];
+    let wav = parse_bytes(au_bytes(3, 8000, 2, &data)).unwrap();
+    assert_eq!(wav.samplerate, 8000);
+    assert_eq!(wav.audiodata, [vec![32767, 1], vec![-32768, -1]]);
+
+    // an unknown data size runs to the end of the file
+    let mut buf = au_bytes(4, 8000, 1, &[0x80, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    buf[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert_eq!(
+        parse_bytes_i32(&buf).unwrap().audiodata,
+        [vec![-8388608, 1]]
+    );
+
+    let wav = parse_bytes(au_bytes(27, 8000, 1, &[0xD5, 0x2A])).unwrap();
+    assert_eq!(wav.audiodata, [vec![8, -32256]]);
+
+    assert!(parse_bytes(au_bytes(23, 8000, 1, &[0])).is_err());
+    assert!(parse_bytes(au_bytes(3, 8000, 0, &[0, 0])).is_err());
+}
+
+#[test]
+fn write_au() {
+    let audiodata = vec![vec![32767, 1], vec![-32768, -1]];
+    let buf = create_au_bytes(&audiodata, 44100).unwrap();
+    let data = [0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF];
+    assert_eq!(buf, au_bytes(3, 44100, 2, &data));
+    assert_eq!(parse_bytes(&buf).unwrap().audiodata, audiodata);
+
+    // µ-law AU data decodes the same as µ-law WAV data
+    let mulaw = parse_bytes(create_au_bytes_mulaw(&audiodata, 8000).unwrap()).unwrap();
+    let wav_mulaw = parse_bytes(create_bytes_mulaw(&audiodata, 8000).unwrap()).unwrap();
+    assert_eq!(mulaw.audiodata, wav_mulaw.audiodata);
+}
+
+/// Builds a CAF file at 48 kHz with `data` as the sample bytes.
+fn caf_bytes(
+    format_id: &[u8; 4],
+    flags: u32,
+    num_channels: u32,
+    bits: u32,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut buf = b"caff\x00\x01\x00\x00".to_vec();
+    buf.extend_from_slice(b"desc");
+    buf.extend_from_slice(&32u64.to_be_bytes());
+    buf.extend_from_slice(&48000f64.to_bits().to_be_bytes());
+    buf.extend_from_slice(format_id);
+    let bytes_per_packet = num_channels * bits.div_ceil(8);
+    for field in [flags, bytes_per_packet, 1, num_channels, bits] {
+        buf.extend_from_slice(&field.to_be_bytes());
+    }
+
+    // an unknown chunk the reader skips
+    buf.extend_from_slice(b"free");
+    buf.extend_from_slice(&3u64.to_be_bytes());
+    buf.extend_from_slice(&[0; 3]);
+
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&(4 + data.len() as u64).to_be_bytes());
+    buf.extend_from_slice(&[0; 4]); // edit count
+    buf.extend_from_slice(data);
+    buf
+}
+
+#[test]
+fn read_caf() {
+    let data = [0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF];
+    let wav = parse_bytes(caf_bytes(b"lpcm", 0, 2, 16, &data)).unwrap();
+    assert_eq!(wav.samplerate, 48000);
+    assert_eq!(wav.audiodata, [vec![32767, 1], vec![-32768, -1]]);
+
+    // little-endian float samples
+    let data: Vec<u8> = [0.5f32, -1.0]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let wav = parse_bytes_f32(caf_bytes(b"lpcm", 0x3, 1, 32, &data)).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5, -1.0]]);
+
+    // a data size of -1 runs to the end of the file
+    let mut buf = caf_bytes(b"ulaw", 0, 1, 8, &[0xFF, 0x80]);
+    let len = buf.len();
+    buf[len - 14..len - 6].copy_from_slice(&u64::MAX.to_be_bytes());
+    assert_eq!(parse_bytes(&buf).unwrap().audiodata, [vec![0, 32124]]);
+
+    assert!(parse_bytes(caf_bytes(b"aac ", 0, 1, 16, &[0, 0])).is_err());
+    assert!(parse_bytes(caf_bytes(b"lpcm", 0, 1, 12, &[0, 0])).is_err());
+}
+
+#[test]
+fn read_raw() {
+    let raw_spec = RawSpec {
+        samplerate: 22050,
+        channels: 2,
+        bit_depth: BitDepth::B16,
+        endianness: Endianness::Big,
+        signedness: Signedness::Signed,
+    };
+    // a trailing partial frame is ignored
+    let data = [0x7F, 0xFF, 0x80, 0x00, 0x00, 0x01, 0xFF, 0xFF, 0x12];
+    let wav = parse_raw(data, raw_spec).unwrap();
+    assert_eq!(wav.samplerate, 22050);
+    assert_eq!(wav.audiodata, [vec![32767, 1], vec![-32768, -1]]);
+
+    let raw_spec = RawSpec {
+        channels: 1,
+        bit_depth: BitDepth::B24,
+        endianness: Endianness::Little,
+        signedness: Signedness::Unsigned,
+        ..raw_spec
+    };
+    let data = [0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0xFF, 0xFF, 0xFF];
+    let wav = parse_raw(data, raw_spec).unwrap();
+    assert_eq!(wav.audiodata, [vec![-8388608, 0, 8388607]]);
+
+    let no_channels = RawSpec {
+        channels: 0,
+        ..raw_spec
+    };
+    assert!(parse_raw([0; 3], no_channels).is_err());
+
+    // frames wider than the 16-bit block align of the decoder
+    let wide = RawSpec {
+        channels: u16::MAX,
+        bit_depth: BitDepth::B32,
+        ..raw_spec
+    };
+    assert!(parse_raw([0; 4], wide).is_err());
+}
+
+#[test]
+fn roundtrip_raw() {
+    let wav = parse_bytes_i32(
+        create_bytes(vec![vec![32767, -1, 256], vec![-32768, 1, 0]], 44100).unwrap(),
+    )
+    .unwrap();
+
+    for (bit_depth, bits) in [
+        (BitDepth::B8, 8),
+        (BitDepth::B16, 16),
+        (BitDepth::B24, 24),
+        (BitDepth::B32, 32),
+    ] {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            for signedness in [Signedness::Signed, Signedness::Unsigned] {
+                let layout = RawLayout {
+                    bit_depth,
+                    endianness,
+                    signedness,
+                };
+                let buf = create_raw_bytes(&wav, layout).unwrap();
+                assert_eq!(buf.len(), 6 * bits / 8);
+
+                let raw_spec = RawSpec {
+                    samplerate: 44100,
+                    channels: 2,
+                    bit_depth,
+                    endianness,
+                    signedness,
+                };
+                let parsed = parse_raw(&buf, raw_spec).unwrap();
+
+                // samples are rescaled to the bit depth of the layout
+                let expected: Vec<Vec<i32>> = wav
+                    .audiodata
+                    .iter()
+                    .map(|c| {
+                        c.iter()
+                            .map(|&s| match bits {
+                                8 => s >> 8,
+                                _ => s << (bits - 16),
+                            })
+                            .collect()
+                    })
+                    .collect();
+                assert_eq!(parsed.audiodata, expected, "{bits}-bit {endianness:?}");
+            }
+        }
+    }
+
+    // 16-bit silence as unsigned 8-bit
+    let layout = RawLayout {
+        bit_depth: BitDepth::B8,
+        endianness: Endianness::Little,
+        signedness: Signedness::Unsigned,
+    };
+    let silence = parse_bytes_i32(create_bytes(vec![vec![0]], 8000).unwrap()).unwrap();
+    assert_eq!(create_raw_bytes(&silence, layout).unwrap(), [0x80]);
+}
+
+/// Builds a DSDIFF file from per-channel DSD bytes.
+#[cfg(feature = "dsd")]
+fn dff_bytes(samplerate: u32, channels: &[Vec<u8>]) -> Vec<u8> {
+    fn chunk(buf: &mut Vec<u8>, id: &[u8; 4], payload: &[u8]) {
+        buf.extend_from_slice(id);
+        buf.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+        buf.extend_from_slice(payload);
+    }
+
+    let mut prop = b"SND ".to_vec();
+    chunk(&mut prop, b"FS  ", &samplerate.to_be_bytes());
+    chunk(&mut prop, b"CHNL", &(channels.len() as u16).to_be_bytes());
+
+    let data: Vec<u8> = (0..channels[0].len())
+        .flat_map(|i| channels.iter().map(move |channel| channel[i]))
+        .collect();
+
+    let mut form = b"DSD ".to_vec();
+    chunk(&mut form, b"PROP", &prop);
+    chunk(&mut form, b"DSD ", &data);
+
+    let mut buf = vec![];
+    chunk(&mut buf, b"FRM8", &form);
+    buf
+}
+
+#[cfg(feature = "dsd")]
+#[test]
+fn read_dff() {
+    // constant streams of ones and zeros, and an alternating one that averages to silence
+    let buf = dff_bytes(2822400, &[vec![0xFF; 16], vec![0x00; 16], vec![0x55; 16]]);
+
+    let info = probe_dsd(&buf).unwrap();
+    assert_eq!(info.container, DsdContainer::Dff);
+    assert_eq!(info.num_channels, 3);
+    assert_eq!(info.num_samples, 128);
+
+    let wav = parse_dsd(&buf, 8).unwrap();
+    assert_eq!(wav.samplerate, 352800);
+    assert_eq!(wav.audiodata[0].len(), 16);
+    // the last sample's filter window runs past the end of the stream
+    for (channel, level) in wav.audiodata.iter().zip([1.0, -1.0, 0.0]) {
+        for &sample in &channel[..15] {
+            assert!((sample - level).abs() < 0.02, "{sample} != {level}");
+        }
+    }
+
+    assert!(parse_dsd(&buf, 0).is_err());
+}
+
+#[test]
+fn export_npy_layout() {
+    let audiodata = vec![vec![1, -2, 3], vec![300, -400, 500]];
+    let wav = parse_bytes(create_bytes(&audiodata, 44100).unwrap()).unwrap();
+    let buf = create_npy_bytes(&wav).unwrap();
+
+    assert_eq!(&buf[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([buf[8], buf[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&buf[10..10 + header_len]).unwrap();
+    assert!(header.starts_with("{'descr': '<i2', 'fortran_order': False, 'shape': (2, 3), }"));
+    assert!(header.ends_with('\n'));
+
+    // a row per channel
+    let samples: Vec<i16> = buf[10 + header_len..]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(samples, audiodata.concat());
+
+    let wav = parse_bytes_f64(create_bytes_f64(vec![vec![0.5]], 8000).unwrap()).unwrap();
+    let buf = create_npy_bytes(&wav).unwrap();
+    assert!(buf.windows(5).any(|w| w == b"'<f8'"));
+    assert_eq!(buf[buf.len() - 8..], 0.5f64.to_le_bytes());
+}
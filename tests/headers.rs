@@ -0,0 +1,216 @@
+use onda::*;
+
+/// Two channels of four frames.
+fn audiodata() -> Vec<Vec<i16>> {
+    vec![vec![1, 2, 3, 4], vec![-5, -6, -7, -8]]
+}
+
+/// A CAF file of big-endian 16-bit samples, which onda reads but doesn't write.
+fn caf_bytes(audiodata: &[Vec<i16>], samplerate: f64) -> Vec<u8> {
+    let num_channels = audiodata.len() as u32;
+
+    let mut buf = b"caff\x00\x01\x00\x00".to_vec();
+    buf.extend_from_slice(b"desc");
+    buf.extend_from_slice(&32u64.to_be_bytes());
+    buf.extend_from_slice(&samplerate.to_bits().to_be_bytes());
+    buf.extend_from_slice(b"lpcm");
+    for field in [0, 2 * num_channels, 1, num_channels, 16] {
+        buf.extend_from_slice(&field.to_be_bytes());
+    }
+
+    let data_size = 4 + audiodata.concat().len() as u64 * 2;
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_size.to_be_bytes());
+    // edit count
+    buf.extend_from_slice(&[0; 4]);
+    for f in 0..audiodata[0].len() {
+        for channel in audiodata {
+            buf.extend_from_slice(&channel[f].to_be_bytes());
+        }
+    }
+
+    buf
+}
+
+/// Asserts that every prefix of `buf` fails to parse as truncated.
+fn assert_truncated(buf: &[u8], format: &str) {
+    for len in 0..buf.len() {
+        match parse_bytes(&buf[..len]) {
+            Err(OndaError::Truncated { .. } | OndaError::MissingChunk { .. }) => {}
+            result => panic!("{format} cut to {len} bytes: {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn truncated_wav() {
+    let buf = create_bytes(audiodata(), 8000).unwrap();
+    assert_truncated(&buf, "WAV");
+
+    assert!(matches!(
+        parse_bytes(&buf[..3]),
+        Err(OndaError::Truncated {
+            offset: 0,
+            needed: 4
+        })
+    ));
+    assert!(matches!(
+        parse_bytes(&buf[..12]),
+        Err(OndaError::MissingChunk { id: b }) if &b == b"fmt "
+    ));
+    assert!(matches!(
+        parse_bytes(&buf[..buf.len() - 1]),
+        Err(OndaError::Truncated {
+            offset: 44,
+            needed: 16
+        })
+    ));
+}
+
+#[test]
+fn truncated_aiff() {
+    assert_truncated(&create_aiff_bytes(audiodata(), 8000).unwrap(), "AIFF");
+}
+
+#[test]
+fn truncated_caf() {
+    let buf = caf_bytes(&audiodata(), 8000.0);
+    assert_eq!(parse_bytes(&buf).unwrap().audiodata, audiodata());
+    assert_truncated(&buf, "CAF");
+}
+
+#[test]
+fn truncated_au() {
+    let buf = create_au_bytes(audiodata(), 8000).unwrap();
+    for len in 0..24 {
+        assert!(matches!(
+            parse_bytes(&buf[..len]),
+            Err(OndaError::Truncated { .. })
+        ));
+    }
+
+    // the data of AU files may run short, and only whole frames are kept
+    let wav = parse_bytes(&buf[..buf.len() - 1]).unwrap();
+    assert_eq!(wav.audiodata, [vec![1, 2, 3], vec![-5, -6, -7]]);
+}
+
+#[test]
+fn wrong_format() {
+    let mut buf = create_bytes(audiodata(), 8000).unwrap();
+    buf[..4].copy_from_slice(b"JUNK");
+    assert!(matches!(parse_bytes(&buf), Err(OndaError::NotRiff)));
+
+    let mut buf = create_bytes(audiodata(), 8000).unwrap();
+    buf[8..12].copy_from_slice(b"AVI ");
+    assert!(matches!(parse_bytes(&buf), Err(OndaError::NotWave)));
+}
+
+#[test]
+fn oversized_chunks() {
+    let buf = create_bytes(audiodata(), 8000).unwrap();
+
+    let mut fmt = buf.clone();
+    fmt[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(parse_bytes(&fmt).is_err());
+
+    let mut data = buf.clone();
+    data[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        parse_bytes(&data),
+        Err(OndaError::Truncated {
+            offset: 44,
+            needed: 0xFFFF_FFFF
+        })
+    ));
+
+    let mut caf = caf_bytes(&audiodata(), 8000.0);
+    caf[12..20].copy_from_slice(&(u64::MAX - 1).to_be_bytes());
+    assert!(matches!(
+        parse_bytes(&caf),
+        Err(OndaError::Truncated { .. })
+    ));
+
+    let mut au = create_au_bytes(audiodata(), 8000).unwrap();
+    au[20..24].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(
+        parse_bytes(&au),
+        Err(OndaError::UnsupportedChannels(u32::MAX))
+    ));
+}
+
+#[test]
+fn wide_frames() {
+    let audiodata = vec![vec![0i16; 2]; 4096];
+    let buf = create_bytes(&audiodata, 8000).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().num_channels, 4096);
+
+    // the frame size must fit the 16-bit block align of the fmt chunk
+    let audiodata = vec![vec![0i32; 1]; 20000];
+    assert!(matches!(
+        create_bytes_with_depth(&audiodata, 8000, BitDepth::B32),
+        Err(OndaError::UnsupportedChannels(20000))
+    ));
+    let raw_spec = RawSpec {
+        samplerate: 8000,
+        channels: u16::MAX,
+        bit_depth: BitDepth::B32,
+        endianness: Endianness::Little,
+        signedness: Signedness::Signed,
+    };
+    assert!(matches!(
+        parse_raw([0; 16], raw_spec),
+        Err(OndaError::UnsupportedChannels(65535))
+    ));
+
+    // and the byte rate its 32-bit field
+    let audiodata = vec![vec![0i32; 1]; 2];
+    assert!(matches!(
+        create_bytes_with_depth(&audiodata, u32::MAX, BitDepth::B32),
+        Err(OndaError::TooLarge(_))
+    ));
+}
+
+#[test]
+fn mismatched_channels() {
+    let audiodata = vec![vec![0i16; 2], vec![0; 3]];
+    assert!(matches!(
+        create_bytes(&audiodata, 8000),
+        Err(OndaError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        create_bytes(Vec::<Vec<i16>>::new(), 8000),
+        Err(OndaError::NoChannels)
+    ));
+}
+
+#[test]
+fn lenient_fmt_chunk() {
+    let mut buf = create_bytes(audiodata(), 8000).unwrap();
+    // a byte rate of 1000 and a block align of 3
+    buf[28..32].copy_from_slice(&1000u32.to_le_bytes());
+    buf[32..34].copy_from_slice(&3u16.to_le_bytes());
+    assert!(matches!(parse_bytes(&buf), Err(OndaError::BadFmtChunk(_))));
+
+    let lenient = ParseOptions { strict: false };
+    let wav = parse_bytes_with_options(&buf, lenient).unwrap();
+    assert_eq!(wav.audiodata, audiodata());
+    assert_eq!(
+        wav.warnings,
+        [
+            ParseWarning::ByteRateMismatch {
+                found: 1000,
+                expected: 32000
+            },
+            ParseWarning::BlockAlignMismatch {
+                found: 3,
+                expected: 4
+            },
+        ]
+    );
+    assert_eq!(wav.warnings[1].to_string(), "block align is 3 instead of 4");
+
+    assert!(WavReader::new(&buf[..]).is_err());
+    let mut reader = WavReader::with_options(&buf[..], lenient).unwrap();
+    assert_eq!(reader.warnings(), wav.warnings);
+    assert_eq!(reader.read_frames(10).unwrap(), audiodata());
+}
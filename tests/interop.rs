@@ -0,0 +1,149 @@
+// every test needs one of the interop features
+#![allow(unused_imports, dead_code)]
+
+use onda::*;
+
+/// Two channels of 100 frames of ramps.
+fn stereo_i16() -> Vec<Vec<i16>> {
+    vec![
+        (0..100).map(|i| i * 300).collect(),
+        (0..100).map(|i| -i * 300).collect(),
+    ]
+}
+
+#[cfg(feature = "hound")]
+#[test]
+fn hound_reader() {
+    let buf = create_bytes(stereo_i16(), 44100).unwrap();
+    let reader = hound::WavReader::new(&buf[..]).unwrap();
+    let spec = WavSpec::from(reader.spec());
+    assert_eq!(
+        spec,
+        WavSpec {
+            num_channels: 2,
+            samplerate: 44100,
+            bits_per_sample: 16,
+            float: false,
+        }
+    );
+    assert_eq!(hound::WavSpec::from(spec), reader.spec());
+
+    let wav = from_hound_reader::<i16, _>(reader).unwrap();
+    assert_eq!(wav.audiodata, stereo_i16());
+    assert_eq!(wav.valid_bits_per_sample, 16);
+
+    // wider sample types hold the same values
+    let reader = hound::WavReader::new(&buf[..]).unwrap();
+    let wav = from_hound_reader::<i32, _>(reader).unwrap();
+    assert_eq!(wav.audiodata[1][99], -29700);
+}
+
+#[cfg(feature = "dasp")]
+#[test]
+fn dasp_signal() {
+    use dasp::Signal;
+
+    let wav = parse_bytes(create_bytes(stereo_i16(), 44100).unwrap()).unwrap();
+    let mut signal = wav.signal::<2>().unwrap();
+    assert_eq!(signal.next(), [0, 0]);
+    assert_eq!(signal.next(), [300, -300]);
+    assert!(wav.signal::<1>().is_err());
+
+    // the signal ends in silence, so it is taken by the number of frames
+    let frames = wav.signal::<2>().unwrap().take(wav.num_frames());
+    let collected = WavData::from_frames(frames, 44100);
+    assert_eq!(collected.num_channels, 2);
+    assert_eq!(collected.bits_per_sample, 16);
+    assert_eq!(collected.audiodata, wav.audiodata);
+}
+
+#[cfg(feature = "rodio")]
+#[test]
+fn rodio_source() {
+    use rodio::Source;
+    use std::time::Duration;
+
+    let wav = parse_bytes(create_bytes(stereo_i16(), 1000).unwrap()).unwrap();
+    let mut source = WavSource::from(wav);
+    assert_eq!(source.channels(), 2);
+    assert_eq!(source.sample_rate(), 1000);
+    assert_eq!(source.total_duration(), Some(Duration::from_millis(100)));
+    assert_eq!(source.size_hint(), (200, Some(200)));
+
+    // samples are interleaved and scaled by the bit depth
+    source.try_seek(Duration::from_millis(50)).unwrap();
+    assert_eq!(source.next(), Some(15000.0 / 32768.0));
+    assert_eq!(source.next(), Some(-15000.0 / 32768.0));
+    assert_eq!(source.size_hint(), (98, Some(98)));
+    assert_eq!(source.count(), 98);
+
+    let wav = parse_bytes_f32(create_bytes_f32(vec![vec![0.5, -0.25]], 8000).unwrap()).unwrap();
+    assert_eq!(WavSource::from(wav).collect::<Vec<_>>(), [0.5, -0.25]);
+}
+
+#[cfg(feature = "cpal")]
+#[test]
+fn ring_writer_frames() {
+    use std::io::Cursor;
+
+    let writer = WavWriter::new(Cursor::new(vec![]), 2, 8000).unwrap();
+    let (mut producer, mut ring) = ring_writer(writer, 6);
+
+    // float samples are converted, and frames past the capacity dropped
+    assert_eq!(producer.push(&[0.5f32, -0.5, 0.25, -0.25]), 2);
+    assert_eq!(producer.push(&[1i16, 2, 3, 4, 5]), 1);
+    assert_eq!(ring.dropped_frames(), 1);
+
+    assert_eq!(ring.drain(2).unwrap(), 2);
+    assert_eq!(ring.drain(10).unwrap(), 1);
+    assert_eq!(ring.num_frames(), 3);
+
+    let buf = ring.finalize().unwrap().into_inner();
+    let wav = parse_bytes(buf).unwrap();
+    assert_eq!(
+        wav.audiodata,
+        [vec![16384, 8192, 1], vec![-16384, -8192, 2]]
+    );
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn ndarray_conversions() {
+    let wav = parse_bytes(create_bytes(stereo_i16(), 44100).unwrap()).unwrap();
+    let array = wav.to_ndarray();
+    assert_eq!(array.dim(), (2, 100));
+    assert_eq!(array[[1, 99]], -29700);
+
+    // views convert as well as owned arrays
+    let collected = WavData::from_ndarray(&array.view(), 48000).unwrap();
+    assert_eq!(collected.samplerate, 48000);
+    assert_eq!(collected.bits_per_sample, 16);
+    assert_eq!(collected.audiodata, wav.audiodata);
+
+    let empty = ndarray::Array2::<f32>::zeros((0, 10));
+    assert!(matches!(
+        WavData::from_ndarray(&empty, 48000),
+        Err(OndaError::NoChannels)
+    ));
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn arrow_record_batch() {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int16Type, UInt64Type};
+
+    let wav = parse_bytes(create_bytes(stereo_i16(), 44100).unwrap()).unwrap();
+    let batch = wav.to_record_batch().unwrap();
+    assert_eq!(batch.num_rows(), 100);
+    assert_eq!(batch.num_columns(), 3);
+
+    let schema = batch.schema();
+    assert_eq!(schema.field(2).name(), "channel_1");
+    assert_eq!(schema.metadata()["samplerate"], "44100");
+    assert_eq!(schema.metadata()["bits_per_sample"], "16");
+
+    assert_eq!(batch.column(0).as_primitive::<UInt64Type>().value(99), 99);
+    let channel = batch.column(2).as_primitive::<Int16Type>();
+    assert_eq!(channel.values()[..], wav.audiodata[1][..]);
+}
@@ -0,0 +1,753 @@
+use onda::*;
+use std::io::Cursor;
+
+/// Builds a 16-bit mono WAV file with the given chunks following the data chunk.
+fn wav_with_chunks(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut buf = create_bytes(vec![vec![1, 2, 3]], 44100).unwrap();
+    for (id, payload) in chunks {
+        buf.extend_from_slice(*id);
+        buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        buf.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            buf.push(0);
+        }
+    }
+
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf
+}
+
+/// Builds the payload of a LIST chunk of the given type from its items.
+fn list(list_type: &[u8; 4], items: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut payload = list_type.to_vec();
+    for (id, value) in items {
+        payload.extend_from_slice(*id);
+        payload.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        payload.extend_from_slice(value);
+        if value.len() % 2 == 1 {
+            payload.push(0);
+        }
+    }
+    payload
+}
+
+#[test]
+fn read_info_tags() {
+    let info = list(
+        b"INFO",
+        &[
+            (b"IART", b"Artist\0"),
+            (b"INAM", b"Title\0"),
+            (b"ICRD", b"2024-01-02\0"),
+            (b"IPRD", b"Album\0"),
+            (b"ICMT", b""),
+            (b"IENG", b"Engineer"),
+        ],
+    );
+    let wav = parse_bytes(wav_with_chunks(&[(b"LIST", info)])).unwrap();
+
+    assert_eq!(wav.metadata.artist.as_deref(), Some("Artist"));
+    assert_eq!(wav.metadata.title.as_deref(), Some("Title"));
+    assert_eq!(wav.metadata.creation_date.as_deref(), Some("2024-01-02"));
+    assert_eq!(wav.metadata.comment, None);
+    assert_eq!(
+        wav.metadata.other_tags,
+        [
+            (*b"IPRD", "Album".to_string()),
+            (*b"IENG", "Engineer".to_string()),
+        ]
+    );
+    assert_eq!(wav.audiodata, [vec![1, 2, 3]]);
+
+    // other list types and files without tags leave the metadata empty
+    let adtl = list(b"adtl", &[(b"labl", b"\x01\0\0\0Marker\0")]);
+    let wav = parse_bytes(wav_with_chunks(&[(b"LIST", adtl)])).unwrap();
+    assert_eq!(wav.metadata, WavMetadata::default());
+}
+
+#[test]
+fn read_malformed_info_tags() {
+    // a tag running past the end of its list keeps the tags before it
+    let mut info = list(b"INFO", &[(b"IART", b"Artist\0")]);
+    info.extend_from_slice(b"INAM\xFF\0\0\0Title");
+    let wav = parse_bytes(wav_with_chunks(&[(b"LIST", info)])).unwrap();
+
+    assert_eq!(wav.metadata.artist.as_deref(), Some("Artist"));
+    assert_eq!(wav.metadata.title, None);
+}
+
+#[test]
+fn write_info_tags() {
+    let metadata = WavMetadata {
+        artist: Some("Artist".to_string()),
+        title: Some("Odd".to_string()),
+        software: Some("onda".to_string()),
+        other_tags: vec![(*b"IPRD", "Album".to_string())],
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![1, 2, 3]], 44100, &metadata).unwrap();
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata, metadata);
+    assert_eq!(wav.audiodata, [vec![1, 2, 3]]);
+
+    // no tags, no LIST chunk
+    let plain = create_bytes(vec![vec![1, 2, 3]], 44100).unwrap();
+    let empty = create_bytes_with_metadata(vec![vec![1, 2, 3]], 44100, &WavMetadata::default());
+    assert_eq!(empty.unwrap(), plain);
+}
+
+#[test]
+fn write_info_tags_file() {
+    let path = std::env::temp_dir().join(format!("onda-{}-info.wav", std::process::id()));
+    let metadata = WavMetadata {
+        comment: Some("Comment".to_string()),
+        ..Default::default()
+    };
+    write_with_metadata(vec![vec![0; 4]; 2], 48000, &metadata, &path).unwrap();
+
+    let wav = read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(wav.metadata, metadata);
+}
+
+/// Builds the payload of a cue chunk from marker IDs and sample offsets.
+fn cue(points: &[(u32, u32)]) -> Vec<u8> {
+    let mut payload = (points.len() as u32).to_le_bytes().to_vec();
+    for &(id, sample_offset) in points {
+        for field in [id, sample_offset, 0x61746164, 0, 0, sample_offset] {
+            payload.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+    payload
+}
+
+#[test]
+fn read_cue_points() {
+    let wav = parse_bytes(wav_with_chunks(&[(b"cue ", cue(&[(1, 0), (7, 2)]))])).unwrap();
+    assert_eq!(
+        wav.metadata.cue_points,
+        [
+            CuePoint {
+                id: 1,
+                sample_offset: 0,
+                ..Default::default()
+            },
+            CuePoint {
+                id: 7,
+                sample_offset: 2,
+                ..Default::default()
+            },
+        ]
+    );
+
+    // a count larger than the chunk holds keeps the whole markers
+    let mut payload = cue(&[(1, 0), (2, 1)]);
+    payload[0] = 5;
+    payload.truncate(payload.len() - 4);
+    let wav = parse_bytes(wav_with_chunks(&[(b"cue ", payload)])).unwrap();
+    assert_eq!(wav.metadata.cue_points.len(), 1);
+}
+
+#[test]
+fn roundtrip_cue_points() {
+    let metadata = WavMetadata {
+        title: Some("Title".to_string()),
+        cue_points: vec![
+            CuePoint {
+                id: 1,
+                sample_offset: 0,
+                length: 2,
+                label: Some("Intro".to_string()),
+                note: Some("Count in".to_string()),
+            },
+            CuePoint {
+                id: 2,
+                sample_offset: 3,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 4]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // labels are matched to their markers by ID, whatever the order
+    let adtl = list(
+        b"adtl",
+        &[
+            (b"labl", b"\x07\0\0\0Second\0"),
+            (b"note", b"\x09\0\0\0Orphan\0"),
+            (b"labl", b"\x01\0\0\0First\0"),
+        ],
+    );
+    let buf = wav_with_chunks(&[(b"LIST", adtl), (b"cue ", cue(&[(1, 0), (7, 2)]))]);
+    let labels: Vec<_> = parse_bytes(&buf)
+        .unwrap()
+        .metadata
+        .cue_points
+        .into_iter()
+        .map(|c| c.label)
+        .collect();
+    assert_eq!(
+        labels,
+        [Some("First".to_string()), Some("Second".to_string())]
+    );
+}
+
+#[test]
+fn roundtrip_sampler() {
+    let metadata = WavMetadata {
+        sampler: Some(SamplerInfo {
+            midi_unity_note: 69,
+            midi_pitch_fraction: 1 << 31,
+            loops: vec![
+                SampleLoop {
+                    cue_point_id: 0,
+                    loop_type: 0,
+                    start: 1,
+                    end: 3,
+                    play_count: 0,
+                },
+                SampleLoop {
+                    cue_point_id: 0,
+                    loop_type: 1,
+                    start: 0,
+                    end: 2,
+                    play_count: 4,
+                },
+            ],
+        }),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 4]], 48000, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // a truncated smpl chunk is ignored
+    let wav = parse_bytes(wav_with_chunks(&[(b"smpl", vec![0; 20])])).unwrap();
+    assert_eq!(wav.metadata.sampler, None);
+}
+
+#[test]
+fn roundtrip_instrument() {
+    let instrument = InstrumentInfo {
+        base_note: 60,
+        detune: -12,
+        gain: -3,
+        low_note: 48,
+        high_note: 72,
+        low_velocity: 1,
+        high_velocity: 127,
+    };
+    let metadata = WavMetadata {
+        instrument: Some(instrument.clone()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 4]], 48000, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // the chunk is 7 bytes, with a pad byte
+    let payload = vec![60, 0xF4, 0xFD, 48, 72, 1, 127];
+    let wav = parse_bytes(wav_with_chunks(&[(b"inst", payload)])).unwrap();
+    assert_eq!(wav.metadata.instrument, Some(instrument));
+
+    let wav = parse_bytes(wav_with_chunks(&[(b"inst", vec![60; 6])])).unwrap();
+    assert_eq!(wav.metadata.instrument, None);
+}
+
+#[test]
+fn roundtrip_acid() {
+    let acid = AcidInfo {
+        one_shot: false,
+        stretch: true,
+        root_note: Some(57),
+        num_beats: 8,
+        meter_denominator: 4,
+        meter_numerator: 4,
+        tempo: 128.5,
+    };
+    let metadata = WavMetadata {
+        acid: Some(acid.clone()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 4]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // the root note only counts when its flag is set
+    let mut payload = 0x05u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&60u16.to_le_bytes());
+    payload.extend_from_slice(&[0; 6]);
+    payload.extend_from_slice(&1u32.to_le_bytes());
+    payload.extend_from_slice(&4u16.to_le_bytes());
+    payload.extend_from_slice(&3u16.to_le_bytes());
+    payload.extend_from_slice(&90f32.to_le_bytes());
+    let wav = parse_bytes(wav_with_chunks(&[(b"acid", payload)])).unwrap();
+    assert_eq!(
+        wav.metadata.acid,
+        Some(AcidInfo {
+            one_shot: true,
+            stretch: true,
+            root_note: None,
+            num_beats: 1,
+            meter_denominator: 4,
+            meter_numerator: 3,
+            tempo: 90.0,
+        })
+    );
+}
+
+/// Builds a version 2 bext payload with the given description and coding history.
+fn bext(description: &str, coding_history: &str) -> Vec<u8> {
+    let mut payload = vec![0; 602];
+    payload[..description.len()].copy_from_slice(description.as_bytes());
+    payload[256..262].copy_from_slice(b"Studio");
+    payload[320..330].copy_from_slice(b"2024-05-06");
+    payload[330..338].copy_from_slice(b"07:08:09");
+    // time reference of 2^32 + 48000 samples
+    payload[338..342].copy_from_slice(&48000u32.to_le_bytes());
+    payload[342..346].copy_from_slice(&1u32.to_le_bytes());
+    payload[346..348].copy_from_slice(&2u16.to_le_bytes());
+    payload[348] = 0x06;
+    // integrated loudness of -23 LUFS and a true peak of -1.5 dBTP
+    payload[412..414].copy_from_slice(&(-2300i16).to_le_bytes());
+    payload[416..418].copy_from_slice(&(-150i16).to_le_bytes());
+    payload.extend_from_slice(coding_history.as_bytes());
+    payload
+}
+
+#[test]
+fn read_bext() {
+    let history = "A=PCM,F=48000,W=24,M=stereo\r\n";
+    let wav = parse_bytes(wav_with_chunks(&[(b"bext", bext("Take 1", history))])).unwrap();
+
+    let bext = wav.metadata.bext.unwrap();
+    assert_eq!(bext.description, "Take 1");
+    assert_eq!(bext.originator, "Studio");
+    assert_eq!(bext.originator_reference, "");
+    assert_eq!(bext.origination_date, "2024-05-06");
+    assert_eq!(bext.origination_time, "07:08:09");
+    assert_eq!(bext.time_reference, (1 << 32) + 48000);
+    assert_eq!(bext.version, 2);
+    assert_eq!(bext.umid[0], 0x06);
+    assert_eq!(bext.loudness_value, -2300);
+    assert_eq!(bext.max_true_peak_level, -150);
+    assert_eq!(bext.coding_history, history);
+
+    // too short for the fixed fields
+    let wav = parse_bytes(wav_with_chunks(&[(b"bext", vec![0; 600])])).unwrap();
+    assert_eq!(wav.metadata.bext, None);
+}
+
+#[test]
+fn read_bext_before_fmt() {
+    // Broadcast Wave files often put the bext chunk ahead of the fmt chunk
+    let mut buf = create_bytes(vec![vec![5, 6]], 48000).unwrap();
+    let mut chunk = b"bext".to_vec();
+    let payload = bext("First", "");
+    chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(&payload);
+    buf.splice(12..12, chunk);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, [vec![5, 6]]);
+    assert_eq!(wav.metadata.bext.unwrap().description, "First");
+}
+
+#[test]
+fn write_bext() {
+    let bext = BextChunk {
+        description: "Take 2".to_string(),
+        originator: "Recorder".to_string(),
+        origination_date: "2024-05-06".to_string(),
+        origination_time: "07:08:09".to_string(),
+        time_reference: 86400 * 48000,
+        loudness_value: -1800,
+        coding_history: "A=PCM,F=48000,W=16,M=mono\r\n".to_string(),
+        ..Default::default()
+    };
+    let metadata = WavMetadata {
+        bext: Some(bext.clone()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![1, 2]], 48000, &metadata).unwrap();
+
+    // the bext chunk comes first, and is always written as version 2
+    assert_eq!(&buf[12..16], b"bext");
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata.bext, Some(BextChunk { version: 2, ..bext }));
+    assert_eq!(wav.audiodata, [vec![1, 2]]);
+
+    let too_long = WavMetadata {
+        bext: Some(BextChunk {
+            origination_date: "2024-05-06T07".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(create_bytes_with_metadata(vec![vec![1, 2]], 48000, &too_long).is_err());
+}
+
+#[test]
+fn roundtrip_ixml() {
+    let xml = r#"<?xml version="1.0"?>
+<BWFXML>
+  <PROJECT>Feature &amp; Short</PROJECT>
+  <SCENE>12A</SCENE>
+  <TAKE>3</TAKE>
+  <SPEED>
+    <TIMECODE_RATE>25/1</TIMECODE_RATE>
+    <TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>1</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>
+    <TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>48000</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>
+  </SPEED>
+  <TRACK_LIST>
+    <TRACK><CHANNEL_INDEX>1</CHANNEL_INDEX><NAME>Boom</NAME></TRACK>
+    <TRACK><CHANNEL_INDEX>2</CHANNEL_INDEX><NAME>Lav &lt;1&gt;</NAME></TRACK>
+  </TRACK_LIST>
+</BWFXML>"#;
+    let metadata = WavMetadata {
+        ixml: Some(xml.to_string()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]; 2], 48000, &metadata).unwrap();
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata, metadata);
+
+    let info = parse_ixml(wav.metadata.ixml.as_deref().unwrap());
+    assert_eq!(info.project.as_deref(), Some("Feature & Short"));
+    assert_eq!(info.scene.as_deref(), Some("12A"));
+    assert_eq!(info.take.as_deref(), Some("3"));
+    assert_eq!(info.tape, None);
+    assert_eq!(info.timecode_rate.as_deref(), Some("25/1"));
+    assert_eq!(info.timestamp, Some((1 << 32) + 48000));
+    assert_eq!(
+        info.tracks,
+        [
+            IxmlTrack {
+                channel_index: Some(1),
+                name: Some("Boom".to_string()),
+            },
+            IxmlTrack {
+                channel_index: Some(2),
+                name: Some("Lav <1>".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn roundtrip_id3() {
+    // the chunk is kept as is, odd sizes included
+    let metadata = WavMetadata {
+        id3: Some(b"ID3\x04\0\0\0\0\0\0\x01".to_vec()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // some writers use an uppercase chunk ID
+    let wav = parse_bytes(wav_with_chunks(&[(b"ID3 ", b"ID3".to_vec())])).unwrap();
+    assert_eq!(wav.metadata.id3.as_deref(), Some(&b"ID3"[..]));
+}
+
+#[cfg(feature = "id3")]
+#[test]
+fn id3_tags() {
+    let tags = Id3Tags {
+        title: Some("Title".to_string()),
+        artist: Some("Artist".to_string()),
+        artwork: Some(Artwork {
+            mime_type: "image/png".to_string(),
+            data: vec![0x89, b'P', b'N', b'G'],
+        }),
+    };
+    let metadata = WavMetadata {
+        id3: Some(create_id3_bytes(&tags).unwrap()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]], 44100, &metadata).unwrap();
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(parse_id3(wav.metadata.id3.unwrap()).unwrap(), tags);
+}
+
+#[test]
+fn roundtrip_cart() {
+    let cart = CartChunk {
+        version: "0101".to_string(),
+        title: "Station ID".to_string(),
+        artist: "Voice".to_string(),
+        cut_id: "12345".to_string(),
+        category: "ID".to_string(),
+        out_cue: "...the best music".to_string(),
+        start_date: "2024-01-01".to_string(),
+        start_time: "00:00:00".to_string(),
+        end_date: "2024-12-31".to_string(),
+        end_time: "23:59:59".to_string(),
+        producer_app_id: "onda".to_string(),
+        level_reference: 32768,
+        post_timers: vec![
+            CartTimer {
+                usage: *b"INT ",
+                value: 4410,
+            },
+            CartTimer {
+                usage: *b"SEG1",
+                value: 88200,
+            },
+        ],
+        url: "https://example.com/cut/12345".to_string(),
+        tag_text: "Line 1\r\nLine 2\r\n".to_string(),
+        ..Default::default()
+    };
+    let metadata = WavMetadata {
+        cart: Some(cart),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    let too_many_timers = WavMetadata {
+        cart: Some(CartChunk {
+            post_timers: vec![CartTimer::default(); 9],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(create_bytes_with_metadata(vec![vec![0; 2]], 44100, &too_many_timers).is_err());
+
+    let too_long = WavMetadata {
+        cart: Some(CartChunk {
+            start_time: "00:00:00.000".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(create_bytes_with_metadata(vec![vec![0; 2]], 44100, &too_long).is_err());
+}
+
+#[test]
+fn roundtrip_peak() {
+    let audiodata = vec![vec![0, -16384, 8192], vec![100, 200, -32768]];
+    let peak = compute_peak(&audiodata);
+    assert_eq!(peak.peaks.len(), 2);
+    assert_eq!(peak.peaks[0].value, 0.5);
+    assert_eq!(peak.peaks[0].position, 1);
+    assert_eq!(peak.peaks[1].value, 1.0);
+    assert_eq!(peak.peaks[1].position, 2);
+
+    let metadata = WavMetadata {
+        peak: Some(peak),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(&audiodata, 44100, &metadata).unwrap();
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata, metadata);
+    assert_eq!(wav.compute_peak().peaks, metadata.peak.unwrap().peaks);
+}
+
+#[test]
+fn compute_peak_other_types() {
+    let buf = create_bytes_with_depth(vec![vec![4194304, -8388608]], 48000, BitDepth::B24).unwrap();
+    let peak = parse_bytes_i32(&buf).unwrap().compute_peak();
+    assert_eq!(peak.peaks[0].value, 1.0);
+    assert_eq!(peak.peaks[0].position, 1);
+
+    let buf = create_bytes_f32(vec![vec![0.25, -0.75, 0.5]], 48000).unwrap();
+    let peak = parse_bytes_f32(&buf).unwrap().compute_peak();
+    assert_eq!(peak.peaks[0].value, 0.75);
+    assert_eq!(peak.peaks[0].position, 1);
+    assert_eq!(compute_peak([[0.25f32, -0.75, 0.5]]).peaks, peak.peaks);
+}
+
+#[test]
+fn roundtrip_adm() {
+    let chna = ChnaChunk {
+        num_tracks: 2,
+        audio_ids: vec![
+            AudioId {
+                track_index: 1,
+                uid: "ATU_00000001".to_string(),
+                track_ref: "AT_00010001_01".to_string(),
+                pack_ref: "AP_00010002".to_string(),
+            },
+            AudioId {
+                track_index: 2,
+                uid: "ATU_00000002".to_string(),
+                track_ref: "AT_00010002_01".to_string(),
+                pack_ref: "AP_00010002".to_string(),
+            },
+        ],
+    };
+    let metadata = WavMetadata {
+        axml: Some(r#"<ebuCoreMain><coreMetadata/></ebuCoreMain>"#.to_string()),
+        chna: Some(chna),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]; 2], 48000, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    let too_long = WavMetadata {
+        chna: Some(ChnaChunk {
+            num_tracks: 1,
+            audio_ids: vec![AudioId {
+                track_index: 1,
+                uid: "ATU_000000001".to_string(),
+                ..Default::default()
+            }],
+        }),
+        ..Default::default()
+    };
+    assert!(create_bytes_with_metadata(vec![vec![0; 2]], 48000, &too_long).is_err());
+}
+
+#[test]
+fn read_chna_unused_entries() {
+    let mut payload = vec![1, 0, 2, 0];
+    for track_index in [0u16, 1] {
+        payload.extend_from_slice(&track_index.to_le_bytes());
+        payload.extend_from_slice(b"ATU_00000001AT_00010001_01AP_00010001\0");
+    }
+    let wav = parse_bytes(wav_with_chunks(&[(b"chna", payload)])).unwrap();
+
+    let chna = wav.metadata.chna.unwrap();
+    assert_eq!(chna.num_tracks, 1);
+    assert_eq!(chna.audio_ids.len(), 1);
+    assert_eq!(chna.audio_ids[0].track_index, 1);
+    assert_eq!(chna.audio_ids[0].pack_ref, "AP_00010001");
+}
+
+#[test]
+fn roundtrip_unknown_chunks() {
+    let wav = parse_bytes(wav_with_chunks(&[
+        (b"umid", vec![1, 2, 3]),
+        (b"JUNK", vec![0; 4]),
+        // too short for a bext chunk, so it is kept as stored
+        (b"bext", vec![4; 10]),
+    ]))
+    .unwrap();
+    assert_eq!(wav.metadata.bext, None);
+    assert_eq!(
+        wav.metadata.unknown_chunks,
+        [
+            RawChunk {
+                id: *b"umid",
+                data: vec![1, 2, 3],
+            },
+            RawChunk {
+                id: *b"bext",
+                data: vec![4; 10],
+            },
+        ]
+    );
+
+    let buf = create_bytes_with_metadata(&wav.audiodata, 44100, &wav.metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, wav.metadata);
+}
+
+#[test]
+fn write_custom_chunks() {
+    let mut writer = WavWriter::new(Cursor::new(vec![]), 1, 44100).unwrap();
+    writer.add_chunk(*b"umid", &[1, 2, 3]).unwrap();
+    writer.add_chunk(*b"xtra", &[]).unwrap();
+    assert!(writer.add_chunk(*b"data", &[0; 4]).is_err());
+    writer.write_frames(&[[5], [6]]).unwrap();
+
+    let buf = writer.finalize().unwrap().into_inner();
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.audiodata, [vec![5, 6]]);
+    assert_eq!(
+        wav.metadata.unknown_chunks,
+        [
+            RawChunk {
+                id: *b"umid",
+                data: vec![1, 2, 3],
+            },
+            RawChunk {
+                id: *b"xtra",
+                data: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn roundtrip_markers() {
+    let markers = vec![
+        Marker {
+            name: Some("Intro".to_string()),
+            start: 0,
+            length: 0,
+            kind: MarkerKind::Cue,
+        },
+        Marker {
+            name: Some("Sustain".to_string()),
+            start: 100,
+            length: 50,
+            kind: MarkerKind::Loop {
+                loop_type: 0,
+                play_count: 0,
+            },
+        },
+        Marker {
+            name: Some("Verse".to_string()),
+            start: 200,
+            length: 300,
+            kind: MarkerKind::Cue,
+        },
+        Marker {
+            name: None,
+            start: 400,
+            length: 10,
+            kind: MarkerKind::Loop {
+                loop_type: 1,
+                play_count: 2,
+            },
+        },
+    ];
+    let mut metadata = WavMetadata::default();
+    metadata.set_markers(&markers);
+    assert_eq!(metadata.cue_points.len(), 3);
+    assert_eq!(metadata.sampler.as_ref().unwrap().midi_unity_note, 60);
+    assert_eq!(metadata.markers(), markers);
+
+    let buf = create_bytes_with_metadata(vec![vec![0; 500]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata.markers(), markers);
+}
+
+#[test]
+fn roundtrip_display_title() {
+    let metadata = WavMetadata {
+        display_title: Some("Windows title".to_string()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]], 44100, &metadata).unwrap();
+    assert_eq!(parse_bytes(&buf).unwrap().metadata, metadata);
+
+    // DISP chunks holding other clipboard formats are kept as stored
+    let bitmap = [&8u32.to_le_bytes()[..], &[1, 2, 3, 4]].concat();
+    let wav = parse_bytes(wav_with_chunks(&[(b"DISP", bitmap.clone())])).unwrap();
+    assert_eq!(wav.metadata.display_title, None);
+    assert_eq!(
+        wav.metadata.unknown_chunks,
+        [RawChunk {
+            id: *b"DISP",
+            data: bitmap,
+        }]
+    );
+}
+
+#[test]
+fn text_chunks() {
+    let mut metadata = WavMetadata::default();
+    metadata.set_text_chunk(*b"ISBJ", "First");
+    metadata.set_text_chunk(*b"ISBJ", "Subject");
+    assert_eq!(metadata.unknown_chunks.len(), 1);
+
+    let buf = create_bytes_with_metadata(vec![vec![0; 2]], 44100, &metadata).unwrap();
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.metadata.text_chunk(b"ISBJ").as_deref(), Some("Subject"));
+    assert_eq!(wav.metadata.text_chunk(b"ICMT"), None);
+}
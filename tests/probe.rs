@@ -0,0 +1,85 @@
+use onda::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes `buf` to a temporary file for a test, returning its path.
+fn temp_file(name: &str, buf: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("onda-{}-{name}", std::process::id()));
+    fs::write(&path, buf).unwrap();
+    path
+}
+
+#[test]
+fn probe_wav() {
+    let metadata = WavMetadata {
+        title: Some("Probed".to_string()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 44100]; 2], 44100, &metadata).unwrap();
+    let path = temp_file("probe-wav.wav", &buf);
+    let info = probe(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(info.num_channels, 2);
+    assert_eq!(info.samplerate, 44100);
+    assert_eq!(info.bits_per_sample, 16);
+    assert_eq!(info.num_frames, 44100);
+    assert_eq!(info.duration(), std::time::Duration::from_secs(1));
+
+    let ids: Vec<_> = info.chunks.iter().map(|chunk| &chunk.id).collect();
+    assert_eq!(ids, [b"fmt ", b"data", b"LIST"]);
+    assert_eq!(
+        info.chunks[1],
+        ChunkInfo {
+            id: *b"data",
+            offset: 36,
+            size: 44100 * 4,
+        }
+    );
+    assert_eq!(info.chunks[2].offset, 44 + 44100 * 4);
+}
+
+#[test]
+fn probe_adpcm_fact() {
+    let mut buf = create_bytes(vec![vec![0; 4]], 8000).unwrap();
+    // an IMA ADPCM fmt chunk with 8-byte blocks of 9 frames, two blocks and a fact chunk
+    buf[20..22].copy_from_slice(&0x11u16.to_le_bytes());
+    buf[32..34].copy_from_slice(&8u16.to_le_bytes());
+    buf[34..36].copy_from_slice(&4u16.to_le_bytes());
+    buf.truncate(40);
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&[0; 16]);
+    buf.extend_from_slice(b"fact\x04\x00\x00\x00");
+    buf.extend_from_slice(&12u32.to_le_bytes());
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let path = temp_file("probe-adpcm-fact.wav", &buf);
+    let info = probe(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(info.bits_per_sample, 16);
+    assert_eq!(info.num_frames, 12);
+}
+
+#[test]
+fn probe_other_containers() {
+    let buf = create_aiff_bytes(vec![vec![0; 100]], 22050).unwrap();
+    let path = temp_file("probe-other-containers.aiff", &buf);
+    let info = probe(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(info.num_channels, 1);
+    assert_eq!(info.samplerate, 22050);
+    assert_eq!(info.num_frames, 100);
+    assert!(info.chunks.is_empty());
+}
+
+#[test]
+fn probe_missing_chunks() {
+    let buf = create_bytes(vec![vec![0; 4]], 8000).unwrap();
+    let path = temp_file("probe-missing-chunks.wav", &buf[..36]);
+    let result = probe(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+}
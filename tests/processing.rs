@@ -0,0 +1,467 @@
+use onda::*;
+use std::f64::consts::TAU;
+
+fn sine_wav(samplerate: u32, num_channels: u16, seconds: f64, dbfs: f64) -> WavData<f32> {
+    let amplitude = 10f64.powf(dbfs / 20.0);
+    let num_frames = (samplerate as f64 * seconds) as usize;
+    let channel: Vec<f32> = (0..num_frames)
+        .map(|i| (amplitude * (TAU * 1000.0 * i as f64 / samplerate as f64).sin()) as f32)
+        .collect();
+
+    let mut wav = WavData::new(samplerate, num_channels);
+    wav.audiodata = vec![channel; num_channels as usize];
+    wav
+}
+
+#[test]
+fn shared_copy_on_write() {
+    let wav = sine_wav(8000, 2, 0.5, -6.0);
+    let mut shared = wav.clone().into_shared();
+    let snapshot = shared.clone();
+    assert!(shared.is_shared(0) && shared.is_shared(1));
+
+    shared.channel_mut(0).unwrap()[0] = 1.0;
+    // only the mutated channel is copied
+    assert!(!shared.is_shared(0) && shared.is_shared(1));
+    assert_eq!(snapshot.channel(0).unwrap(), wav.audiodata[0]);
+    assert_eq!(shared.channel(0).unwrap()[0], 1.0);
+
+    for channel in shared.channels_mut() {
+        channel[1] = 0.5;
+    }
+    assert!(!shared.is_shared(1));
+    assert_eq!(snapshot.channels().len(), 2);
+    assert!(snapshot
+        .channels()
+        .all(|channel| channel[1] == wav.audiodata[0][1]));
+
+    let owned = shared.into_wavdata();
+    assert_eq!(owned.audiodata[1][..2], [wav.audiodata[1][0], 0.5]);
+    assert_eq!(WavData::from(snapshot).audiodata, wav.audiodata);
+}
+
+#[test]
+fn resample() {
+    let wav = sine_wav(44100, 2, 0.5, -6.0);
+    let resampled = wav.resample(48000).unwrap();
+
+    assert_eq!(resampled.samplerate, 48000);
+    assert_eq!(resampled.num_channels, 2);
+    assert_eq!(resampled.num_frames(), 24000);
+
+    let expected = sine_wav(48000, 2, 0.5, -6.0);
+    for (&a, &b) in resampled.audiodata[0].iter().zip(&expected.audiodata[0]) {
+        assert!((a - b).abs() < 0.01);
+    }
+
+    let back = resampled.resample(44100).unwrap();
+    assert_eq!(back.num_frames(), wav.num_frames());
+
+    assert!(matches!(
+        wav.resample(0),
+        Err(OndaError::InvalidArgument(_))
+    ));
+    let mut uneven = wav.clone();
+    uneven.audiodata[1].pop();
+    assert!(uneven.resample(48000).is_err());
+}
+
+#[cfg(feature = "hq-resample")]
+#[test]
+fn resample_hq() {
+    let wav = sine_wav(44100, 2, 0.5, -6.0);
+    let resampled = wav.resample_hq(48000).unwrap();
+    assert_eq!(
+        resampled.num_frames(),
+        wav.resample(48000).unwrap().num_frames()
+    );
+    let peak = resampled.audiodata[0][1000..23000]
+        .iter()
+        .fold(0f32, |peak, s| peak.max(s.abs()));
+    assert!((peak - 0.5).abs() < 0.005);
+
+    // a tone above the Nyquist frequency of the target rate is filtered out instead of
+    // aliasing
+    let mut high = WavData::<f32>::new(44100, 1);
+    high.audiodata = vec![(0..22050)
+        .map(|i| (0.5 * (TAU * 15000.0 * i as f64 / 44100.0).sin()) as f32)
+        .collect()];
+    let rms = |wav: &WavData<f32>| {
+        let channel = &wav.audiodata[0][1000..10000];
+        (channel.iter().map(|s| s * s).sum::<f32>() / channel.len() as f32).sqrt()
+    };
+    assert!(rms(&high.resample_hq(22050).unwrap()) < 0.001);
+    assert!(rms(&high.resample(22050).unwrap()) > 0.1);
+}
+
+#[test]
+fn resample_metadata() {
+    let mut wav = sine_wav(22050, 1, 1.0, -6.0);
+    wav.metadata.cue_points = vec![CuePoint {
+        id: 1,
+        sample_offset: 11025,
+        ..Default::default()
+    }];
+    wav.metadata.bext = Some(BextChunk {
+        time_reference: 22050 * 3600,
+        ..Default::default()
+    });
+    wav.metadata.peak = Some(PeakChunk {
+        timestamp: 0,
+        peaks: vec![Peak {
+            value: 0.5,
+            position: 100,
+        }],
+    });
+
+    let resampled = wav.resample(44100).unwrap();
+    let metadata = resampled.metadata;
+    assert_eq!(metadata.cue_points[0].sample_offset, 22050);
+    // still an hour after midnight
+    assert_eq!(metadata.bext.unwrap().time_reference, 44100 * 3600);
+    assert_eq!(metadata.peak.unwrap().peaks[0].position, 200);
+}
+
+#[test]
+fn mono_and_stereo() {
+    let mut wav = WavData::<i16>::new(44100, 2);
+    wav.audiodata = vec![vec![1000, 2000, -3000], vec![3000, 0, -1000]];
+    wav.channel_mask = 0x3;
+
+    let mono = wav.to_mono().unwrap();
+    assert_eq!((mono.num_channels, mono.channel_mask), (1, 0));
+    assert_eq!(mono.audiodata, [vec![2000, 1000, -2000]]);
+
+    let stereo = mono.to_stereo().unwrap();
+    assert_eq!(stereo.num_channels, 2);
+    assert_eq!(stereo.audiodata, vec![vec![2000, 1000, -2000]; 2]);
+    assert_eq!(wav.to_stereo().unwrap().audiodata, wav.audiodata);
+
+    let mut surround = WavData::<i16>::new(44100, 3);
+    surround.audiodata = vec![vec![0]; 3];
+    assert!(matches!(
+        surround.to_stereo(),
+        Err(OndaError::UnsupportedChannels(3))
+    ));
+    wav.audiodata[1].pop();
+    assert!(wav.to_mono().is_err());
+}
+
+#[test]
+fn shared_mono_and_stereo() {
+    let mut wav = sine_wav(8000, 2, 0.5, -6.0);
+    wav.audiodata[1].iter_mut().for_each(|s| *s *= 0.5);
+    let shared = wav.clone().into_shared();
+
+    let mono = shared.to_mono().unwrap();
+    assert_eq!(
+        mono.clone().into_wavdata().audiodata,
+        wav.to_mono().unwrap().audiodata
+    );
+
+    // the mono channel is shared rather than copied
+    let stereo = mono.to_stereo().unwrap();
+    assert!(stereo.is_shared(0) && stereo.is_shared(1));
+    assert_eq!(stereo.channel(0), stereo.channel(1));
+}
+
+#[test]
+fn remix() {
+    let mut wav = WavData::<i16>::new(44100, 3);
+    wav.audiodata = vec![vec![1000, 30000], vec![2000, 30000], vec![-4000, 0]];
+
+    // swap the first two channels and sum all three into a third
+    let matrix = [
+        vec![0.0, 1.0, 0.0],
+        vec![1.0, 0.0, 0.0],
+        vec![1.0, 1.0, 1.0],
+    ];
+    let remixed = wav.remix(&matrix).unwrap();
+    assert_eq!(remixed.num_channels, 3);
+    // integer samples are clamped where the mix is louder than full scale
+    assert_eq!(
+        remixed.audiodata,
+        [vec![2000, 30000], vec![1000, 30000], vec![-1000, i16::MAX]]
+    );
+
+    let stereo = wav.remix(&vec![vec![0.5, 0.5, 0.0]; 2]).unwrap();
+    assert_eq!((stereo.num_channels, stereo.channel_mask), (2, 0));
+    assert_eq!(stereo.audiodata, vec![vec![1500, 30000]; 2]);
+
+    assert!(matches!(wav.remix(&[]), Err(OndaError::NoChannels)));
+    assert!(matches!(
+        wav.remix(&[vec![1.0, 0.0]]),
+        Err(OndaError::ChannelMismatch {
+            expected: 3,
+            found: 2
+        })
+    ));
+
+    // floats are left unclamped, on shared audio data as well
+    let mut loud = WavData::<f32>::new(44100, 2);
+    loud.audiodata = vec![vec![0.75], vec![0.75]];
+    let summed = loud.into_shared().remix(&[vec![1.0, 1.0]]).unwrap();
+    assert_eq!(summed.num_channels, 1);
+    assert_eq!(summed.channel(0).unwrap(), [1.5]);
+}
+
+#[test]
+fn gain() {
+    let mut wav = WavData::<i16>::new(44100, 1);
+    wav.audiodata = vec![vec![16384, -16384, 1000]];
+
+    let mut halved = wav.clone();
+    halved.apply_gain_db(-6.0206, Overflow::Error).unwrap();
+    assert_eq!(halved.audiodata, [vec![8192, -8192, 500]]);
+
+    // +12 dB takes the first two samples beyond full scale
+    let mut clamped = wav.clone();
+    clamped.apply_gain_db(12.0, Overflow::Clamp).unwrap();
+    assert_eq!(clamped.audiodata[0][..2], [i16::MAX, i16::MIN]);
+
+    let mut unchanged = wav.clone();
+    assert!(matches!(
+        unchanged.apply_gain_db(12.0, Overflow::Error),
+        Err(OndaError::SampleOutOfRange {
+            bits_per_sample: 16
+        })
+    ));
+    assert_eq!(unchanged.audiodata, wav.audiodata);
+
+    let mut limited = wav.clone();
+    limited.apply_gain_db(6.0206, Overflow::SoftLimit).unwrap();
+    let [loud, quiet] = [limited.audiodata[0][0], limited.audiodata[0][2]];
+    // full scale is bent down to about -0.5 dBFS, and quiet samples are only scaled
+    assert!((30700..30900).contains(&loud));
+    assert_eq!(quiet, 2000);
+
+    assert!(wav.apply_gain_db(f32::NAN, Overflow::Clamp).is_err());
+}
+
+#[test]
+fn shared_gain() {
+    let mut wav = sine_wav(8000, 2, 0.5, -6.0);
+    wav.audiodata[1].iter_mut().for_each(|s| *s *= 0.5);
+    let mut shared = wav.clone().into_shared();
+    let snapshot = shared.clone();
+
+    wav.apply_gain_db(-6.0, Overflow::Clamp).unwrap();
+    shared.apply_gain_db(-6.0, Overflow::Clamp).unwrap();
+    assert_eq!(shared.clone().into_wavdata().audiodata, wav.audiodata);
+
+    // the snapshot keeps the samples from before the gain
+    assert!(snapshot
+        .channel(0)
+        .unwrap()
+        .iter()
+        .zip(shared.channel(0).unwrap())
+        .any(|(a, b)| a != b));
+
+    // a failed check leaves shared channels shared
+    let mut loud = snapshot.clone();
+    assert!(loud.apply_gain_db(12.0, Overflow::Error).is_err());
+    assert!(loud.is_shared(0) && loud.is_shared(1));
+}
+
+#[test]
+fn loudness() {
+    // the first test signal of EBU Tech 3341, which measures -23 LUFS
+    let wav = sine_wav(48000, 2, 20.0, -23.0);
+    let loudness = wav.loudness().unwrap();
+    assert!((loudness.integrated + 23.0).abs() < 0.1);
+    assert!((loudness.true_peak + 23.0).abs() < 0.5);
+
+    let mut normalized = sine_wav(48000, 2, 5.0, -30.0);
+    normalized.normalize_lufs(-16.0).unwrap();
+    let loudness = normalized.loudness().unwrap();
+    assert!((loudness.integrated + 16.0).abs() < 0.1);
+
+    let silence = sine_wav(48000, 2, 1.0, f64::NEG_INFINITY);
+    assert_eq!(silence.loudness().unwrap().integrated, f32::NEG_INFINITY);
+}
+
+#[test]
+fn fades() {
+    use std::time::Duration;
+
+    let mut wav = WavData::<f32>::new(10, 2);
+    wav.audiodata = vec![vec![1.0; 10], vec![-0.5; 10]];
+
+    let mut linear = wav.clone();
+    linear.fade_in(Duration::from_millis(400), Curve::Linear);
+    assert_eq!(linear.audiodata[0][..5], [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(linear.audiodata[1][..5], [0.0, -0.125, -0.25, -0.375, -0.5]);
+    linear.fade_out(Duration::from_millis(200), Curve::Linear);
+    assert_eq!(linear.audiodata[0][7..], [1.0, 0.5, 0.0]);
+
+    // equal power fades in and out of the same length sum to constant power
+    let mut fade_in = wav.clone();
+    fade_in.fade_in(Duration::from_secs(1), Curve::EqualPower);
+    let mut fade_out = wav.clone();
+    fade_out.fade_out(Duration::from_secs(1), Curve::EqualPower);
+    // fade outs mirror fade ins, ending at silence on the last frame
+    for f in 1..10 {
+        let power = fade_in.audiodata[0][f].powi(2) + fade_out.audiodata[0][f - 1].powi(2);
+        assert!((power - 1.0).abs() < 1e-6);
+    }
+
+    // exponential fades stay quiet for longer, and durations beyond the audio data fade
+    // all of it
+    let mut exponential = wav.clone();
+    exponential.fade_in(Duration::from_secs(10), Curve::Exponential);
+    assert_eq!(exponential.audiodata[0][0], 0.0);
+    assert!(exponential.audiodata[0][5] < linear.audiodata[0][5] * 0.1);
+    let mut whole = wav.clone();
+    whole.fade_in(Duration::from_secs(1), Curve::Exponential);
+    assert_eq!(exponential.audiodata, whole.audiodata);
+
+    let mut shared = wav.clone().into_shared();
+    let snapshot = shared.clone();
+    shared.fade_in(Duration::from_millis(400), Curve::Linear);
+    shared.fade_out(Duration::from_millis(200), Curve::Linear);
+    assert_eq!(shared.into_wavdata().audiodata, linear.audiodata);
+    assert_eq!(snapshot.channel(0).unwrap(), wav.audiodata[0]);
+}
+
+/// Mono 16-bit audio data at 10 Hz, loud where `pattern` has a 1.
+fn pattern_wav(pattern: &[u8]) -> WavData<i16> {
+    let mut wav = WavData::new(10, 1);
+    wav.audiodata = vec![pattern.iter().map(|&p| p as i16 * 10000).collect()];
+    wav
+}
+
+#[test]
+fn trim_silence() {
+    use std::time::Duration;
+
+    let wav = pattern_wav(&[0, 0, 0, 1, 0, 1, 0, 0]);
+    let trimmed = wav.trim_silence(-50.0, Duration::ZERO).unwrap();
+    assert_eq!(trimmed.audiodata, [vec![10000, 0, 10000]]);
+
+    // silence shorter than the minimum duration is kept
+    let trimmed = wav.trim_silence(-50.0, Duration::from_millis(300)).unwrap();
+    assert_eq!(trimmed.audiodata, [vec![10000, 0, 10000, 0, 0]]);
+
+    // quiet samples count as silence below the threshold only
+    assert_eq!(
+        wav.trim_silence(0.0, Duration::ZERO).unwrap().num_frames(),
+        0
+    );
+
+    let shared = wav.clone().into_shared();
+    let trimmed = shared.trim_silence(-50.0, Duration::ZERO).unwrap();
+    assert_eq!(trimmed.channel(0).unwrap(), [10000, 0, 10000]);
+    // nothing to trim shares the channels
+    let untrimmed = shared.trim_silence(-50.0, Duration::from_secs(1)).unwrap();
+    assert!(untrimmed.is_shared(0));
+}
+
+#[test]
+fn split_on_silence() {
+    use std::time::Duration;
+
+    let wav = pattern_wav(&[0, 1, 1, 0, 1, 0, 0, 1, 0]);
+
+    // gaps shorter than the minimum join the sounds on either side
+    let ranges = wav.sound_ranges(-50.0, Duration::from_millis(200)).unwrap();
+    assert_eq!(ranges, [1..5, 7..8]);
+    let parts = wav
+        .split_on_silence(-50.0, Duration::from_millis(200))
+        .unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].audiodata, [vec![10000, 10000, 0, 10000]]);
+    assert_eq!(parts[1].audiodata, [vec![10000]]);
+
+    assert_eq!(
+        wav.sound_ranges(-50.0, Duration::ZERO).unwrap(),
+        [1..3, 4..5, 7..8]
+    );
+    assert!(pattern_wav(&[0, 0])
+        .split_on_silence(-50.0, Duration::ZERO)
+        .unwrap()
+        .is_empty());
+
+    let shared = wav.clone().into_shared();
+    let parts = shared
+        .split_on_silence(-50.0, Duration::from_millis(200))
+        .unwrap();
+    assert_eq!(parts[0].channel(0).unwrap(), [10000, 10000, 0, 10000]);
+    assert_eq!(shared.sound_ranges(-50.0, Duration::ZERO).unwrap().len(), 3);
+}
+
+#[test]
+fn stats() {
+    let mut wav = pattern_wav(&[0, 1, 0, 1]);
+    wav.audiodata.push(vec![]);
+    wav.audiodata[0][2] = -5000;
+
+    let stats = wav.stats();
+    assert_eq!(stats[0].min, -5000);
+    assert_eq!(stats[0].max, 10000);
+    assert!((stats[0].peak - 10000.0 / 32768.0).abs() < 1e-9);
+    assert!((stats[0].dc_offset - 15000.0 / 4.0 / 32768.0).abs() < 1e-9);
+    let rms = (225_000_000f64 / 4.0).sqrt() / 32768.0;
+    assert!((stats[0].rms - rms).abs() < 1e-9);
+    // an empty channel measures silence
+    assert_eq!(stats[1].min, 0);
+    assert_eq!(stats[1].peak, 0.0);
+    assert_eq!(stats[1].peak_dbfs(), f64::NEG_INFINITY);
+
+    let sine = sine_wav(48000, 1, 1.0, -6.0).stats();
+    assert!((sine[0].peak_dbfs() + 6.0).abs() < 0.01);
+    assert!((sine[0].rms_dbfs() + 9.01).abs() < 0.05);
+    assert!(sine[0].dc_offset.abs() < 1e-3);
+}
+
+#[test]
+fn detect_clipping() {
+    let mut wav = pattern_wav(&[0; 10]);
+    wav.audiodata[0][1..4].fill(i16::MAX);
+    wav.audiodata[0][5] = i16::MIN;
+    wav.audiodata[0][8..].fill(i16::MIN);
+    wav.audiodata.push(vec![i16::MAX; 2]);
+
+    let regions = wav.detect_clipping(2);
+    assert_eq!(
+        regions,
+        [
+            ClipRegion {
+                channel: 0,
+                start: 1,
+                end: 4
+            },
+            ClipRegion {
+                channel: 0,
+                start: 8,
+                end: 10
+            },
+            ClipRegion {
+                channel: 1,
+                start: 0,
+                end: 2
+            },
+        ]
+    );
+    assert_eq!(regions[0].len(), 3);
+    assert_eq!(wav.detect_clipping(0).len(), 4);
+    assert!(wav.detect_clipping(4).is_empty());
+
+    let mut float = sine_wav(48000, 1, 0.01, 0.0);
+    float.audiodata[0][..3].fill(1.5);
+    assert_eq!(float.detect_clipping(3)[0].end, 3);
+}
+
+#[test]
+fn remove_dc_offset() {
+    let mut wav = pattern_wav(&[1, 0, 1, 0]);
+    wav.audiodata.push(vec![]);
+    wav.remove_dc_offset();
+    assert_eq!(wav.audiodata, [vec![5000, -5000, 5000, -5000], vec![]]);
+    assert!(wav.stats()[0].dc_offset.abs() < 1e-9);
+
+    let shared = pattern_wav(&[1, 1]).into_shared();
+    let mut centered = shared.clone();
+    centered.remove_dc_offset();
+    assert_eq!(centered.channel(0).unwrap(), [0, 0]);
+    assert_eq!(shared.channel(0).unwrap(), [10000, 10000]);
+}
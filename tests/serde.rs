@@ -0,0 +1,54 @@
+#![cfg(feature = "serde")]
+
+use onda::*;
+
+/// A stereo file with a bext chunk, whose UMID has a custom serde representation.
+fn wav() -> WavData {
+    let metadata = WavMetadata {
+        bext: Some(BextChunk {
+            description: "Serialized".to_string(),
+            umid: [7; 64],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(
+        vec![vec![1, -2, 300], vec![0, 32767, -32768]],
+        48000,
+        &metadata,
+    )
+    .unwrap();
+    parse_bytes(buf).unwrap()
+}
+
+#[test]
+fn serde_roundtrip() {
+    let wav = wav();
+    let json = serde_json::to_string(&wav).unwrap();
+    assert!(json.contains("[1,-2,300]"));
+
+    let decoded: WavData = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.samplerate, 48000);
+    assert_eq!(decoded.audiodata, wav.audiodata);
+    assert_eq!(decoded.metadata, wav.metadata);
+}
+
+#[test]
+fn serde_payloads() {
+    let wav = wav();
+
+    let json = serde_json::to_string(&wav.with_payload(SamplePayload::Base64)).unwrap();
+    // the little-endian bytes of 1, -2 and 300
+    assert!(json.contains("\"AQD+/ywB\""));
+    let decoded: WavData = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.audiodata, wav.audiodata);
+
+    let json = serde_json::to_string(&wav.with_payload(SamplePayload::Skip)).unwrap();
+    assert!(!json.contains("audiodata"));
+    let decoded: WavData = serde_json::from_str(&json).unwrap();
+    assert!(decoded.audiodata.is_empty());
+    assert_eq!(decoded.metadata, wav.metadata);
+
+    let json = json.replacen("\"metadata\"", "\"audiodata\":[\"AQD+\"],\"metadata\"", 1);
+    assert!(serde_json::from_str::<WavData>(&json).is_err());
+}
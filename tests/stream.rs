@@ -0,0 +1,382 @@
+use onda::*;
+use std::fs;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+/// Two channels of 1000 frames of ramps.
+fn stereo_i16() -> Vec<Vec<i16>> {
+    vec![
+        (0..1000).map(|i| i * 30).collect(),
+        (0..1000).map(|i| -i * 30).collect(),
+    ]
+}
+
+#[test]
+fn wav_reader() {
+    let audiodata = stereo_i16();
+    let metadata = WavMetadata {
+        bext: Some(BextChunk {
+            description: "Streamed".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(&audiodata, 44100, &metadata).unwrap();
+
+    // a slice is read without seeking, like stdin
+    let mut reader = WavReader::new(&buf[..]).unwrap();
+    assert_eq!(reader.num_channels(), 2);
+    assert_eq!(reader.samplerate(), 44100);
+    assert_eq!(reader.bits_per_sample(), 16);
+    assert_eq!(
+        reader.metadata().bext.as_ref().unwrap().description,
+        "Streamed"
+    );
+
+    let mut channels = vec![vec![]; 2];
+    loop {
+        let frames = reader.read_frames(64).unwrap();
+        if frames[0].is_empty() {
+            break;
+        }
+        assert!(frames[0].len() <= 64);
+        for (channel, read) in channels.iter_mut().zip(frames) {
+            channel.extend(read);
+        }
+    }
+    assert_eq!(channels, audiodata);
+}
+
+#[test]
+fn wav_reader_sample_types() {
+    let buf = create_bytes_with_depth(vec![vec![-8388608, 8388607]], 48000, BitDepth::B24).unwrap();
+    let mut reader = WavReader::new(&buf[..]).unwrap();
+    assert_eq!(reader.bits_per_sample(), 24);
+    assert!(reader.read_frames(1).is_err());
+    let mut reader = WavReader::new(&buf[..]).unwrap();
+    assert_eq!(
+        reader.read_frames_i32(10).unwrap(),
+        [vec![-8388608, 8388607]]
+    );
+
+    let buf = create_bytes_f32(vec![vec![0.5, -0.5]], 48000).unwrap();
+    let mut reader = WavReader::new(&buf[..]).unwrap();
+    assert_eq!(reader.read_frames_f64(10).unwrap(), [vec![0.5, -0.5]]);
+}
+
+#[test]
+fn wav_reader_truncated() {
+    let buf = create_bytes(stereo_i16(), 44100).unwrap();
+
+    // a stream that ends early ends the data
+    let mut reader = WavReader::new(&buf[..buf.len() - 400]).unwrap();
+    assert_eq!(reader.read_frames(2000).unwrap()[0].len(), 900);
+    assert!(reader.read_frames(10).unwrap()[0].is_empty());
+
+    // the fmt chunk must come before the data chunk
+    assert!(WavReader::new(&buf[..30]).is_err());
+}
+
+#[test]
+fn wav_reader_seek() {
+    let audiodata = stereo_i16();
+    let buf = create_bytes(&audiodata, 1000).unwrap();
+    let mut reader = WavReader::new(Cursor::new(buf)).unwrap();
+    assert_eq!(reader.num_frames(), 1000);
+
+    reader.seek_to_frame(990).unwrap();
+    assert_eq!(reader.read_frames(100).unwrap()[0], audiodata[0][990..]);
+
+    // seeking back works after reading
+    reader.seek_to_time(Duration::from_millis(500)).unwrap();
+    assert_eq!(reader.read_frames(2).unwrap()[1], [-15000, -15030]);
+
+    reader.seek_to_frame(5000).unwrap();
+    assert!(reader.read_frames(10).unwrap()[0].is_empty());
+}
+
+#[test]
+fn wav_reader_seek_adpcm() {
+    // IMA ADPCM blocks of 9 frames: a header with the first sample, then 8 codes
+    let block = [0xE8, 0x03, 0, 0, 0x44, 0x57, 0xC8, 0x01];
+    let decoded = [1000, 1007, 1017, 1036, 1066, 1062, 1028, 1041, 1045];
+    let mut buf = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    for field in [0x11u16, 1] {
+        buf.extend_from_slice(&field.to_le_bytes());
+    }
+    buf.extend_from_slice(&8000u32.to_le_bytes());
+    buf.extend_from_slice(&8000u32.to_le_bytes());
+    for field in [8u16, 4] {
+        buf.extend_from_slice(&field.to_le_bytes());
+    }
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&24u32.to_le_bytes());
+    buf.extend_from_slice(&block.repeat(3));
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let mut reader = WavReader::new(Cursor::new(buf)).unwrap();
+    assert_eq!(reader.num_frames(), 27);
+
+    // the frame is in the middle of the second block
+    reader.seek_to_frame(13).unwrap();
+    let frames = reader.read_frames(7).unwrap();
+    assert_eq!(frames[0][..5], decoded[4..]);
+    assert_eq!(frames[0][5..7], decoded[..2]);
+
+    reader.seek_to_frame(26).unwrap();
+    assert_eq!(reader.read_frames(10).unwrap(), [vec![1045]]);
+}
+
+#[test]
+fn read_range_frames() {
+    let audiodata = stereo_i16();
+    let path = std::env::temp_dir().join(format!("onda-{}-range.wav", std::process::id()));
+    write(&audiodata, 44100, &path).unwrap();
+
+    let wav = read_range(&path, 100, 50).unwrap();
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.audiodata[0], audiodata[0][100..150]);
+    assert_eq!(wav.audiodata[1], audiodata[1][100..150]);
+
+    // integer samples are normalized
+    assert_eq!(
+        read_range_f64(&path, 1, 1).unwrap().audiodata[0],
+        [30.0 / 32768.0]
+    );
+    assert_eq!(
+        read_range_i32(&path, 980, 50).unwrap().audiodata[0].len(),
+        20
+    );
+    assert!(read_range(&path, 2000, 50).unwrap().audiodata[0].is_empty());
+
+    // lenient parsing applies to ranges too
+    let mut buf = fs::read(&path).unwrap();
+    buf[32..34].copy_from_slice(&3u16.to_le_bytes());
+    fs::write(&path, &buf).unwrap();
+    assert!(read_range(&path, 0, 10).is_err());
+    let wav = read_range_with_options(&path, 10, 2, ParseOptions { strict: false }).unwrap();
+    assert_eq!(wav.audiodata, [vec![300, 330], vec![-300, -330]]);
+    assert_eq!(wav.warnings.len(), 1);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_wav() {
+    use std::borrow::Cow;
+
+    let audiodata = stereo_i16();
+    let path = std::env::temp_dir().join(format!("onda-{}-mapped.wav", std::process::id()));
+    write(&audiodata, 44100, &path).unwrap();
+
+    let mapped = MappedWav::open(&path).unwrap();
+    assert_eq!(mapped.num_channels(), 2);
+    assert_eq!(mapped.bits_per_sample(), 16);
+    let samples = mapped.samples().unwrap();
+    assert!(matches!(samples, Cow::Borrowed(_)));
+    assert_eq!(samples[..4], [0, 0, 30, -30]);
+    drop(mapped);
+
+    // 8-bit samples are decoded
+    write_with_depth(vec![vec![-128, 127]], 8000, BitDepth::B8, &path).unwrap();
+    let mapped = MappedWav::open(&path).unwrap();
+    let samples = mapped.samples().unwrap();
+    assert!(matches!(samples, Cow::Owned(_)));
+    assert_eq!(samples[..], [-128, 127]);
+    drop(mapped);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn wav_writer() {
+    let audiodata = stereo_i16();
+    let frames: Vec<[i16; 2]> = (0..audiodata[0].len())
+        .map(|f| [audiodata[0][f], audiodata[1][f]])
+        .collect();
+    let metadata = WavMetadata {
+        title: Some("Recording".to_string()),
+        bext: Some(BextChunk {
+            description: "Live".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let mut writer =
+        WavWriter::with_metadata(Cursor::new(vec![]), 2, 44100, metadata.clone()).unwrap();
+    writer.write_frames(&frames[..100]).unwrap();
+    writer.write_frames(&frames[100..]).unwrap();
+    assert!(writer.write_frames(&[[0i16; 3]]).is_err());
+    let buf = writer.finalize().unwrap().into_inner();
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.audiodata, audiodata);
+    assert_eq!(wav.metadata.title, metadata.title);
+    assert_eq!(wav.metadata.bext.unwrap().description, "Live");
+}
+
+#[test]
+fn wav_writer_spec() {
+    let spec = WavSpec {
+        num_channels: 1,
+        samplerate: 48000,
+        bits_per_sample: 24,
+        float: false,
+    };
+    let mut writer =
+        WavWriter::with_spec(Cursor::new(vec![]), spec, WavMetadata::default()).unwrap();
+    writer.write_samples(&[-8388608i32, 0, 8388607]).unwrap();
+    assert!(writer.write_samples(&[8388608i32]).is_err());
+    assert!(writer.write_samples(&[0.5f32]).is_err());
+    let buf = writer.finalize().unwrap().into_inner();
+
+    // odd-sized audio data is padded before the chunks that follow
+    assert_eq!(buf.len() % 2, 0);
+    let wav: WavData<i32> = parse_bytes_as(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 24);
+    assert_eq!(wav.audiodata, [vec![-8388608, 0, 8388607]]);
+
+    let spec = WavSpec {
+        num_channels: 2,
+        bits_per_sample: 32,
+        float: true,
+        ..spec
+    };
+    let path = std::env::temp_dir().join(format!("onda-{}-from-frames.wav", std::process::id()));
+    let frames = (0..4).map(|i| [i as f32 / 4.0, -(i as f32) / 4.0]);
+    write_from_frames(frames, spec, &path).unwrap();
+    let wav = read_f32(&path).unwrap();
+    assert_eq!(
+        wav.audiodata,
+        [vec![0.0, 0.25, 0.5, 0.75], vec![0.0, -0.25, -0.5, -0.75]]
+    );
+    assert!(contains_fact(&fs::read(&path).unwrap(), 4));
+
+    let mismatched = [vec![0.0f32; 3]];
+    assert!(matches!(
+        write_from_frames(mismatched, spec, &path),
+        Err(OndaError::ChannelMismatch {
+            expected: 2,
+            found: 3
+        })
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+/// Whether a WAV file has a fact chunk holding `num_frames`.
+fn contains_fact(buf: &[u8], num_frames: u32) -> bool {
+    let mut fact = b"fact\x04\0\0\0".to_vec();
+    fact.extend_from_slice(&num_frames.to_le_bytes());
+    buf.windows(fact.len()).any(|w| w == fact)
+}
+
+/// A writer that keeps only the first bytes written to it, to write large files without
+/// holding them in memory.
+struct HeaderSink {
+    header: Vec<u8>,
+    position: u64,
+    len: u64,
+}
+
+impl Write for HeaderSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (position, &byte) in (self.position..).zip(buf) {
+            match self.header.get_mut(position as usize) {
+                Some(header_byte) => *header_byte = byte,
+                None => break,
+            }
+        }
+        self.position += buf.len() as u64;
+        self.len = self.len.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for HeaderSink {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(position) => position,
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset).unwrap(),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset).unwrap(),
+        };
+        Ok(self.position)
+    }
+}
+
+#[test]
+#[ignore = "writes 4 GiB of audio"]
+fn wav_writer_rf64() {
+    let sink = HeaderSink {
+        header: vec![0; 80],
+        position: 0,
+        len: 0,
+    };
+    let mut writer = WavWriter::new(sink, 2, 48000).unwrap();
+
+    // just over the 4 GiB limit of RIFF sizes
+    let num_frames = (1u64 << 30) + 1;
+    let block = vec![[1i16, -1]; 1 << 16];
+    let mut written = 0;
+    while written < num_frames {
+        let len = (num_frames - written).min(block.len() as u64) as usize;
+        writer.write_frames(&block[..len]).unwrap();
+        written += len as u64;
+    }
+    let sink = writer.finalize().unwrap();
+
+    let header = &sink.header;
+    let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+    assert_eq!(&header[..4], b"RF64");
+    assert_eq!(u32_at(4), u32::MAX);
+    assert_eq!(&header[12..16], b"ds64");
+    assert_eq!(u64_at(20), sink.len - 8);
+    assert_eq!(u64_at(28), num_frames * 4);
+    assert_eq!(u64_at(36), num_frames);
+    // the data chunk follows the fmt chunk
+    assert_eq!(&header[72..76], b"data");
+    assert_eq!(u32_at(76), u32::MAX);
+}
+
+#[test]
+fn parse_reader_matches_parse_bytes() {
+    let metadata = WavMetadata {
+        title: Some("After the data".to_string()),
+        ..Default::default()
+    };
+    // an odd number of 8-bit samples leaves a padding byte before the metadata
+    let buf = create_bytes_with_depth(vec![vec![-128, 0, 127]], 8000, BitDepth::B8).unwrap();
+    let wav = parse_reader(Cursor::new(&buf)).unwrap();
+    assert_eq!(wav.audiodata, [vec![-128, 0, 127]]);
+    assert_eq!(wav.bits_per_sample, 8);
+
+    let audiodata = stereo_i16();
+    let buf = create_bytes_with_metadata(&audiodata, 44100, &metadata).unwrap();
+    let wav = parse_reader(Cursor::new(&buf)).unwrap();
+    assert_eq!(wav.audiodata, audiodata);
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.metadata, parse_bytes(&buf).unwrap().metadata);
+    assert_eq!(wav.metadata.title, metadata.title);
+
+    // other containers are read whole
+    let buf = create_aiff_bytes(&audiodata, 44100).unwrap();
+    assert_eq!(
+        parse_reader(Cursor::new(&buf)).unwrap().audiodata,
+        audiodata
+    );
+
+    let buf = create_bytes_f64(vec![vec![0.25, -1.0]], 48000).unwrap();
+    assert_eq!(
+        parse_reader_f64(Cursor::new(&buf)).unwrap().audiodata,
+        [vec![0.25, -1.0]]
+    );
+}
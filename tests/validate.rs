@@ -0,0 +1,107 @@
+use onda::*;
+
+/// A 16-bit stereo WAV file of three frames.
+fn wav() -> Vec<u8> {
+    create_bytes(vec![vec![1, 2, 3], vec![-1, -2, -3]], 8000).unwrap()
+}
+
+/// Messages of the issues of a report with the given severity.
+fn messages(report: &ValidationReport, severity: Severity) -> Vec<&str> {
+    report
+        .issues
+        .iter()
+        .filter(|issue| issue.severity == severity)
+        .map(|issue| issue.message.as_str())
+        .collect()
+}
+
+#[test]
+fn valid_files() {
+    assert_eq!(validate(wav()), ValidationReport::default());
+
+    let metadata = WavMetadata {
+        title: Some("Odd".to_string()),
+        ..Default::default()
+    };
+    let buf = create_bytes_with_metadata(vec![vec![0; 3]], 8000, &metadata).unwrap();
+    assert!(validate(&buf).issues.is_empty());
+
+    let buf = create_bytes_with_depth(vec![vec![0; 3]; 6], 48000, BitDepth::B24).unwrap();
+    assert!(validate(&buf).issues.is_empty());
+}
+
+#[test]
+fn fmt_mismatches_are_warnings() {
+    let mut buf = wav();
+    buf[28..32].copy_from_slice(&1000u32.to_le_bytes());
+    buf[32..34].copy_from_slice(&3u16.to_le_bytes());
+
+    let report = validate(&buf);
+    assert!(report.is_valid());
+    assert_eq!(
+        messages(&report, Severity::Warning),
+        [
+            "byte rate is 1000 instead of 32000",
+            "block align is 3 instead of 4"
+        ]
+    );
+    assert_eq!(report.issues[0].offset, 28);
+    assert_eq!(
+        report.issues[1].to_string(),
+        "warning at offset 32: block align is 3 instead of 4"
+    );
+}
+
+#[test]
+fn chunk_errors() {
+    // the data chunk claims more bytes than the file holds
+    let mut buf = wav();
+    buf[40..44].copy_from_slice(&100u32.to_le_bytes());
+    let report = validate(&buf);
+    assert!(!report.is_valid());
+    assert_eq!(
+        messages(&report, Severity::Error),
+        ["data chunk missing", "data chunk size exceeds RIFF size"]
+    );
+
+    // a chunk of odd size without its padding byte, and trailing garbage
+    let mut buf = wav();
+    buf.extend_from_slice(b"abcd\x01\x00\x00\x00\x07");
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf.extend_from_slice(&[0xFF; 3]);
+    let report = validate(&buf);
+    assert!(report.is_valid());
+    assert_eq!(
+        messages(&report, Severity::Warning),
+        [
+            "trailing bytes after RIFF chunk",
+            "padding byte of abcd chunk outside RIFF chunk"
+        ]
+    );
+
+    assert_eq!(
+        messages(&validate(&buf[..10]), Severity::Error),
+        ["file too small for a RIFF header"]
+    );
+    assert_eq!(
+        messages(&validate(&buf[..36]), Severity::Error),
+        ["RIFF size exceeds file size", "data chunk missing"]
+    );
+}
+
+#[test]
+fn fact_chunk() {
+    let mut buf = wav();
+    buf.extend_from_slice(b"fact\x04\x00\x00\x00");
+    buf.extend_from_slice(&5u32.to_le_bytes());
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let report = validate(&buf);
+    assert_eq!(
+        messages(&report, Severity::Error),
+        ["fact chunk has 5 frames, but data chunk holds 3"]
+    );
+    assert_eq!(report.issues[0].offset, 56);
+}
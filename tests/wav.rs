@@ -0,0 +1,752 @@
+use onda::*;
+
+/// Builds a WAV file of a fmt chunk without extension and a data chunk holding `data` as
+/// is.
+fn wav_bytes(
+    format_tag: u16,
+    num_channels: u16,
+    samplerate: u32,
+    bits_per_sample: u16,
+    data: &[u8],
+) -> Vec<u8> {
+    let block_align = num_channels * bits_per_sample.div_ceil(8);
+
+    let mut buf = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&format_tag.to_le_bytes());
+    buf.extend_from_slice(&num_channels.to_le_bytes());
+    buf.extend_from_slice(&samplerate.to_le_bytes());
+    buf.extend_from_slice(&(samplerate * block_align as u32).to_le_bytes());
+    buf.extend_from_slice(&block_align.to_le_bytes());
+    buf.extend_from_slice(&bits_per_sample.to_le_bytes());
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf
+}
+
+#[test]
+fn read_24_bit() {
+    let data = [
+        0xFF, 0xFF, 0x7F, 0x00, 0x00, 0x80, // frame 0
+        0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF, // frame 1
+    ];
+    let wav = parse_bytes_i32(wav_bytes(1, 2, 48000, 24, &data)).unwrap();
+
+    assert_eq!(wav.num_channels, 2);
+    assert_eq!(wav.samplerate, 48000);
+    assert_eq!(wav.bits_per_sample, 24);
+    assert_eq!(wav.audiodata, [vec![8388607, 1], vec![-8388608, -1]]);
+
+    assert!(parse_bytes(wav_bytes(1, 2, 48000, 24, &data)).is_err());
+}
+
+#[test]
+fn read_16_bit_into_i32() {
+    let data = [0xFF, 0x7F, 0x00, 0x80, 0x01, 0x00];
+    let wav = parse_bytes_i32(wav_bytes(1, 1, 8000, 16, &data)).unwrap();
+
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(wav.audiodata, [vec![32767, -32768, 1]]);
+}
+
+#[test]
+fn write_24_bit() {
+    let audiodata = vec![vec![8388607, 1, 0], vec![-8388608, -1, 256]];
+    let buf = create_bytes_with_depth(&audiodata, 48000, BitDepth::B24).unwrap();
+
+    let data = [
+        0xFF, 0xFF, 0x7F, 0x00, 0x00, 0x80, // frame 0
+        0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF, // frame 1
+        0x00, 0x00, 0x00, 0x00, 0x01, 0x00, // frame 2
+    ];
+    // more than 16 bits call for an extensible fmt chunk
+    let expected = extensible(wav_bytes(1, 2, 48000, 24, &data), 24, 0x3, 1);
+    assert_eq!(buf, expected);
+    assert_eq!(parse_bytes_i32(&buf).unwrap().audiodata, audiodata);
+
+    let out_of_range = vec![vec![8388608]];
+    assert!(create_bytes_with_depth(out_of_range, 48000, BitDepth::B24).is_err());
+}
+
+#[test]
+fn write_24_bit_file() {
+    let path = std::env::temp_dir().join(format!("onda-{}-24-bit.wav", std::process::id()));
+    let audiodata = vec![vec![-8388608, 0, 8388607]];
+    write_with_depth(&audiodata, 44100, BitDepth::B24, &path).unwrap();
+
+    let wav = read_i32(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(wav.bits_per_sample, 24);
+    assert_eq!(wav.audiodata, audiodata);
+}
+
+#[test]
+fn roundtrip_32_bit() {
+    let audiodata = vec![vec![i32::MIN, -1, 0, 1, i32::MAX]];
+    let buf = create_bytes_with_depth(&audiodata, 96000, BitDepth::B32).unwrap();
+
+    let wav = parse_bytes_i32(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 32);
+    assert_eq!(wav.audiodata, audiodata);
+}
+
+#[test]
+fn roundtrip_8_bit() {
+    // 8-bit samples are stored as offset binary, with silence at 128
+    let wav = parse_bytes(wav_bytes(1, 1, 8000, 8, &[0x00, 0x80, 0xFF])).unwrap();
+    assert_eq!(wav.audiodata, [vec![-128, 0, 127]]);
+
+    let audiodata = vec![vec![-128, 0, 127], vec![1, -1, 64]];
+    let buf = create_bytes_with_depth(&audiodata, 8000, BitDepth::B8).unwrap();
+    assert_eq!(buf, wav_bytes(1, 2, 8000, 8, &[0, 129, 128, 127, 255, 192]));
+    assert_eq!(parse_bytes_i32(&buf).unwrap().audiodata, audiodata);
+}
+
+#[test]
+fn read_float32() {
+    let samples = [0.5f32, -1.0, 0.25, 1.0];
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    let buf = wav_bytes(3, 2, 44100, 32, &data);
+
+    let wav = parse_bytes_f32(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 32);
+    assert_eq!(wav.audiodata, [vec![0.5, 0.25], vec![-1.0, 1.0]]);
+
+    assert!(parse_bytes(&buf).is_err());
+    assert!(parse_bytes_i32(&buf).is_err());
+    let wav = parse_bytes_f32(wav_bytes(1, 1, 8000, 16, &[0, 0x40])).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5]]);
+}
+
+#[test]
+fn read_fmt_extension() {
+    // an 18-byte fmt chunk, as many encoders write for float files, ends with an empty
+    // extension
+    let data: Vec<u8> = [0.5f32, -0.5]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let mut buf = wav_bytes(3, 1, 44100, 32, &data);
+    buf[16..20].copy_from_slice(&18u32.to_le_bytes());
+    buf.splice(36..36, [0, 0]);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    assert_eq!(parse_bytes_f32(&buf).unwrap().audiodata, [vec![0.5, -0.5]]);
+}
+
+/// Returns the body of the first top-level chunk with the given id in a RIFF file.
+fn find_chunk<'a>(buf: &'a [u8], id: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut pos = 12;
+    while pos + 8 <= buf.len() {
+        let size = u32::from_le_bytes(buf[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        if &buf[pos..pos + 4] == id {
+            return Some(&buf[pos + 8..pos + 8 + size]);
+        }
+        pos += 8 + size + size % 2;
+    }
+    None
+}
+
+#[test]
+fn write_float32() {
+    let audiodata = vec![vec![0.5, -1.0, 0.0], vec![1.0, -0.25, 0.125]];
+    let buf = create_bytes_f32(&audiodata, 48000).unwrap();
+
+    let fmt = find_chunk(&buf, b"fmt ").unwrap();
+    assert_eq!(fmt.len(), 18);
+    assert_eq!(fmt[0..2], 3u16.to_le_bytes());
+    assert_eq!(find_chunk(&buf, b"fact").unwrap(), 3u32.to_le_bytes());
+    assert_eq!(find_chunk(&buf, b"data").unwrap().len(), 24);
+    assert_eq!(buf[4..8], (buf.len() as u32 - 8).to_le_bytes());
+    assert_eq!(parse_bytes_f32(&buf).unwrap().audiodata, audiodata);
+
+    // PCM files keep the plain 16-byte fmt chunk and no fact chunk
+    let pcm = create_bytes(vec![vec![1, 2]], 48000).unwrap();
+    assert_eq!(find_chunk(&pcm, b"fmt ").unwrap().len(), 16);
+    assert!(find_chunk(&pcm, b"fact").is_none());
+}
+
+#[test]
+fn write_float32_file() {
+    let path = std::env::temp_dir().join(format!("onda-{}-float32.wav", std::process::id()));
+    let audiodata = vec![vec![0.75, -0.5]];
+    write_f32(&audiodata, 22050, &path).unwrap();
+
+    let wav = read_f32(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(wav.samplerate, 22050);
+    assert_eq!(wav.audiodata, audiodata);
+}
+
+#[test]
+fn roundtrip_float64() {
+    let audiodata = vec![vec![0.1, -1.0, 1.0 / 3.0]];
+    let buf = create_bytes_f64(&audiodata, 44100).unwrap();
+    assert_eq!(find_chunk(&buf, b"fact").unwrap(), 3u32.to_le_bytes());
+
+    let wav = parse_bytes_f64(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 64);
+    assert_eq!(wav.audiodata, audiodata);
+    let wav = parse_bytes_f32(&buf).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.1f32, -1.0, 1.0 / 3.0]]);
+
+    // 32-bit float files widen losslessly into f64
+    let buf = create_bytes_f32(vec![vec![0.1f32, -0.5]], 44100).unwrap();
+    let wav = parse_bytes_f64(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 32);
+    assert_eq!(wav.audiodata, [vec![0.1f32 as f64, -0.5]]);
+}
+
+#[test]
+fn write_float64_file() {
+    let path = std::env::temp_dir().join(format!("onda-{}-float64.wav", std::process::id()));
+    let audiodata = vec![vec![0.25, -0.75], vec![1e-9, 0.0]];
+    write_f64(&audiodata, 96000, &path).unwrap();
+
+    let wav = read_f64(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(wav.audiodata, audiodata);
+}
+
+/// Turns the plain fmt chunk of a `wav_bytes` file into an extensible one.
+fn extensible(mut buf: Vec<u8>, valid_bits: u16, channel_mask: u32, subformat: u16) -> Vec<u8> {
+    let mut ext = 22u16.to_le_bytes().to_vec();
+    ext.extend_from_slice(&valid_bits.to_le_bytes());
+    ext.extend_from_slice(&channel_mask.to_le_bytes());
+    ext.extend_from_slice(&subformat.to_le_bytes());
+    ext.extend_from_slice(&[
+        0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+    ]);
+
+    buf[16..20].copy_from_slice(&40u32.to_le_bytes());
+    buf[20..22].copy_from_slice(&0xFFFEu16.to_le_bytes());
+    buf.splice(36..36, ext);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    buf
+}
+
+#[test]
+fn read_extensible() {
+    let data = [0x00, 0x10, 0x00, 0x00, 0xF0, 0xFF];
+    let buf = extensible(wav_bytes(0, 2, 48000, 24, &data), 20, 0x3, 1);
+
+    let wav = parse_bytes_i32(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 24);
+    assert_eq!(wav.valid_bits_per_sample, 20);
+    assert_eq!(wav.channel_mask, 0x3);
+    assert_eq!(wav.audiodata, [vec![4096], vec![-4096]]);
+
+    let data: Vec<u8> = [0.5f32, -0.5]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let buf = extensible(wav_bytes(0, 1, 48000, 32, &data), 32, 0x4, 3);
+    assert_eq!(parse_bytes_f32(&buf).unwrap().audiodata, [vec![0.5, -0.5]]);
+
+    // plain fmt chunks report the container size and no speaker mapping
+    let wav = parse_bytes(wav_bytes(1, 1, 8000, 16, &[1, 0])).unwrap();
+    assert_eq!(wav.valid_bits_per_sample, 16);
+    assert_eq!(wav.channel_mask, 0);
+}
+
+#[test]
+fn read_bad_extensible() {
+    let data = [0, 0, 0, 0, 0, 0];
+    // unknown subformat
+    assert!(parse_bytes_i32(extensible(wav_bytes(0, 2, 48000, 24, &data), 24, 0x3, 2)).is_err());
+    // more valid bits than the container holds
+    assert!(parse_bytes_i32(extensible(wav_bytes(0, 2, 48000, 24, &data), 32, 0x3, 1)).is_err());
+    // extension too short
+    let mut buf = wav_bytes(0xFFFE, 1, 48000, 16, &data);
+    buf[16..20].copy_from_slice(&18u32.to_le_bytes());
+    buf.splice(36..36, [0, 0]);
+    assert!(parse_bytes(&buf).is_err());
+}
+
+#[test]
+fn write_extensible() {
+    // 16-bit stereo keeps the plain fmt chunk
+    let buf = create_bytes(vec![vec![1], vec![2]], 44100).unwrap();
+    assert_eq!(find_chunk(&buf, b"fmt ").unwrap().len(), 16);
+
+    let audiodata: Vec<Vec<i16>> = (0..6).map(|ch| vec![ch, -ch]).collect();
+    let buf = create_bytes(&audiodata, 48000).unwrap();
+    let fmt = find_chunk(&buf, b"fmt ").unwrap();
+    assert_eq!(fmt.len(), 40);
+    assert_eq!(fmt[0..2], 0xFFFEu16.to_le_bytes());
+    // 5.1 speaker layout and the PCM subformat
+    assert_eq!(fmt[20..24], 0x3Fu32.to_le_bytes());
+    assert_eq!(fmt[24..26], 1u16.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.channel_mask, 0x3F);
+    assert_eq!(wav.audiodata, audiodata);
+
+    // float files only need it for more than 2 channels
+    let buf = create_bytes_f32(vec![vec![0.5]; 3], 48000).unwrap();
+    let fmt = find_chunk(&buf, b"fmt ").unwrap();
+    assert_eq!(fmt[24..26], 3u16.to_le_bytes());
+    assert_eq!(parse_bytes_f32(&buf).unwrap().channel_mask, 0x7);
+}
+
+#[test]
+fn read_multichannel() {
+    // channels come back in the order they are interleaved in each frame
+    let data: Vec<u8> = (0..16i16).flat_map(|s| s.to_le_bytes()).collect();
+    let wav = parse_bytes(wav_bytes(1, 8, 48000, 16, &data)).unwrap();
+
+    assert_eq!(wav.num_channels, 8);
+    let expected: Vec<Vec<i16>> = (0..8).map(|ch| vec![ch, ch + 8]).collect();
+    assert_eq!(wav.audiodata, expected);
+}
+
+#[test]
+fn write_channel_layout() {
+    assert!(create_bytes(Vec::<Vec<i16>>::new(), 44100).is_err());
+    assert!(create_bytes(vec![vec![1, 2], vec![3]], 44100).is_err());
+
+    let audiodata = vec![vec![1, 2]; 300];
+    let wav = parse_bytes(create_bytes(&audiodata, 44100).unwrap()).unwrap();
+    assert_eq!(wav.num_channels, 300);
+    assert_eq!(wav.audiodata, audiodata);
+
+    // frames wider than the 16-bit block align field
+    assert!(create_bytes_with_depth(vec![vec![0]; 20000], 44100, BitDepth::B32).is_err());
+    assert!(create_bytes_with_depth(vec![vec![0]; 4096], u32::MAX, BitDepth::B32).is_err());
+}
+
+#[test]
+fn read_alaw() {
+    // G.711 reference points: the smallest and largest codes of each sign
+    let buf = wav_bytes(6, 2, 8000, 8, &[0xD5, 0x55, 0xAA, 0x2A]);
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(wav.audiodata, [vec![8, 32256], vec![-8, -32256]]);
+    assert_eq!(parse_bytes_i32(&buf).unwrap().audiodata[1], [-8, -32256]);
+    let wav = parse_bytes_f32(&buf).unwrap();
+    assert_eq!(wav.audiodata[1], [-8.0 / 32768.0, -32256.0 / 32768.0]);
+
+    // A-law samples are always 8 bits wide
+    assert!(parse_bytes(wav_bytes(6, 1, 8000, 16, &[0, 0])).is_err());
+}
+
+#[test]
+fn roundtrip_mulaw() {
+    // G.711 reference points: both zeros and the largest codes of each sign
+    let wav = parse_bytes(wav_bytes(7, 1, 8000, 8, &[0xFF, 0x7F, 0x80, 0x00])).unwrap();
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(wav.audiodata, [vec![0, 0, 32124, -32124]]);
+
+    let audiodata = vec![
+        vec![0, 100, -100, 1000, -1000],
+        vec![10000, -10000, 32767, -32768, 5],
+    ];
+    let buf = create_bytes_mulaw(&audiodata, 8000).unwrap();
+    assert_eq!(find_chunk(&buf, b"data").unwrap()[0], 0xFF);
+    assert_eq!(find_chunk(&buf, b"fact").unwrap(), 5u32.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    for (decoded, original) in wav.audiodata.iter().zip(&audiodata) {
+        for (&d, &o) in decoded.iter().zip(original) {
+            // µ-law keeps about 13 bits, with coarser steps for louder samples
+            assert!((d as i32 - o as i32).abs() <= (o as i32).abs() / 16 + 8);
+        }
+    }
+}
+
+#[test]
+fn read_ima_adpcm() {
+    let data = [
+        0xE8, 0x03, 0, 0, // header: predictor 1000, step index 0
+        0x44, 0x57, 0xC8, 0x01, // 8 codes, low nibble first
+        0x18, 0xFC, 0, 0, // trailing partial block: predictor -1000
+    ];
+    let mut buf = wav_bytes(0x11, 1, 8000, 4, &data);
+    buf[32..34].copy_from_slice(&8u16.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(
+        wav.audiodata,
+        [vec![
+            1000, 1007, 1017, 1036, 1066, 1062, 1028, 1041, 1045, -1000
+        ]]
+    );
+
+    // blocks must have room for codes after the channel headers
+    buf[32..34].copy_from_slice(&4u16.to_le_bytes());
+    assert!(parse_bytes(&buf).is_err());
+}
+
+#[test]
+fn read_adpcm_fact_frames() {
+    let data = [0xE8, 0x03, 0, 0, 0x44, 0x57, 0xC8, 0x01, 0x18, 0xFC, 0, 0];
+    let mut buf = wav_bytes(0x11, 1, 8000, 4, &data);
+    buf[32..34].copy_from_slice(&8u16.to_le_bytes());
+    buf.extend_from_slice(b"fact");
+    buf.extend_from_slice(&4u32.to_le_bytes());
+    buf.extend_from_slice(&9u32.to_le_bytes());
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(
+        wav.audiodata,
+        [vec![1000, 1007, 1017, 1036, 1066, 1062, 1028, 1041, 1045]]
+    );
+
+    // the fact chunk may not claim more frames than the blocks hold
+    let len = buf.len();
+    buf[len - 4..].copy_from_slice(&20u32.to_le_bytes());
+    assert!(parse_bytes(&buf).is_err());
+}
+
+#[test]
+fn read_ms_adpcm() {
+    let data = [
+        0, // predictor index
+        16, 0, // delta
+        100, 0, // newest initial sample
+        50, 0, // oldest initial sample
+        0x12, 0xF0, // 4 codes, high nibble first
+    ];
+    let mut buf = wav_bytes(2, 1, 8000, 4, &data);
+    buf[32..34].copy_from_slice(&9u16.to_le_bytes());
+
+    let wav = parse_bytes(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 16);
+    assert_eq!(wav.audiodata, [vec![50, 100, 116, 148, 132, 132]]);
+
+    // files without a coefficient table get the 7 standard predictors
+    let mut unknown = buf.clone();
+    unknown[44] = 7;
+    assert!(parse_bytes(&unknown).is_err());
+}
+
+#[test]
+fn read_ms_adpcm_coefficients() {
+    // a fmt extension with a single predictor that halves the previous sample
+    let mut ext = 6u16.to_le_bytes().to_vec();
+    ext.extend_from_slice(&4u16.to_le_bytes()); // samples per block
+    ext.extend_from_slice(&1u16.to_le_bytes());
+    ext.extend_from_slice(&128i16.to_le_bytes());
+    ext.extend_from_slice(&0i16.to_le_bytes());
+
+    let data = [0, 16, 0, 100, 0, 50, 0, 0x00];
+    let mut buf = wav_bytes(2, 1, 8000, 4, &data);
+    buf[16..20].copy_from_slice(&(16 + ext.len() as u32).to_le_bytes());
+    buf[32..34].copy_from_slice(&8u16.to_le_bytes());
+    buf.splice(36..36, ext);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    assert_eq!(
+        parse_bytes(&buf).unwrap().audiodata,
+        [vec![50, 100, 50, 25]]
+    );
+}
+
+/// Turns a `wav_bytes` file into an RF64 one, with the sizes moved to a ds64 chunk.
+fn rf64(mut buf: Vec<u8>, magic: &[u8; 4]) -> Vec<u8> {
+    let data_size = u32::from_le_bytes(buf[40..44].try_into().unwrap()) as u64;
+    let riff_size = buf.len() as u64 + 36 - 8;
+
+    let mut ds64 = b"ds64".to_vec();
+    ds64.extend_from_slice(&28u32.to_le_bytes());
+    ds64.extend_from_slice(&riff_size.to_le_bytes());
+    ds64.extend_from_slice(&data_size.to_le_bytes());
+    ds64.extend_from_slice(&0u64.to_le_bytes()); // sample count
+    ds64.extend_from_slice(&0u32.to_le_bytes()); // size table entries
+
+    buf[0..4].copy_from_slice(magic);
+    buf[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    buf[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    buf.splice(12..12, ds64);
+    buf
+}
+
+#[test]
+fn read_rf64() {
+    let data = [1, 0, 2, 0, 3, 0, 4, 0];
+    for magic in [b"RF64", b"BW64"] {
+        let wav = parse_bytes(rf64(wav_bytes(1, 2, 48000, 16, &data), magic)).unwrap();
+        assert_eq!(wav.audiodata, [vec![1, 3], vec![2, 4]]);
+    }
+
+    // RF64 files need the ds64 chunk right after the header
+    let mut buf = rf64(wav_bytes(1, 2, 48000, 16, &data), b"RF64");
+    buf[12..16].copy_from_slice(b"JUNK");
+    assert!(parse_bytes(&buf).is_err());
+}
+
+#[test]
+fn write_small_files_as_riff() {
+    // RF64 promotion only kicks in near 4 GiB of audio data; smaller files stay plain RIFF
+    let buf = create_bytes_with_depth(vec![vec![0; 1000]; 2], 44100, BitDepth::B24).unwrap();
+    assert_eq!(&buf[0..4], b"RIFF");
+    assert!(find_chunk(&buf, b"ds64").is_none());
+    assert_eq!(find_chunk(&buf, b"data").unwrap().len(), 6000);
+}
+
+/// Turns a `wav_bytes` file into a Sony Wave64 one, with a 5-byte unknown chunk between
+/// the fmt and data chunks.
+fn w64(buf: &[u8]) -> Vec<u8> {
+    const SUFFIX: [u8; 12] = [
+        0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+    ];
+
+    fn chunk(out: &mut Vec<u8>, id: &[u8; 4], payload: &[u8]) {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&SUFFIX);
+        out.extend_from_slice(&(24 + payload.len() as u64).to_le_bytes());
+        out.extend_from_slice(payload);
+        out.resize(out.len().next_multiple_of(8), 0);
+    }
+
+    let data_size = u32::from_le_bytes(buf[40..44].try_into().unwrap()) as usize;
+
+    let mut out = vec![
+        0x72, 0x69, 0x66, 0x66, 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00,
+        0x00,
+    ];
+    out.extend_from_slice(&0u64.to_le_bytes());
+    out.extend_from_slice(b"wave");
+    out.extend_from_slice(&SUFFIX);
+    chunk(&mut out, b"fmt ", &buf[20..36]);
+    chunk(&mut out, b"junk", &[1, 2, 3, 4, 5]);
+    chunk(&mut out, b"data", &buf[44..44 + data_size]);
+
+    let size = out.len() as u64;
+    out[16..24].copy_from_slice(&size.to_le_bytes());
+    out
+}
+
+#[test]
+fn read_w64() {
+    let data = [0x00, 0x00, 0x80, 0xFF, 0xFF, 0x7F];
+    let wav = parse_bytes_i32(w64(&wav_bytes(1, 1, 44100, 24, &data))).unwrap();
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.audiodata, [vec![-8388608, 8388607]]);
+
+    // a data chunk running past the end of the file
+    let mut buf = w64(&wav_bytes(1, 1, 44100, 24, &data));
+    buf.truncate(buf.len() - 8);
+    assert!(parse_bytes_i32(&buf).is_err());
+}
+
+/// Turns a `wav_bytes` file into a big-endian RIFX one, reversing the bytes of every
+/// header field and of each `sample_size` byte sample.
+fn rifx(mut buf: Vec<u8>, sample_size: usize) -> Vec<u8> {
+    buf[0..4].copy_from_slice(b"RIFX");
+    for field in [
+        4..8,
+        16..20,
+        20..22,
+        22..24,
+        24..28,
+        28..32,
+        32..34,
+        34..36,
+        40..44,
+    ] {
+        buf[field].reverse();
+    }
+    let data_size = u32::from_be_bytes(buf[40..44].try_into().unwrap()) as usize;
+    for sample in buf[44..44 + data_size].chunks_exact_mut(sample_size) {
+        sample.reverse();
+    }
+    buf
+}
+
+#[test]
+fn read_rifx() {
+    let data = [0xFF, 0xFF, 0x7F, 0x01, 0x00, 0x00];
+    let wav = parse_bytes_i32(rifx(wav_bytes(1, 2, 48000, 24, &data), 3)).unwrap();
+    assert_eq!(wav.samplerate, 48000);
+    assert_eq!(wav.audiodata, [vec![8388607], vec![1]]);
+
+    let data = [0x01, 0x80, 0xFF, 0x7F];
+    let wav = parse_bytes(rifx(wav_bytes(1, 1, 8000, 16, &data), 2)).unwrap();
+    assert_eq!(wav.audiodata, [vec![-32767, 32767]]);
+
+    let data: Vec<u8> = [0.5f32, -2.0]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let wav = parse_bytes_f32(rifx(wav_bytes(3, 1, 8000, 32, &data), 4)).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5, -2.0]]);
+}
+
+#[test]
+fn read_encoded_data() {
+    // a GSM 6.10 file, which isn't decoded, with its 2-byte samples-per-block extension
+    let data = [0xA5; 65];
+    let mut buf = wav_bytes(0x31, 1, 8000, 0, &data);
+    buf[16..20].copy_from_slice(&20u32.to_le_bytes());
+    buf.splice(36..36, [2, 0, 0x40, 0x01]);
+    let riff_size = buf.len() as u32 - 8;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    assert!(parse_bytes(&buf).is_err());
+
+    let encoded = parse_encoded(&buf).unwrap();
+    assert_eq!(encoded.format_tag, 0x31);
+    assert_eq!(encoded.samplerate, 8000);
+    assert_eq!(encoded.fmt_extension, [0x40, 0x01]);
+    assert_eq!(encoded.data, data);
+
+    // extensible files report their subformat
+    let encoded = parse_encoded(extensible(wav_bytes(0, 1, 8000, 32, &[0; 4]), 32, 0, 3)).unwrap();
+    assert_eq!(encoded.format_tag, 3);
+    assert_eq!(encoded.fmt_extension.len(), 22);
+}
+
+#[test]
+fn writer_builder() {
+    let builder = WriterBuilder::new()
+        .channels(1)
+        .samplerate(48000)
+        .bit_depth(24);
+    let spec = builder.build().unwrap();
+    assert_eq!((spec.num_channels, spec.bits_per_sample), (1, 24));
+
+    let buf = builder.create_bytes_i32([vec![-8388608, 8388607]]).unwrap();
+    let wav = parse_bytes_i32(&buf).unwrap();
+    assert_eq!((wav.samplerate, wav.bits_per_sample), (48000, 24));
+    assert_eq!(wav.audiodata, [vec![-8388608, 8388607]]);
+
+    let builder = builder.float(true).bit_depth(64);
+    let buf = builder.create_bytes_f64([vec![0.25, -1.0]]).unwrap();
+    assert_eq!(parse_bytes_f64(&buf).unwrap().audiodata, [vec![0.25, -1.0]]);
+
+    assert!(matches!(
+        builder.create_bytes_i32([vec![0]]),
+        Err(OndaError::SampleTypeMismatch { .. })
+    ));
+    assert!(matches!(
+        builder.create_bytes_f32([vec![0.0], vec![0.0]]),
+        Err(OndaError::ChannelMismatch {
+            expected: 1,
+            found: 2
+        })
+    ));
+    assert!(matches!(
+        WriterBuilder::new().bit_depth(20).build(),
+        Err(OndaError::UnsupportedBitDepth(20))
+    ));
+    assert!(matches!(
+        WriterBuilder::new().channels(0).build(),
+        Err(OndaError::NoChannels)
+    ));
+}
+
+#[cfg(feature = "symphonia")]
+#[test]
+fn read_mp3_in_wav() {
+    // silent MPEG-1 layer III frames: 128 kbit/s, 44.1 kHz, mono, no side info set
+    let mut frame = vec![0; 417];
+    frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+    let data = frame.repeat(4);
+
+    let wav = parse_bytes(wav_bytes(0x55, 1, 44100, 0, &data)).unwrap();
+    assert_eq!(wav.num_channels, 1);
+    assert_eq!(wav.samplerate, 44100);
+    assert_eq!(wav.bits_per_sample, 16);
+    assert!(wav.num_frames() > 0);
+    assert!(wav.audiodata[0].iter().all(|&s| s == 0));
+
+    // codecs Symphonia doesn't know keep their error
+    assert!(matches!(
+        parse_bytes(wav_bytes(0x31, 1, 8000, 0, &[0; 65])),
+        Err(OndaError::UnsupportedCodec { tag: 0x31 })
+    ));
+}
+
+#[test]
+fn read_normalized_floats() {
+    let buf = create_bytes_with_depth(vec![vec![-8388608, 4194304]], 48000, BitDepth::B24).unwrap();
+    let wav: WavDataF32 = parse_bytes_f32(&buf).unwrap();
+    assert_eq!(wav.bits_per_sample, 32);
+    assert_eq!(wav.audiodata, [vec![-1.0, 0.5]]);
+
+    let buf = create_bytes_with_depth(vec![vec![-128, 64]], 8000, BitDepth::B8).unwrap();
+    assert_eq!(parse_bytes_f64(&buf).unwrap().audiodata, [vec![-1.0, 0.5]]);
+
+    // 64-bit floats are rounded
+    let buf = create_bytes_f64(vec![vec![0.1, -0.5]], 8000).unwrap();
+    assert_eq!(
+        parse_bytes_f32(&buf).unwrap().audiodata,
+        [vec![0.1f32, -0.5]]
+    );
+
+    let path = std::env::temp_dir().join(format!("onda-{}-read-f32.wav", std::process::id()));
+    write(vec![vec![16384, -32768]], 8000, &path).unwrap();
+    assert_eq!(read_f32(&path).unwrap().audiodata, [vec![0.5, -1.0]]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn write_quantized_floats() {
+    let audiodata = vec![vec![0.5f32, -1.0, 2.0, 0.25 / 32768.0]];
+    let builder = WriterBuilder::new().channels(1).bit_depth(16);
+
+    // samples are rounded and clipped
+    let wav = parse_bytes(builder.create_bytes_f32(&audiodata).unwrap()).unwrap();
+    assert_eq!(wav.audiodata, [vec![16384, -32768, 32767, 0]]);
+
+    let wav = parse_bytes(builder.dither(true).create_bytes_f32(&audiodata).unwrap()).unwrap();
+    for (dithered, rounded) in wav.audiodata[0].iter().zip([16384, -32768, 32767, 0]) {
+        assert!((dithered - rounded).abs() <= 1);
+    }
+
+    let builder = WriterBuilder::new().channels(1).bit_depth(24);
+    let wav = parse_bytes_i32(builder.create_bytes_f64([vec![0.5, -0.5]]).unwrap()).unwrap();
+    assert_eq!(wav.audiodata, [vec![4194304, -4194304]]);
+}
+
+#[test]
+fn write_borrowed_channels() {
+    let left = [1i16, 2, 3];
+    let right = [-1i16, -2, -3];
+    let owned = create_bytes(vec![left.to_vec(), right.to_vec()], 44100).unwrap();
+
+    let borrowed: [&[i16]; 2] = [&left, &right];
+    assert_eq!(create_bytes(borrowed, 44100).unwrap(), owned);
+    assert_eq!(create_bytes(&borrowed[..], 44100).unwrap(), owned);
+
+    let floats: [&[f32]; 1] = [&[0.5, -0.5]];
+    let wav = parse_bytes_f32(create_bytes_f32(floats, 48000).unwrap()).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5, -0.5]]);
+    let wide = WriterBuilder::new().bit_depth(24);
+    assert!(wide.create_bytes_i32([&[1 << 23][..]]).is_err());
+}
+
+#[test]
+fn write_into_buffer_and_writer() {
+    let audiodata = vec![vec![1i16, 2, 3], vec![-1, -2, -3]];
+    let expected = create_bytes(&audiodata, 44100).unwrap();
+
+    let mut buf = b"kept".to_vec();
+    create_bytes_into(&mut buf, &audiodata, 44100).unwrap();
+    assert_eq!(&buf[..4], b"kept");
+    assert_eq!(buf[4..], expected);
+    buf.clear();
+    create_bytes_into(&mut buf, &audiodata, 44100).unwrap();
+    assert_eq!(buf, expected);
+
+    let mut out = std::io::Cursor::new(vec![]);
+    write_to(&mut out, &audiodata, 44100).unwrap();
+    assert_eq!(out.into_inner(), expected);
+
+    let mismatched = vec![vec![1i16, 2], vec![1]];
+    assert!(write_to(std::io::sink(), mismatched, 44100).is_err());
+}
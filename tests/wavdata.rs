@@ -0,0 +1,169 @@
+use onda::*;
+
+/// Stereo 16-bit audio data of four frames.
+fn stereo() -> WavData {
+    parse_bytes(create_bytes(vec![vec![1, 2, 3, 4], vec![-1, -2, -3, -4]], 44100).unwrap()).unwrap()
+}
+
+#[test]
+fn frames() {
+    let wav = stereo();
+    let frames: Vec<[i16; 2]> = wav.frames().unwrap().collect();
+    assert_eq!(frames, [[1, -1], [2, -2], [3, -3], [4, -4]]);
+
+    let pairs: Vec<_> = wav.frames_stereo().unwrap().collect();
+    assert_eq!(pairs, [(1, -1), (2, -2), (3, -3), (4, -4)]);
+
+    assert!(wav.frames::<1>().is_err());
+}
+
+#[test]
+fn interleaved() {
+    let audiodata = vec![vec![1, 2, 3], vec![-1, -2, -3]];
+    let buf = create_bytes(&audiodata, 44100).unwrap();
+
+    let wav = parse_bytes_interleaved(&buf).unwrap();
+    assert_eq!(wav.num_channels, 2);
+    assert_eq!(wav.samples, [1, -1, 2, -2, 3, -3]);
+    assert_eq!(wav.deinterleaved(), audiodata);
+    assert_eq!(parse_bytes(&buf).unwrap().interleaved(), wav.samples);
+
+    let buf = create_bytes_f32(vec![vec![0.5, 0.25], vec![-0.5, -0.25]], 48000).unwrap();
+    let wav = parse_bytes_interleaved_f32(&buf).unwrap();
+    assert_eq!(wav.samples, [0.5, -0.5, 0.25, -0.25]);
+    assert!(parse_bytes_interleaved(&buf).is_err());
+}
+
+#[test]
+fn duration() {
+    let buf = create_bytes(vec![vec![0; 66150]; 2], 44100).unwrap();
+    let wav = parse_bytes(buf).unwrap();
+    assert_eq!(wav.num_frames(), 66150);
+    assert_eq!(wav.duration(), std::time::Duration::from_millis(1500));
+    assert_eq!(wav.len_seconds(), 1.5);
+    assert_eq!(stereo().num_frames(), 4);
+}
+
+#[test]
+fn convert() {
+    let buf = create_bytes_with_depth(vec![vec![-8388608, 4194304, 8388607]], 48000, BitDepth::B24)
+        .unwrap();
+    let wav: WavData<i32> = parse_bytes_as(&buf).unwrap();
+    assert!(parse_bytes_as::<i16>(&buf).is_err());
+
+    // widening keeps the bit depth, and narrowing rounds to the new one
+    let wide = wav.convert::<f64>();
+    assert_eq!(wide.bits_per_sample, 64);
+    assert_eq!(wide.audiodata, [vec![-1.0, 0.5, 8388607.0 / 8388608.0]]);
+    let narrow = wav.convert::<i16>();
+    assert_eq!(narrow.bits_per_sample, 16);
+    assert_eq!(narrow.audiodata, [vec![-32768, 16384, 32767]]);
+    assert_eq!(narrow.convert::<i32>().bits_per_sample, 16);
+
+    // dither stays within a step of the undithered samples
+    let dithered = wide.convert_dithered::<i16>();
+    for (d, n) in dithered.audiodata[0].iter().zip(&narrow.audiodata[0]) {
+        assert!((d - n).abs() <= 1);
+    }
+
+    let bytes = wide.convert::<f32>().create_bytes().unwrap();
+    let wav = parse_bytes_f32(bytes).unwrap();
+    assert_eq!(wav.audiodata[0][..2], [-1.0, 0.5]);
+}
+
+#[test]
+fn channel_accessors() {
+    let mut wav = stereo();
+    assert_eq!(wav.channel(1), Some(&[-1, -2, -3, -4][..]));
+    assert_eq!(wav.channel(2), None);
+    assert_eq!(wav.left(), Some(&[1, 2, 3, 4][..]));
+
+    wav.right_mut().unwrap()[0] = 10;
+    wav.channel_mut(0).unwrap().reverse();
+    assert_eq!(wav.right().unwrap()[0], 10);
+    assert_eq!(wav.left().unwrap()[0], 4);
+
+    for channel in wav.channels_mut() {
+        channel.fill(0);
+    }
+    assert_eq!(wav.channels().len(), 2);
+    assert!(wav.channels().all(|channel| channel == [0; 4]));
+}
+
+#[test]
+fn build_frames() {
+    let mut wav = WavData::<i32>::new(48000, 2);
+    assert_eq!(wav.bits_per_sample, 32);
+    wav.push_frame(&[1, -1]).unwrap();
+    wav.extend_from_frames([[2, -2], [3, -3]]).unwrap();
+    assert_eq!(wav.audiodata, [vec![1, 2, 3], vec![-1, -2, -3]]);
+
+    assert!(wav.push_frame(&[4]).is_err());
+    assert!(wav.extend_from_frames([[4, -4, 0]]).is_err());
+
+    wav.truncate_frames(2);
+    assert_eq!(wav.num_frames(), 2);
+    wav.audiodata[0].push(5);
+    assert!(wav.validate().is_err());
+    assert!(wav.push_frame(&[6, -6]).is_err());
+
+    let wav = WavData::from_interleaved(&[0.5f32, -0.5, 0.25, -0.25], 2, 8000).unwrap();
+    assert_eq!(wav.audiodata, [vec![0.5, 0.25], vec![-0.5, -0.25]]);
+    assert!(WavData::from_interleaved(&[0i16; 3], 2, 8000).is_err());
+    assert!(WavData::from_interleaved(&[0i16; 3], 0, 8000).is_err());
+}
+
+#[test]
+fn slice_and_split() {
+    use std::time::Duration;
+
+    let mut wav = stereo();
+    wav.samplerate = 4;
+    wav.metadata.title = Some("Take 1".to_string());
+
+    let slice = wav.slice(Duration::from_millis(250), Duration::from_millis(800));
+    assert_eq!(slice.audiodata, [vec![2, 3], vec![-2, -3]]);
+    assert_eq!(slice.metadata.title.as_deref(), Some("Take 1"));
+    // clamped to the audio data, and empty for an end before the start
+    assert_eq!(
+        wav.slice(Duration::from_millis(500), Duration::from_secs(5))
+            .audiodata,
+        [vec![3, 4], vec![-3, -4]]
+    );
+    assert_eq!(
+        wav.slice(Duration::from_secs(1), Duration::ZERO)
+            .num_frames(),
+        0
+    );
+
+    let (head, tail) = wav.split_at_frame(1);
+    assert_eq!(head.audiodata, [vec![1], vec![-1]]);
+    assert_eq!(tail.audiodata, [vec![2, 3, 4], vec![-2, -3, -4]]);
+    let (head, tail) = wav.split_at_frame(10);
+    assert_eq!(head.audiodata, wav.audiodata);
+    assert_eq!(tail.num_frames(), 0);
+}
+
+#[test]
+fn frame_iteration_and_formatting() {
+    let mut wav = stereo();
+
+    let frames: Vec<Vec<i16>> = (&wav).into_iter().map(|frame| frame.to_vec()).collect();
+    assert_eq!(frames, [[1, -1], [2, -2], [3, -3], [4, -4]]);
+    let frame = (&wav).into_iter().nth(2).unwrap();
+    assert_eq!((frame.len(), frame[1], frame.get(2)), (2, -3, None));
+    assert_eq!((&wav).into_iter().len(), 4);
+
+    assert_eq!(wav[1], [-1, -2, -3, -4]);
+    wav[0][0] = 10;
+    assert_eq!(wav.audiodata[0][0], 10);
+
+    assert_eq!(wav.to_string(), "2 channels, 44100 Hz, 16-bit int, 0.000 s");
+    let mono = WavData::<f32>::new(48000, 1);
+    assert_eq!(
+        mono.to_string(),
+        "1 channel, 48000 Hz, 32-bit float, 0.000 s"
+    );
+    let debug = format!("{wav:?}");
+    assert!(debug.contains("num_frames: 4") && !debug.contains("audiodata"));
+}